@@ -0,0 +1,60 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use nes_core::{Mirroring, Nes, Rom};
+
+const INSTRUCTIONS: u64 = 100_000;
+
+// A loop mixing loads, stores, arithmetic, shifts, branches and subroutine calls:
+//
+// 8000  LDX #$00
+// 8002  LDA $0200,X
+// 8005  ADC #$01
+// 8007  STA $0200,X
+// 800A  JSR $8020
+// 800D  INX
+// 800E  BNE $8002
+// 8010  JMP $8000
+// 8020  ASL A
+// 8021  ROR $10
+// 8023  CMP #$40
+// 8025  RTS
+fn bench_rom() -> Rom {
+    let mut prg_rom = vec![0xEA; 0x4000];
+    prg_rom[..0x13].copy_from_slice(&[
+        0xA2, 0x00, 0xBD, 0x00, 0x02, 0x69, 0x01, 0x9D, 0x00, 0x02, 0x20, 0x20, 0x80, 0xE8, 0xD0, 0xF2, 0x4C, 0x00,
+        0x80,
+    ]);
+    prg_rom[0x20..0x26].copy_from_slice(&[0x0A, 0x66, 0x10, 0xC9, 0x40, 0x60]);
+    prg_rom[0x3FFC..0x3FFE].copy_from_slice(&[0x00, 0x80]);
+
+    Rom {
+        prg_rom,
+        chr_rom: vec![0; 0x2000],
+        mapper: 0,
+        screen_mirroring: Mirroring::HORIZONTAL,
+        battery: false,
+    }
+}
+
+fn instructions(c: &mut Criterion) {
+    let mut nes = Nes::from_rom(bench_rom()).unwrap();
+
+    let mut group = c.benchmark_group("cpu");
+    group.throughput(Throughput::Elements(INSTRUCTIONS));
+    group.bench_function("instructions", |b| {
+        b.iter(|| {
+            for _ in 0..INSTRUCTIONS {
+                black_box(nes.step());
+            }
+        })
+    });
+    group.finish();
+}
+
+fn frames(c: &mut Criterion) {
+    let mut nes = Nes::from_rom(bench_rom()).unwrap();
+
+    c.bench_function("frame", |b| b.iter(|| black_box(nes.step_frame())));
+}
+
+criterion_group!(benches, instructions, frames);
+criterion_main!(benches);
@@ -0,0 +1,323 @@
+use super::{dmc::Dmc, noise::Noise, pulse::Pulse, triangle::Triangle};
+use crate::savestate::{Snapshot, StateReader, StateWriter};
+
+const CPU_CLOCK: f64 = 1_789_773.0;
+pub const SAMPLE_RATE: u32 = 44_100;
+
+// Frame counter steps in CPU cycles
+const QUARTER_FRAME_1: usize = 7457;
+const HALF_FRAME_1: usize = 14913;
+const QUARTER_FRAME_3: usize = 22371;
+const FOUR_STEP_END: usize = 29829;
+const FIVE_STEP_END: usize = 37281;
+
+// Status (0x4015)
+// 7  bit  0
+// ---- ----
+// IF-D NT21
+// |||| ||||
+// |||| |||+- Pulse 1 length counter > 0 / enable
+// |||| ||+-- Pulse 2 length counter > 0 / enable
+// |||| |+--- Triangle length counter > 0 / enable
+// |||| +---- Noise length counter > 0 / enable
+// |||+------ DMC bytes remaining > 0 / enable
+// ||+------- Open bus
+// |+-------- Frame interrupt (read only)
+// +--------- DMC interrupt (read only)
+pub struct Apu {
+    pulse1: Pulse,
+    pulse2: Pulse,
+    triangle: Triangle,
+    noise: Noise,
+    dmc: Dmc,
+
+    // Frame counter (0x4017)
+    five_step_mode: bool,
+    irq_inhibit: bool,
+    frame_interrupt: bool,
+    frame_cycle: usize,
+
+    cycles: usize,
+    sample_clock: f64,
+    filter_input: f32,
+    filter_output: f32,
+    samples: Vec<f32>,
+}
+
+impl Apu {
+    pub fn new() -> Self {
+        Apu {
+            pulse1: Pulse::new(true),
+            pulse2: Pulse::new(false),
+            triangle: Triangle::new(),
+            noise: Noise::new(),
+            dmc: Dmc::new(),
+            five_step_mode: false,
+            irq_inhibit: false,
+            frame_interrupt: false,
+            frame_cycle: 0,
+            cycles: 0,
+            sample_clock: 0.0,
+            filter_input: 0.0,
+            filter_output: 0.0,
+            samples: Vec::new(),
+        }
+    }
+
+    pub fn write_register(&mut self, addr: u16, data: u8) {
+        match addr {
+            0x4000..=0x4003 => self.pulse1.write_register(addr - 0x4000, data),
+            0x4004..=0x4007 => self.pulse2.write_register(addr - 0x4004, data),
+            0x4008..=0x400B => self.triangle.write_register(addr - 0x4008, data),
+            0x400C..=0x400F => self.noise.write_register(addr - 0x400C, data),
+            0x4010..=0x4013 => self.dmc.write_register(addr - 0x4010, data),
+            0x4015 => {
+                self.pulse1.length_counter.set_enabled(data & 0b0000_0001 != 0);
+                self.pulse2.length_counter.set_enabled(data & 0b0000_0010 != 0);
+                self.triangle.length_counter.set_enabled(data & 0b0000_0100 != 0);
+                self.noise.length_counter.set_enabled(data & 0b0000_1000 != 0);
+                self.dmc.set_enabled(data & 0b0001_0000 != 0);
+            }
+            // MI-- ----
+            0x4017 => {
+                self.five_step_mode = data & 0b1000_0000 != 0;
+                self.irq_inhibit = data & 0b0100_0000 != 0;
+                if self.irq_inhibit {
+                    self.frame_interrupt = false;
+                }
+
+                self.frame_cycle = 0;
+                if self.five_step_mode {
+                    self.clock_quarter_frame();
+                    self.clock_half_frame();
+                }
+            }
+            _ => println!("Ignoring APU write-access at 0x{addr:X}"),
+        }
+    }
+
+    pub fn read_status(&mut self) -> u8 {
+        let mut status = 0;
+        status |= self.pulse1.length_counter.is_active() as u8;
+        status |= (self.pulse2.length_counter.is_active() as u8) << 1;
+        status |= (self.triangle.length_counter.is_active() as u8) << 2;
+        status |= (self.noise.length_counter.is_active() as u8) << 3;
+        status |= ((self.dmc.bytes_remaining > 0) as u8) << 4;
+        status |= (self.frame_interrupt as u8) << 6;
+        status |= (self.dmc.interrupt as u8) << 7;
+
+        self.frame_interrupt = false;
+        status
+    }
+
+    // Advances the APU by one CPU cycle
+    pub fn tick(&mut self) {
+        self.cycles += 1;
+
+        self.triangle.clock_timer();
+        self.noise.clock_timer();
+        self.dmc.clock_timer();
+        if self.cycles & 1 == 0 {
+            self.pulse1.clock_timer();
+            self.pulse2.clock_timer();
+        }
+
+        self.clock_frame_counter();
+
+        self.sample_clock += SAMPLE_RATE as f64;
+        if self.sample_clock >= CPU_CLOCK {
+            self.sample_clock -= CPU_CLOCK;
+            let sample = self.high_pass(self.mix());
+            self.samples.push(sample);
+        }
+    }
+
+    fn clock_frame_counter(&mut self) {
+        self.frame_cycle += 1;
+
+        match (self.frame_cycle, self.five_step_mode) {
+            (QUARTER_FRAME_1, _) | (QUARTER_FRAME_3, _) => self.clock_quarter_frame(),
+            (HALF_FRAME_1, _) => {
+                self.clock_quarter_frame();
+                self.clock_half_frame();
+            }
+            (FOUR_STEP_END, false) | (FIVE_STEP_END, true) => {
+                self.clock_quarter_frame();
+                self.clock_half_frame();
+                if !self.five_step_mode && !self.irq_inhibit {
+                    self.frame_interrupt = true;
+                }
+                self.frame_cycle = 0;
+            }
+            _ => {}
+        }
+    }
+
+    fn clock_quarter_frame(&mut self) {
+        self.pulse1.envelope.clock();
+        self.pulse2.envelope.clock();
+        self.noise.envelope.clock();
+        self.triangle.clock_linear_counter();
+    }
+
+    fn clock_half_frame(&mut self) {
+        self.pulse1.length_counter.clock();
+        self.pulse2.length_counter.clock();
+        self.triangle.length_counter.clock();
+        self.noise.length_counter.clock();
+        self.pulse1.clock_sweep();
+        self.pulse2.clock_sweep();
+    }
+
+    // https://www.nesdev.org/wiki/APU_Mixer
+    fn mix(&self) -> f32 {
+        let pulse = (self.pulse1.output() + self.pulse2.output()) as f32;
+        let pulse_out = if pulse == 0.0 { 0.0 } else { 95.88 / (8128.0 / pulse + 100.0) };
+
+        let tnd = self.triangle.output() as f32 / 8227.0
+            + self.noise.output() as f32 / 12241.0
+            + self.dmc.output() as f32 / 22638.0;
+        let tnd_out = if tnd == 0.0 { 0.0 } else { 159.79 / (1.0 / tnd + 100.0) };
+
+        pulse_out + tnd_out
+    }
+
+    // removes the DC offset of the mixer output so it is centered around 0
+    fn high_pass(&mut self, input: f32) -> f32 {
+        self.filter_output = 0.996 * (self.filter_output + input - self.filter_input);
+        self.filter_input = input;
+        self.filter_output
+    }
+
+    // Frame counter and DMC interrupts, cleared by reading the status or through their own registers
+    pub fn irq(&self) -> bool {
+        self.frame_interrupt || self.dmc.interrupt
+    }
+
+    pub fn dmc_fetch_addr(&self) -> Option<u16> {
+        self.dmc.fetch_addr()
+    }
+
+    pub fn dmc_fill_sample_buffer(&mut self, data: u8) {
+        self.dmc.fill_sample_buffer(data);
+    }
+
+    pub fn pending_samples(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn take_samples(&mut self) -> Vec<f32> {
+        std::mem::take(&mut self.samples)
+    }
+}
+
+impl Default for Apu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Snapshot for Apu {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.pulse1.save_state(w);
+        self.pulse2.save_state(w);
+        self.triangle.save_state(w);
+        self.noise.save_state(w);
+        self.dmc.save_state(w);
+        w.write_bool(self.five_step_mode);
+        w.write_bool(self.irq_inhibit);
+        w.write_bool(self.frame_interrupt);
+        w.write_u64(self.frame_cycle as u64);
+        w.write_u64(self.cycles as u64);
+        w.write_f64(self.sample_clock);
+        w.write_f32(self.filter_input);
+        w.write_f32(self.filter_output);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        self.pulse1.load_state(r)?;
+        self.pulse2.load_state(r)?;
+        self.triangle.load_state(r)?;
+        self.noise.load_state(r)?;
+        self.dmc.load_state(r)?;
+        self.five_step_mode = r.read_bool()?;
+        self.irq_inhibit = r.read_bool()?;
+        self.frame_interrupt = r.read_bool()?;
+        self.frame_cycle = r.read_u64()? as usize;
+        self.cycles = r.read_u64()? as usize;
+        self.sample_clock = r.read_f64()?;
+        self.filter_input = r.read_f32()?;
+        self.filter_output = r.read_f32()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_status_reports_length_counters() {
+        let mut apu = Apu::new();
+        apu.write_register(0x4015, 0b0000_0101);
+        apu.write_register(0x4003, 0b0000_1000);
+        apu.write_register(0x4007, 0b0000_1000); // pulse 2 is disabled, load is ignored
+        apu.write_register(0x400B, 0b0000_1000);
+
+        assert_eq!(apu.read_status(), 0b0000_0101);
+
+        apu.write_register(0x4015, 0b0000_0001);
+        assert_eq!(apu.read_status(), 0b0000_0001);
+    }
+
+    #[test]
+    fn test_frame_interrupt_in_four_step_mode() {
+        let mut apu = Apu::new();
+        for _ in 0..FOUR_STEP_END {
+            apu.tick();
+        }
+
+        assert_eq!(apu.read_status() & 0b0100_0000, 0b0100_0000);
+        assert_eq!(apu.read_status() & 0b0100_0000, 0);
+    }
+
+    #[test]
+    fn test_irq_inhibit_and_five_step_mode_have_no_frame_interrupt() {
+        let mut apu = Apu::new();
+        apu.write_register(0x4017, 0b1000_0000);
+        for _ in 0..2 * FIVE_STEP_END {
+            apu.tick();
+        }
+        assert_eq!(apu.read_status() & 0b0100_0000, 0);
+
+        apu.write_register(0x4017, 0b0100_0000);
+        for _ in 0..2 * FOUR_STEP_END {
+            apu.tick();
+        }
+        assert_eq!(apu.read_status() & 0b0100_0000, 0);
+    }
+
+    #[test]
+    fn test_length_counter_halts_after_half_frames() {
+        let mut apu = Apu::new();
+        apu.write_register(0x4015, 0b0000_0001);
+        apu.write_register(0x4003, 0b0001_1000); // length index 3 -> 2 half frames
+
+        for _ in 0..FOUR_STEP_END {
+            apu.tick();
+        }
+        assert_eq!(apu.read_status() & 1, 0);
+    }
+
+    #[test]
+    fn test_generates_samples_at_sample_rate() {
+        let mut apu = Apu::new();
+        for _ in 0..(CPU_CLOCK as usize / 60) {
+            apu.tick();
+        }
+
+        let samples = apu.take_samples().len() as u32;
+        assert!(samples.abs_diff(SAMPLE_RATE / 60) <= 1);
+        assert_eq!(apu.pending_samples(), 0);
+    }
+}
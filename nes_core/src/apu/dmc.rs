@@ -0,0 +1,214 @@
+use crate::savestate::{Snapshot, StateReader, StateWriter};
+
+// NTSC rates in CPU cycles
+const RATE_TABLE: [u16; 16] = [428, 380, 340, 320, 286, 254, 226, 214, 190, 160, 142, 128, 106, 84, 72, 54];
+
+// Delta modulation channel, plays 1 bit delta encoded samples read straight from PRG ROM
+pub struct Dmc {
+    pub irq_enabled: bool,
+    pub interrupt: bool,
+    looping: bool,
+    timer_period: u16,
+    timer: u16,
+
+    // memory reader
+    sample_addr: u16,
+    sample_length: u16,
+    current_addr: u16,
+    pub bytes_remaining: u16,
+    sample_buffer: Option<u8>,
+
+    // output unit
+    shift_register: u8,
+    bits_remaining: u8,
+    silence: bool,
+    output_level: u8,
+}
+
+impl Dmc {
+    pub fn new() -> Self {
+        Dmc {
+            irq_enabled: false,
+            interrupt: false,
+            looping: false,
+            timer_period: RATE_TABLE[0],
+            timer: 0,
+            sample_addr: 0xC000,
+            sample_length: 1,
+            current_addr: 0xC000,
+            bytes_remaining: 0,
+            sample_buffer: None,
+            shift_register: 0,
+            bits_remaining: 8,
+            silence: true,
+            output_level: 0,
+        }
+    }
+
+    pub fn write_register(&mut self, register: u16, data: u8) {
+        match register {
+            // IL-- RRRR
+            0 => {
+                self.irq_enabled = data & 0b1000_0000 != 0;
+                self.looping = data & 0b0100_0000 != 0;
+                self.timer_period = RATE_TABLE[(data & 0b1111) as usize];
+                if !self.irq_enabled {
+                    self.interrupt = false;
+                }
+            }
+            // -DDD DDDD
+            1 => self.output_level = data & 0b0111_1111,
+            // $C000 + A * 64
+            2 => self.sample_addr = 0xC000 | ((data as u16) << 6),
+            // L * 16 + 1 bytes
+            _ => self.sample_length = ((data as u16) << 4) + 1,
+        }
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.interrupt = false;
+        if !enabled {
+            self.bytes_remaining = 0;
+        } else if self.bytes_remaining == 0 {
+            self.restart();
+        }
+    }
+
+    fn restart(&mut self) {
+        self.current_addr = self.sample_addr;
+        self.bytes_remaining = self.sample_length;
+    }
+
+    // address the memory reader wants to fetch, the bus answers with `fill_sample_buffer`
+    pub fn fetch_addr(&self) -> Option<u16> {
+        (self.sample_buffer.is_none() && self.bytes_remaining > 0).then_some(self.current_addr)
+    }
+
+    pub fn fill_sample_buffer(&mut self, data: u8) {
+        self.sample_buffer = Some(data);
+        self.current_addr = if self.current_addr == 0xFFFF { 0x8000 } else { self.current_addr + 1 };
+        self.bytes_remaining -= 1;
+
+        if self.bytes_remaining == 0 {
+            if self.looping {
+                self.restart();
+            } else if self.irq_enabled {
+                self.interrupt = true;
+            }
+        }
+    }
+
+    // clocked every CPU cycle
+    pub fn clock_timer(&mut self) {
+        if self.timer > 0 {
+            self.timer -= 1;
+            return;
+        }
+        self.timer = self.timer_period - 1;
+
+        if !self.silence {
+            if self.shift_register & 1 == 1 {
+                if self.output_level <= 125 {
+                    self.output_level += 2;
+                }
+            } else if self.output_level >= 2 {
+                self.output_level -= 2;
+            }
+        }
+        self.shift_register >>= 1;
+
+        self.bits_remaining -= 1;
+        if self.bits_remaining == 0 {
+            self.bits_remaining = 8;
+            match self.sample_buffer.take() {
+                Some(sample) => {
+                    self.silence = false;
+                    self.shift_register = sample;
+                }
+                None => self.silence = true,
+            }
+        }
+    }
+
+    pub fn output(&self) -> u8 {
+        self.output_level
+    }
+}
+
+impl Snapshot for Dmc {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bool(self.irq_enabled);
+        w.write_bool(self.interrupt);
+        w.write_bool(self.looping);
+        w.write_u16(self.timer_period);
+        w.write_u16(self.timer);
+        w.write_u16(self.sample_addr);
+        w.write_u16(self.sample_length);
+        w.write_u16(self.current_addr);
+        w.write_u16(self.bytes_remaining);
+        w.write_bool(self.sample_buffer.is_some());
+        w.write_u8(self.sample_buffer.unwrap_or(0));
+        w.write_u8(self.shift_register);
+        w.write_u8(self.bits_remaining);
+        w.write_bool(self.silence);
+        w.write_u8(self.output_level);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        self.irq_enabled = r.read_bool()?;
+        self.interrupt = r.read_bool()?;
+        self.looping = r.read_bool()?;
+        self.timer_period = r.read_u16()?;
+        self.timer = r.read_u16()?;
+        self.sample_addr = r.read_u16()?;
+        self.sample_length = r.read_u16()?;
+        self.current_addr = r.read_u16()?;
+        self.bytes_remaining = r.read_u16()?;
+        let has_sample_buffer = r.read_bool()?;
+        let sample_buffer = r.read_u8()?;
+        self.sample_buffer = has_sample_buffer.then_some(sample_buffer);
+        self.shift_register = r.read_u8()?;
+        self.bits_remaining = r.read_u8()?;
+        self.silence = r.read_bool()?;
+        self.output_level = r.read_u8()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sample_fetch_and_irq() {
+        let mut dmc = Dmc::new();
+        dmc.write_register(0, 0b1000_0000);
+        dmc.write_register(2, 0x01);
+        dmc.write_register(3, 0x00);
+        dmc.set_enabled(true);
+
+        assert_eq!(dmc.fetch_addr(), Some(0xC040));
+        dmc.fill_sample_buffer(0xFF);
+
+        assert_eq!(dmc.fetch_addr(), None);
+        assert!(dmc.interrupt);
+    }
+
+    #[test]
+    fn test_output_level_follows_deltas() {
+        let mut dmc = Dmc::new();
+        dmc.write_register(1, 64);
+        dmc.write_register(3, 0x00);
+        dmc.set_enabled(true);
+        dmc.fill_sample_buffer(0b0000_0011);
+
+        // the first output cycle is silent and loads the buffer into the shift register
+        for _ in 0..8 * RATE_TABLE[0] {
+            dmc.clock_timer();
+        }
+        for _ in 0..2 * RATE_TABLE[0] {
+            dmc.clock_timer();
+        }
+        assert_eq!(dmc.output(), 68);
+    }
+}
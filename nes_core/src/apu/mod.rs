@@ -0,0 +1,9 @@
+mod apu;
+mod envelope;
+mod length_counter;
+mod pulse;
+mod triangle;
+mod noise;
+mod dmc;
+
+pub use apu::{Apu, SAMPLE_RATE};
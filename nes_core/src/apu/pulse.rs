@@ -0,0 +1,218 @@
+use super::{envelope::Envelope, length_counter::LengthCounter};
+use crate::savestate::{Snapshot, StateReader, StateWriter};
+
+const DUTY_TABLE: [[u8; 8]; 4] = [
+    [0, 1, 0, 0, 0, 0, 0, 0], // 12.5%
+    [0, 1, 1, 0, 0, 0, 0, 0], // 25%
+    [0, 1, 1, 1, 1, 0, 0, 0], // 50%
+    [1, 0, 0, 1, 1, 1, 1, 1], // 25% negated
+];
+
+// EPPP NSSS
+// |||| ||||
+// |||| |+++- Shift count (number of bits)
+// |||| +---- Negate flag
+// |+++------ The divider's period is P + 1 half-frames
+// +--------- Enabled flag
+struct Sweep {
+    enabled: bool,
+    period: u8,
+    negate: bool,
+    shift: u8,
+    reload: bool,
+    divider: u8,
+    // pulse 1 adds the ones' complement when negating, pulse 2 the two's complement
+    ones_complement: bool,
+}
+
+impl Sweep {
+    fn new(ones_complement: bool) -> Self {
+        Sweep {
+            enabled: false,
+            period: 0,
+            negate: false,
+            shift: 0,
+            reload: false,
+            divider: 0,
+            ones_complement,
+        }
+    }
+
+    fn write(&mut self, data: u8) {
+        self.enabled = data & 0b1000_0000 != 0;
+        self.period = (data >> 4) & 0b111;
+        self.negate = data & 0b0000_1000 != 0;
+        self.shift = data & 0b111;
+        self.reload = true;
+    }
+
+    fn target_period(&self, timer_period: u16) -> u16 {
+        let change = timer_period >> self.shift;
+        if self.negate {
+            let change = if self.ones_complement { change + 1 } else { change };
+            timer_period.saturating_sub(change)
+        } else {
+            timer_period + change
+        }
+    }
+
+    fn is_muting(&self, timer_period: u16) -> bool {
+        timer_period < 8 || self.target_period(timer_period) > 0x7FF
+    }
+}
+
+pub struct Pulse {
+    pub envelope: Envelope,
+    pub length_counter: LengthCounter,
+    sweep: Sweep,
+    duty: u8,
+    sequence: usize,
+    timer_period: u16,
+    timer: u16,
+}
+
+impl Pulse {
+    pub fn new(ones_complement: bool) -> Self {
+        Pulse {
+            envelope: Envelope::new(),
+            length_counter: LengthCounter::new(),
+            sweep: Sweep::new(ones_complement),
+            duty: 0,
+            sequence: 0,
+            timer_period: 0,
+            timer: 0,
+        }
+    }
+
+    pub fn write_register(&mut self, register: u16, data: u8) {
+        match register {
+            // DDLC VVVV
+            0 => {
+                self.duty = data >> 6;
+                self.length_counter.halt = data & 0b0010_0000 != 0;
+                self.envelope.write(data);
+            }
+            1 => self.sweep.write(data),
+            2 => self.timer_period = (self.timer_period & 0xFF00) | data as u16,
+            // LLLL LTTT
+            _ => {
+                self.timer_period = (self.timer_period & 0x00FF) | ((data as u16 & 0b111) << 8);
+                self.length_counter.load(data >> 3);
+                self.sequence = 0;
+                self.envelope.start = true;
+            }
+        }
+    }
+
+    // clocked every other CPU cycle
+    pub fn clock_timer(&mut self) {
+        if self.timer == 0 {
+            self.timer = self.timer_period;
+            self.sequence = (self.sequence + 1) % 8;
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    pub fn clock_sweep(&mut self) {
+        if self.sweep.divider == 0 && self.sweep.enabled && self.sweep.shift > 0 && !self.sweep.is_muting(self.timer_period)
+        {
+            self.timer_period = self.sweep.target_period(self.timer_period);
+        }
+
+        if self.sweep.divider == 0 || self.sweep.reload {
+            self.sweep.divider = self.sweep.period;
+            self.sweep.reload = false;
+        } else {
+            self.sweep.divider -= 1;
+        }
+    }
+
+    pub fn output(&self) -> u8 {
+        if !self.length_counter.is_active()
+            || self.sweep.is_muting(self.timer_period)
+            || DUTY_TABLE[self.duty as usize][self.sequence] == 0
+        {
+            return 0;
+        }
+        self.envelope.output()
+    }
+}
+
+impl Snapshot for Sweep {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bool(self.enabled);
+        w.write_u8(self.period);
+        w.write_bool(self.negate);
+        w.write_u8(self.shift);
+        w.write_bool(self.reload);
+        w.write_u8(self.divider);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        self.enabled = r.read_bool()?;
+        self.period = r.read_u8()?;
+        self.negate = r.read_bool()?;
+        self.shift = r.read_u8()?;
+        self.reload = r.read_bool()?;
+        self.divider = r.read_u8()?;
+        Ok(())
+    }
+}
+
+impl Snapshot for Pulse {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.envelope.save_state(w);
+        self.length_counter.save_state(w);
+        self.sweep.save_state(w);
+        w.write_u8(self.duty);
+        w.write_u64(self.sequence as u64);
+        w.write_u16(self.timer_period);
+        w.write_u16(self.timer);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        self.envelope.load_state(r)?;
+        self.length_counter.load_state(r)?;
+        self.sweep.load_state(r)?;
+        self.duty = r.read_u8()?;
+        self.sequence = r.read_u64()? as usize;
+        self.timer_period = r.read_u16()?;
+        self.timer = r.read_u16()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sweep_negate_differs_between_channels() {
+        let mut pulse1 = Pulse::new(true);
+        let mut pulse2 = Pulse::new(false);
+        for pulse in [&mut pulse1, &mut pulse2] {
+            pulse.write_register(2, 0x00);
+            pulse.write_register(3, 0x01); // period 0x100
+            pulse.write_register(1, 0b1000_1001); // enabled, period 0, negate, shift 1
+            pulse.clock_sweep();
+        }
+
+        assert_eq!(pulse1.timer_period, 0x100 - 0x80 - 1);
+        assert_eq!(pulse2.timer_period, 0x100 - 0x80);
+    }
+
+    #[test]
+    fn test_low_period_mutes_channel() {
+        let mut pulse = Pulse::new(false);
+        pulse.length_counter.set_enabled(true);
+        pulse.write_register(0, 0b0011_1111); // constant volume 15
+        pulse.write_register(2, 0x05);
+        pulse.write_register(3, 0x00);
+
+        for _ in 0..16 {
+            pulse.clock_timer();
+            assert_eq!(pulse.output(), 0);
+        }
+    }
+}
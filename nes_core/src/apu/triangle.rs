@@ -0,0 +1,107 @@
+use super::length_counter::LengthCounter;
+use crate::savestate::{Snapshot, StateReader, StateWriter};
+
+#[rustfmt::skip]
+const SEQUENCE: [u8; 32] = [
+    15, 14, 13, 12, 11, 10,  9,  8,  7,  6,  5,  4,  3,  2,  1,  0,
+     0,  1,  2,  3,  4,  5,  6,  7,  8,  9, 10, 11, 12, 13, 14, 15,
+];
+
+pub struct Triangle {
+    pub length_counter: LengthCounter,
+    control: bool,
+    linear_reload_value: u8,
+    linear_counter: u8,
+    linear_reload: bool,
+    sequence: usize,
+    timer_period: u16,
+    timer: u16,
+}
+
+impl Triangle {
+    pub fn new() -> Self {
+        Triangle {
+            length_counter: LengthCounter::new(),
+            control: false,
+            linear_reload_value: 0,
+            linear_counter: 0,
+            linear_reload: false,
+            sequence: 0,
+            timer_period: 0,
+            timer: 0,
+        }
+    }
+
+    pub fn write_register(&mut self, register: u16, data: u8) {
+        match register {
+            // CRRR RRRR
+            0 => {
+                self.control = data & 0b1000_0000 != 0;
+                self.length_counter.halt = self.control;
+                self.linear_reload_value = data & 0b0111_1111;
+            }
+            1 => { /* unused */ }
+            2 => self.timer_period = (self.timer_period & 0xFF00) | data as u16,
+            // LLLL LTTT
+            _ => {
+                self.timer_period = (self.timer_period & 0x00FF) | ((data as u16 & 0b111) << 8);
+                self.length_counter.load(data >> 3);
+                self.linear_reload = true;
+            }
+        }
+    }
+
+    // clocked every CPU cycle
+    pub fn clock_timer(&mut self) {
+        if self.timer == 0 {
+            self.timer = self.timer_period;
+            // periods below 2 produce ultrasonic frequencies that only add pops to the output
+            if self.linear_counter > 0 && self.length_counter.is_active() && self.timer_period >= 2 {
+                self.sequence = (self.sequence + 1) % 32;
+            }
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    pub fn clock_linear_counter(&mut self) {
+        if self.linear_reload {
+            self.linear_counter = self.linear_reload_value;
+        } else if self.linear_counter > 0 {
+            self.linear_counter -= 1;
+        }
+
+        if !self.control {
+            self.linear_reload = false;
+        }
+    }
+
+    pub fn output(&self) -> u8 {
+        SEQUENCE[self.sequence]
+    }
+}
+
+impl Snapshot for Triangle {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.length_counter.save_state(w);
+        w.write_bool(self.control);
+        w.write_u8(self.linear_reload_value);
+        w.write_u8(self.linear_counter);
+        w.write_bool(self.linear_reload);
+        w.write_u64(self.sequence as u64);
+        w.write_u16(self.timer_period);
+        w.write_u16(self.timer);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        self.length_counter.load_state(r)?;
+        self.control = r.read_bool()?;
+        self.linear_reload_value = r.read_u8()?;
+        self.linear_counter = r.read_u8()?;
+        self.linear_reload = r.read_bool()?;
+        self.sequence = r.read_u64()? as usize;
+        self.timer_period = r.read_u16()?;
+        self.timer = r.read_u16()?;
+        Ok(())
+    }
+}
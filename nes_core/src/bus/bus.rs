@@ -0,0 +1,310 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    apu::Apu,
+    controller::Joypad,
+    cpu::{CpuBus, FaultKind, Mem},
+    debugger::{Access, Space, Watchpoints},
+    mapper::{new_mapper, Mapper},
+    ppu::{NesPPU, PPU},
+    rom::Rom,
+    savestate::{Snapshot, StateReader, StateWriter},
+};
+
+const RAM: u16 = 0x0000;
+const RAM_MIRRORS_END: u16 = 0x1FFF;
+// const PPU_REGISTERS: u16 = 0x2000;
+const PPU_REGISTERS_MIRRORS_END: u16 = 0x3FFF;
+const PRG_RAM: u16 = 0x6000;
+const PRG_RAM_END: u16 = 0x7FFF;
+
+pub struct Bus<'call> {
+    cpu_vram: [u8; 2048],
+    prg_ram: [u8; 0x2000],
+    mapper: Rc<RefCell<dyn Mapper>>,
+    ppu: NesPPU,
+    apu: Apu,
+    pub cycles: usize,
+    pub frames: usize,
+    gameloop_callback: Box<dyn FnMut(&NesPPU, &mut Joypad) + 'call>,
+    joypad1: Joypad,
+    rom_hash: u32,
+    pub watchpoints: Watchpoints,
+    dmc_fetch: Option<u16>,
+    fault: Option<FaultKind>,
+}
+
+impl<'a> Bus<'a> {
+    pub fn new<'call, F>(rom: Rom, gameloop_callback: F) -> Result<Bus<'call>, String>
+    where
+        F: FnMut(&NesPPU, &mut Joypad) + 'call,
+    {
+        let rom_hash = rom.hash();
+        let mapper = new_mapper(rom)?;
+        let ppu = NesPPU::new(mapper.clone());
+
+        Ok(Bus {
+            cpu_vram: [0; 2048],
+            prg_ram: [0; 0x2000],
+            mapper,
+            ppu,
+            apu: Apu::new(),
+            cycles: 0,
+            frames: 0,
+            gameloop_callback: Box::from(gameloop_callback),
+            joypad1: Joypad::new(),
+            rom_hash,
+            watchpoints: Watchpoints::new(),
+            dmc_fetch: None,
+            fault: None,
+        })
+    }
+
+    pub fn ppu(&self) -> &NesPPU {
+        &self.ppu
+    }
+
+    pub fn ppu_mut(&mut self) -> &mut NesPPU {
+        &mut self.ppu
+    }
+
+    pub fn joypad1(&mut self) -> &mut Joypad {
+        &mut self.joypad1
+    }
+
+    pub fn rom_hash(&self) -> u32 {
+        self.rom_hash
+    }
+
+    pub fn prg_ram(&self) -> &[u8] {
+        &self.prg_ram
+    }
+
+    pub fn load_prg_ram(&mut self, data: &[u8]) {
+        let len = data.len().min(self.prg_ram.len());
+        self.prg_ram[..len].copy_from_slice(&data[..len]);
+    }
+
+    pub fn pending_audio_samples(&self) -> usize {
+        self.apu.pending_samples()
+    }
+
+    pub fn take_audio_samples(&mut self) -> Vec<f32> {
+        self.apu.take_samples()
+    }
+
+    // Address of the last sample byte the DMC read, since the previous call
+    pub fn take_dmc_fetch(&mut self) -> Option<u16> {
+        self.dmc_fetch.take()
+    }
+}
+
+impl CpuBus for Bus<'_> {
+    fn tick(&mut self, cycles: u8) {
+        self.cycles += cycles as usize;
+
+        for _ in 0..cycles {
+            self.apu.tick();
+            if let Some(addr) = self.apu.dmc_fetch_addr() {
+                let data = self.mem_read(addr);
+                self.apu.dmc_fill_sample_buffer(data);
+                self.dmc_fetch = Some(addr);
+            }
+        }
+
+        if self.ppu.tick(cycles * 3) {
+            self.frames += 1;
+            (self.gameloop_callback)(&self.ppu, &mut self.joypad1);
+        }
+    }
+
+    fn poll_nmi_status(&mut self) -> bool {
+        self.ppu.nmi_interrupt.take().is_some()
+    }
+
+    // The APU and the cartridge share the IRQ line, it stays asserted until the game acknowledges it
+    fn poll_irq_status(&self) -> bool {
+        self.apu.irq() || self.mapper.borrow().irq()
+    }
+
+    // the I/O registers read as 0
+    fn peek(&self, addr: u16) -> u8 {
+        match addr {
+            RAM..=RAM_MIRRORS_END => self.cpu_vram[(addr & 0b00000111_11111111) as usize],
+            PRG_RAM..=PRG_RAM_END => self.prg_ram[(addr - PRG_RAM) as usize],
+            0x8000..=0xFFFF => self.mapper.borrow().read_prg(addr),
+            _ => 0,
+        }
+    }
+
+    fn take_fault(&mut self) -> Option<FaultKind> {
+        self.fault.take()
+    }
+
+    fn ppu_position(&self) -> (u16, u16) {
+        (self.ppu.scanline(), self.ppu.dot() as u16)
+    }
+}
+
+impl Snapshot for Bus<'_> {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.cpu_vram);
+        w.write_bytes(&self.prg_ram);
+        w.write_u64(self.cycles as u64);
+        w.write_u64(self.frames as u64);
+        self.ppu.save_state(w);
+        self.apu.save_state(w);
+        self.joypad1.save_state(w);
+        self.mapper.borrow().save_state(w);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        r.read_into(&mut self.cpu_vram)?;
+        r.read_into(&mut self.prg_ram)?;
+        self.cycles = r.read_u64()? as usize;
+        self.frames = r.read_u64()? as usize;
+        self.ppu.load_state(r)?;
+        self.apu.load_state(r)?;
+        self.joypad1.load_state(r)?;
+        self.mapper.borrow_mut().load_state(r)
+    }
+}
+
+impl Mem for Bus<'_> {
+    fn mem_read(&mut self, addr: u16) -> u8 {
+        let data = match addr {
+            RAM..=RAM_MIRRORS_END => {
+                let mirror_down_addr = addr & 0b00000111_11111111;
+                self.cpu_vram[mirror_down_addr as usize]
+            }
+            0x2000 | 0x2001 | 0x2003 | 0x2005 | 0x2006 | 0x4014 => 0, // Write only address
+            0x2002 => self.ppu.read_status(),
+            0x2004 => self.ppu.read_oam_data(),
+            0x2007 => {
+                let ppu_addr = self.ppu.vram_addr();
+                let data = self.ppu.read_data();
+                self.watchpoints.check(Space::Ppu, ppu_addr, Access::Read, data);
+                data
+            }
+            0x4015 => self.apu.read_status(),
+            0x4000..=0x4013 => 0, // Write only APU registers
+            0x4016 => self.joypad1.read(),
+            0x4017 => 0, // TODO joypad 2
+            0x2008..=PPU_REGISTERS_MIRRORS_END => {
+                let mirror_down_addr = addr & 0b00100000_00000111;
+                self.mem_read(mirror_down_addr)
+            }
+            PRG_RAM..=PRG_RAM_END => self.prg_ram[(addr - PRG_RAM) as usize],
+            0x8000..=0xFFFF => self.mapper.borrow().read_prg(addr),
+            _ => {
+                println!("Ignoring mem access at {addr:X}");
+                0
+            }
+        };
+
+        self.watchpoints.check(Space::Cpu, addr, Access::Read, data);
+        data
+    }
+
+    fn mem_write(&mut self, addr: u16, data: u8) {
+        self.watchpoints.check(Space::Cpu, addr, Access::Write, data);
+
+        match addr {
+            RAM..=RAM_MIRRORS_END => {
+                let mirror_down_addr = addr & 0b11111111111;
+                self.cpu_vram[mirror_down_addr as usize] = data;
+            }
+            0x2000 => self.ppu.write_to_ctrl(data),
+            0x2001 => self.ppu.write_to_mask(data),
+            0x2002 => self.fault = Some(FaultKind::PpuStatusWrite { data }),
+            0x2003 => self.ppu.write_to_oam_addr(data),
+            0x2004 => self.ppu.write_to_oam_data(data),
+            0x2005 => self.ppu.write_to_scroll(data),
+            0x2006 => self.ppu.write_to_addr(data),
+            0x2007 => {
+                self.watchpoints.check(Space::Ppu, self.ppu.vram_addr(), Access::Write, data);
+                self.ppu.write_to_data(data);
+            }
+            0x2008..=PPU_REGISTERS_MIRRORS_END => {
+                let mirror_down_addr = addr & 0b00100000_00000111;
+                self.mem_write(mirror_down_addr, data);
+            }
+            0x4000..=0x4013 | 0x4015 | 0x4017 => self.apu.write_register(addr, data),
+            0x4016 => {
+                self.joypad1.write(data);
+            }
+            // the CPU is halted while the page is copied: a cycle to stop it, one more to line up with
+            // a read cycle, then a read and a write for every byte
+            0x4014 => {
+                self.tick(1);
+                if self.cycles % 2 == 1 {
+                    self.tick(1);
+                }
+
+                let mut buffer: [u8; 256] = [0; 256];
+                let hi: u16 = (data as u16) << 8;
+                for i in 0..256u16 {
+                    self.tick(1);
+                    buffer[i as usize] = self.mem_read(hi + i);
+                    self.tick(1);
+                }
+
+                self.ppu.write_oam_dma(&buffer);
+            }
+            PRG_RAM..=PRG_RAM_END => self.prg_ram[(addr - PRG_RAM) as usize] = data,
+            0x8000..=0xFFFF => {
+                if self.mapper.borrow().has_prg_registers() {
+                    self.mapper.borrow_mut().write_prg(addr, data);
+                } else {
+                    self.fault = Some(FaultKind::RomWrite { addr, data });
+                }
+            }
+
+            _ => println!("Ignoring mem write-access at 0x{addr:X}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rom::test;
+
+    #[test]
+    fn test_mem_read_write_to_ram() {
+        let mut bus = Bus::new(test::test_rom(), |_ppu: &NesPPU, _joypad: &mut Joypad| {}).unwrap();
+        bus.mem_write(0x01, 0x55);
+        assert_eq!(bus.mem_read(0x01), 0x55);
+    }
+
+    #[test]
+    fn test_mem_read_write_to_prg_ram() {
+        let mut bus = Bus::new(test::test_rom(), |_ppu: &NesPPU, _joypad: &mut Joypad| {}).unwrap();
+        bus.load_prg_ram(&[0x11, 0x22]);
+        bus.mem_write(0x7FFF, 0x55);
+
+        assert_eq!(bus.mem_read(0x6001), 0x22);
+        assert_eq!(bus.prg_ram()[0x1FFF], 0x55);
+    }
+
+    #[test]
+    fn test_faults() {
+        let mut rom = test::test_rom();
+        rom.mapper = 0;
+        let mut bus = Bus::new(rom, |_ppu: &NesPPU, _joypad: &mut Joypad| {}).unwrap();
+
+        bus.mem_write(0x8000, 0x55);
+        assert_eq!(
+            bus.take_fault(),
+            Some(FaultKind::RomWrite {
+                addr: 0x8000,
+                data: 0x55
+            })
+        );
+        assert_eq!(bus.mem_read(0x8000), 1);
+
+        bus.mem_write(0x3FFA, 0x80);
+        assert_eq!(bus.take_fault(), Some(FaultKind::PpuStatusWrite { data: 0x80 }));
+        assert_eq!(bus.take_fault(), None);
+    }
+}
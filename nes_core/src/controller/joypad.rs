@@ -1,3 +1,5 @@
+use crate::savestate::{Snapshot, StateReader, StateWriter};
+
 pub struct Joypad {
     strobe: bool,
     button_index: u8,
@@ -49,6 +51,21 @@ impl Joypad {
     }
 }
 
+impl Snapshot for Joypad {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bool(self.strobe);
+        w.write_u8(self.button_index);
+        w.write_u8(self.button_status);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        self.strobe = r.read_bool()?;
+        self.button_index = r.read_u8()?;
+        self.button_status = r.read_u8()?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
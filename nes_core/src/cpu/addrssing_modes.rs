@@ -0,0 +1,116 @@
+use super::{cpu::CPU, memory::CpuBus};
+
+#[derive(Debug, Clone, Copy)]
+#[allow(non_camel_case_types)]
+pub enum AddressingMode {
+    Accumulator,
+    Immediate,
+    ZeroPage,
+    ZeroPage_X,
+    ZeroPage_Y,
+    Absolute,
+    Absolute_X,
+    Absolute_Y,
+    Indirect,
+    Indirect_X,
+    Indirect_Y,
+    NoneAddressing,
+}
+
+fn is_in_another_page(addr1: u16, addr2: u16) -> bool {
+    addr1 & 0xFF00 != addr2 & 0xFF00
+}
+
+// The cycles spent fetching the operand and working out its address. The 6502 adds the index to the
+// low byte first and reads from that address while it fixes the high byte, so indexed modes make a
+// dummy read in the page of the base address
+impl<B: CpuBus> CPU<B> {
+    // Instructions that only read skip the dummy read, and its cycle, when no page is crossed
+    pub fn get_operand_address(&mut self, mode: &AddressingMode) -> u16 {
+        self.operand_address(mode, false)
+    }
+
+    // Stores and read-modify-write instructions always make it
+    pub fn get_store_address(&mut self, mode: &AddressingMode) -> u16 {
+        self.operand_address(mode, true)
+    }
+
+    fn operand_address(&mut self, mode: &AddressingMode, always_fix: bool) -> u16 {
+        match mode {
+            AddressingMode::Immediate => self.program_counter,
+            AddressingMode::ZeroPage => self.read(self.program_counter) as u16,
+            AddressingMode::ZeroPage_X => {
+                let base = self.read(self.program_counter);
+                self.read(base as u16);
+                base.wrapping_add(self.register_x) as u16
+            }
+            AddressingMode::ZeroPage_Y => {
+                let base = self.read(self.program_counter);
+                self.read(base as u16);
+                base.wrapping_add(self.register_y) as u16
+            }
+            AddressingMode::Absolute => self.read_u16(self.program_counter),
+            AddressingMode::Absolute_X => {
+                let base = self.read_u16(self.program_counter);
+                self.indexed(base, self.register_x, always_fix)
+            }
+            AddressingMode::Absolute_Y => {
+                let base = self.read_u16(self.program_counter);
+                self.indexed(base, self.register_y, always_fix)
+            }
+            AddressingMode::Indirect_X => {
+                let base = self.read(self.program_counter);
+                self.read(base as u16);
+
+                let ptr: u8 = base.wrapping_add(self.register_x);
+                let lo = self.read(ptr as u16);
+                let hi = self.read(ptr.wrapping_add(1) as u16);
+
+                (hi as u16) << 8 | lo as u16
+            }
+            AddressingMode::Indirect_Y => {
+                let base = self.read(self.program_counter);
+
+                let lo = self.read(base as u16);
+                let hi = self.read(base.wrapping_add(1) as u16);
+                let deref_base = (hi as u16) << 8 | (lo as u16);
+
+                self.indexed(deref_base, self.register_y, always_fix)
+            }
+            _ => panic!("mode {:?} is not supported", mode),
+        }
+    }
+
+    // The address the instruction at PC is going to access, worked out with peeks so none of the registers notice.
+    // JMP ($xxxx) gives the address of its pointer
+    pub fn peek_operand_address(&self, mode: &AddressingMode) -> Option<u16> {
+        let pc = self.program_counter.wrapping_add(1);
+        let lo = self.bus.peek(pc);
+        let word = u16::from_le_bytes([lo, self.bus.peek(pc.wrapping_add(1))]);
+        // pointers in the zero page wrap around inside it
+        let zero_page_u16 =
+            |addr: u8| u16::from_le_bytes([self.bus.peek(addr as u16), self.bus.peek(addr.wrapping_add(1) as u16)]);
+
+        match mode {
+            AddressingMode::ZeroPage => Some(lo as u16),
+            AddressingMode::ZeroPage_X => Some(lo.wrapping_add(self.register_x) as u16),
+            AddressingMode::ZeroPage_Y => Some(lo.wrapping_add(self.register_y) as u16),
+            AddressingMode::Absolute | AddressingMode::Indirect => Some(word),
+            AddressingMode::Absolute_X => Some(word.wrapping_add(self.register_x as u16)),
+            AddressingMode::Absolute_Y => Some(word.wrapping_add(self.register_y as u16)),
+            AddressingMode::Indirect_X => Some(zero_page_u16(lo.wrapping_add(self.register_x))),
+            AddressingMode::Indirect_Y => Some(zero_page_u16(lo).wrapping_add(self.register_y as u16)),
+            _ => None,
+        }
+    }
+
+    fn indexed(&mut self, base: u16, index: u8, always_fix: bool) -> u16 {
+        let addr = base.wrapping_add(index as u16);
+
+        if always_fix || is_in_another_page(base, addr) {
+            self.read((base & 0xFF00) | (addr & 0x00FF));
+        }
+
+        addr
+    }
+}
@@ -0,0 +1,310 @@
+use std::collections::HashMap;
+
+use super::{addrssing_modes::AddressingMode, opscodes::OPS_CODES};
+
+// Assembles `LDA #$c0 / TAX / INX / BRK` to the bytes `CPU::load` expects, with the program at $0600
+#[macro_export]
+macro_rules! asm {
+    ($source:expr) => {
+        $crate::cpu::assemble($source, 0x0600).unwrap_or_else(|err| panic!("{err}"))
+    };
+}
+
+enum Value {
+    Number(u16),
+    Label(String),
+}
+
+enum Statement {
+    Instruction { opcode: u8, operand: Option<Value> },
+    Bytes(Vec<Value>),
+    Words(Vec<Value>),
+}
+
+// A small 6502 assembler for the tests and tools, with the syntax of ca65 and the disassemblers:
+//
+// loop:  LDA $0200,X     ; labels end with ':', comments start with ';'
+//        BNE loop / RTS  ; '/' separates statements on the same line
+// .org $8000             ; moves the address without padding
+// .byte $01, 2, %11      ; hex, decimal and binary
+// .word loop
+//
+// `a:` forces the absolute mode of an operand below $100, labels always take the absolute one. The unofficial
+// opcodes are written like in the disassembler, with a `*`
+pub fn assemble(source: &str, origin: u16) -> Result<Vec<u8>, String> {
+    let mut labels = HashMap::new();
+    let mut statements = vec![];
+    let mut pc = origin;
+
+    for (i, line) in source.lines().enumerate() {
+        let line = line.split(';').next().unwrap();
+        for statement in line.split('/') {
+            let mut statement = statement.trim();
+
+            if let Some((label, rest)) = statement.split_once(':') {
+                if is_identifier(label.trim()) {
+                    if labels.insert(label.trim().to_string(), pc).is_some() {
+                        return Err(format!("line {}: label {} is defined twice", i + 1, label.trim()));
+                    }
+                    statement = rest.trim();
+                }
+            }
+            if statement.is_empty() {
+                continue;
+            }
+
+            let statement = parse_statement(statement, &mut pc).map_err(|err| format!("line {}: {err}", i + 1))?;
+            if let Some(statement) = statement {
+                statements.push((i + 1, statement));
+            }
+        }
+    }
+
+    let mut bytes = vec![];
+    for (line, statement) in statements {
+        emit(&statement, &labels, &mut bytes).map_err(|err| format!("line {line}: {err}"))?;
+    }
+    Ok(bytes)
+}
+
+// `pc` moves past the statement, `.org` returns no statement
+fn parse_statement(statement: &str, pc: &mut u16) -> Result<Option<(u16, Statement)>, String> {
+    let (mnemonic, operand) = match statement.split_once(char::is_whitespace) {
+        Some((mnemonic, operand)) => (mnemonic, operand.trim()),
+        None => (statement, ""),
+    };
+    let start = *pc;
+
+    let statement = match mnemonic.to_ascii_lowercase().as_str() {
+        ".org" => {
+            match parse_value(operand)? {
+                Value::Number(addr) => *pc = addr,
+                Value::Label(_) => return Err(".org needs a number".to_string()),
+            }
+            return Ok(None);
+        }
+        ".byte" => Statement::Bytes(parse_list(operand)?),
+        ".word" => Statement::Words(parse_list(operand)?),
+        _ => parse_instruction(&mnemonic.to_ascii_uppercase(), operand)?,
+    };
+
+    *pc = pc.wrapping_add(match &statement {
+        Statement::Instruction { opcode, .. } => OPS_CODES[*opcode as usize].len as u16,
+        Statement::Bytes(values) => values.len() as u16,
+        Statement::Words(values) => values.len() as u16 * 2,
+    });
+    Ok(Some((start, statement)))
+}
+
+fn parse_instruction(name: &str, operand: &str) -> Result<Statement, String> {
+    use AddressingMode::*;
+
+    let upper = operand.to_ascii_uppercase();
+    let (modes, value): (&[AddressingMode], &str) = if operand.is_empty() {
+        (&[NoneAddressing, Accumulator], "")
+    } else if upper == "A" {
+        (&[Accumulator], "")
+    } else if let Some(value) = operand.strip_prefix('#') {
+        (&[Immediate], value)
+    } else if let Some(value) = upper.strip_prefix('(').and_then(|value| value.strip_suffix(",X)")) {
+        (&[Indirect_X], &operand[1..value.len() + 1])
+    } else if let Some(value) = upper.strip_prefix('(').and_then(|value| value.strip_suffix("),Y")) {
+        (&[Indirect_Y], &operand[1..value.len() + 1])
+    } else if let Some(value) = upper.strip_prefix('(').and_then(|value| value.strip_suffix(')')) {
+        (&[Indirect], &operand[1..value.len() + 1])
+    } else if let Some(value) = upper.strip_suffix(",X") {
+        (&[ZeroPage_X, Absolute_X], &operand[..value.len()])
+    } else if let Some(value) = upper.strip_suffix(",Y") {
+        (&[ZeroPage_Y, Absolute_Y], &operand[..value.len()])
+    } else {
+        // branches and JSR have their operand listed as NoneAddressing
+        (&[ZeroPage, Absolute, NoneAddressing], operand)
+    };
+
+    let (force_absolute, value) = match value.trim().strip_prefix("a:") {
+        Some(value) => (true, value),
+        None => (false, value.trim()),
+    };
+    let value = if value.is_empty() {
+        None
+    } else {
+        Some(parse_value(value)?)
+    };
+
+    // the zero page modes only for numbers that fit in them
+    let zero_page = matches!(value, Some(Value::Number(n)) if n < 0x100) && !force_absolute;
+    let modes = modes.iter().filter(|mode| {
+        zero_page
+            || !matches!(
+                mode,
+                AddressingMode::ZeroPage | AddressingMode::ZeroPage_X | AddressingMode::ZeroPage_Y
+            )
+    });
+
+    for mode in modes {
+        let found = [name.to_string(), format!("*{}", name.trim_start_matches('*'))]
+            .iter()
+            .find_map(|name| {
+                OPS_CODES.iter().position(|op| {
+                    op.name == name
+                        && std::mem::discriminant(&op.mode) == std::mem::discriminant(mode)
+                        // an operand for the NoneAddressing ones means a branch or JSR
+                        && (!matches!(mode, AddressingMode::NoneAddressing) || (op.len > 1) == value.is_some())
+                })
+            });
+        if let Some(opcode) = found {
+            return Ok(Statement::Instruction {
+                opcode: opcode as u8,
+                operand: value,
+            });
+        }
+    }
+
+    Err(format!(
+        "{} is not an instruction",
+        format!("{name} {operand}").trim_end()
+    ))
+}
+
+fn parse_list(list: &str) -> Result<Vec<Value>, String> {
+    list.split(',').map(|value| parse_value(value.trim())).collect()
+}
+
+fn parse_value(value: &str) -> Result<Value, String> {
+    let number = if let Some(hex) = value.strip_prefix('$') {
+        u16::from_str_radix(hex, 16)
+    } else if let Some(binary) = value.strip_prefix('%') {
+        u16::from_str_radix(binary, 2)
+    } else if is_identifier(value) {
+        return Ok(Value::Label(value.to_string()));
+    } else {
+        value.parse()
+    };
+    number
+        .map(Value::Number)
+        .map_err(|_| format!("{value} is not a number or a label"))
+}
+
+fn is_identifier(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn emit((pc, statement): &(u16, Statement), labels: &HashMap<String, u16>, bytes: &mut Vec<u8>) -> Result<(), String> {
+    let resolve = |value: &Value| match value {
+        Value::Number(n) => Ok(*n),
+        Value::Label(name) => labels.get(name).copied().ok_or(format!("label {name} is not defined")),
+    };
+    let byte = |value: u16| u8::try_from(value).map_err(|_| format!("${value:X} doesn't fit in a byte"));
+
+    match statement {
+        Statement::Instruction { opcode, operand } => {
+            let op = &OPS_CODES[*opcode as usize];
+            bytes.push(*opcode);
+
+            let Some(operand) = operand else {
+                return Ok(());
+            };
+            let value = resolve(operand)?;
+            match (op.mode, op.len) {
+                (AddressingMode::NoneAddressing, 2) => {
+                    let offset = value.wrapping_sub(pc.wrapping_add(2)) as i16;
+                    if !(-128..=127).contains(&offset) {
+                        return Err(format!("branch to ${value:04X} is out of range"));
+                    }
+                    bytes.push(offset as u8);
+                }
+                (_, 2) => bytes.push(byte(value)?),
+                _ => bytes.extend(value.to_le_bytes()),
+            }
+        }
+        Statement::Bytes(values) => {
+            for value in values {
+                bytes.push(byte(resolve(value)?)?);
+            }
+        }
+        Statement::Words(values) => {
+            for value in values {
+                bytes.extend(resolve(value)?.to_le_bytes());
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_addressing_modes() {
+        let program = assemble(
+            "LDA #$05 / LDA $10 / LDA $10,X / LDA $0200 / LDA $0200,X / LDA $0200,Y
+             LDA ($10,X) / LDA ($10),Y / JMP ($1234) / ASL A / ASL / LDX $10,Y / LDA a:$0010",
+            0x0600,
+        );
+
+        assert_eq!(
+            program.unwrap(),
+            vec![
+                0xA9, 0x05, 0xA5, 0x10, 0xB5, 0x10, 0xAD, 0x00, 0x02, 0xBD, 0x00, 0x02, 0xB9, 0x00, 0x02, 0xA1, 0x10,
+                0xB1, 0x10, 0x6C, 0x34, 0x12, 0x0A, 0x0A, 0xB6, 0x10, 0xAD, 0x10, 0x00,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_labels_and_directives() {
+        let program = assemble(
+            "start: LDX #0
+             loop:  INX          ; forward and backward references
+                    BNE loop
+                    JSR sub
+                    JMP start
+             sub:   RTS
+             .byte $FF, 2, %11
+             .word start, sub",
+            0x0600,
+        );
+
+        assert_eq!(
+            program.unwrap(),
+            vec![
+                0xA2, 0x00, 0xE8, 0xD0, 0xFD, 0x20, 0x0B, 0x06, 0x4C, 0x00, 0x06, 0x60, 0xFF, 0x02, 0x03, 0x00, 0x06,
+                0x0B, 0x06,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_org_and_unofficial_opcodes() {
+        let program = assemble(".org $C000 / here: *LAX $10 / LAX $10 / *NOP $10 / JMP here", 0);
+
+        assert_eq!(
+            program.unwrap(),
+            vec![0xA7, 0x10, 0xA7, 0x10, 0x04, 0x10, 0x4C, 0x00, 0xC0]
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(assemble("LDA", 0), Err("line 1: LDA is not an instruction".to_string()));
+        assert_eq!(
+            assemble("NOP\nJMP nowhere", 0),
+            Err("line 2: label nowhere is not defined".to_string())
+        );
+        assert_eq!(
+            assemble("LDA ($0200),Y", 0),
+            Err("line 1: $200 doesn't fit in a byte".to_string())
+        );
+        assert!(assemble("far: .org $0700 / BNE far", 0x0600).is_err());
+    }
+
+    #[test]
+    fn test_asm_macro() {
+        assert_eq!(
+            crate::asm!("LDA #$c0 / TAX / INX / BRK"),
+            vec![0xa9, 0xc0, 0xaa, 0xe8, 0x00]
+        );
+    }
+}
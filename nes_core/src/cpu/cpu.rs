@@ -0,0 +1,945 @@
+use crate::{
+    bus::Bus,
+    savestate::{Snapshot, StateReader, StateWriter},
+};
+
+use super::{
+    fault::{Fault, FaultAction, FaultKind, FaultPolicy},
+    flags,
+    history::{Executed, History},
+    memory::{CpuBus, Mem},
+    opscodes::OPS_CODES,
+};
+
+const STACK: u16 = 0x0100;
+const STACK_RESET: u8 = 0xfd;
+const NMI_VECTOR: u16 = 0xFFFA;
+const IRQ_VECTOR: u16 = 0xFFFE; // shared by IRQ and BRK
+
+// change I on their last cycle, after the interrupt lines were polled
+const CLI: u8 = 0x58;
+const SEI: u8 = 0x78;
+const PLP: u8 = 0x28;
+
+pub struct CPU<B> {
+    pub register_a: u8,
+    pub register_x: u8,
+    pub register_y: u8,
+    pub status: u8,
+    pub program_counter: u16,
+    pub stack_counter: u8,
+    // set by the KIL opcodes, the CPU stops fetching instructions until a reset
+    pub jammed: bool,
+    // BCD arithmetic with the D flag, the NES 2A03 has it cut out so it's off by default
+    pub decimal_mode: bool,
+    // vector of the NMI or IRQ taken right before the instruction about to run, for the callbacks
+    pub taken_interrupt: Option<u16>,
+    // IRQ line and clear I as polled by the last instruction, the next step acts on it
+    irq_latch: bool,
+    pub fault_policy: FaultPolicy,
+    // the fault that stopped the CPU, until a reset
+    pub halted: Option<Fault>,
+    // set by the instructions that jump, the others have PC moved past their operands
+    pc_written: bool,
+    // raised during the current instruction
+    fault: Option<FaultKind>,
+    // waiting for the debugger
+    fault_break: Option<Fault>,
+    // the last one the policy didn't ignore, for the crash reports
+    last_fault: Option<Fault>,
+    // the last executed instructions, off unless it's given a capacity
+    pub history: History,
+    pub bus: B,
+}
+
+// The CPU of the NES, with the PPU, APU and cartridge behind its bus
+pub type NesCPU<'a> = CPU<Bus<'a>>;
+
+// Accesses from outside the instructions, like the debugger or the tests, they don't spend CPU cycles
+impl<B: CpuBus> Mem for CPU<B> {
+    fn mem_read(&mut self, addr: u16) -> u8 {
+        self.bus.mem_read(addr)
+    }
+
+    fn mem_write(&mut self, addr: u16, data: u8) {
+        self.bus.mem_write(addr, data)
+    }
+
+    fn mem_read_u16(&mut self, pos: u16) -> u16 {
+        self.bus.mem_read_u16(pos)
+    }
+
+    fn mem_write_u16(&mut self, pos: u16, data: u16) {
+        self.bus.mem_write_u16(pos, data)
+    }
+}
+
+impl Snapshot for NesCPU<'_> {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.register_a);
+        w.write_u8(self.register_x);
+        w.write_u8(self.register_y);
+        w.write_u8(self.status);
+        w.write_u16(self.program_counter);
+        w.write_u8(self.stack_counter);
+        w.write_bool(self.jammed);
+        self.bus.save_state(w);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        self.register_a = r.read_u8()?;
+        self.register_x = r.read_u8()?;
+        self.register_y = r.read_u8()?;
+        self.status = r.read_u8()?;
+        self.program_counter = r.read_u16()?;
+        self.stack_counter = r.read_u8()?;
+        self.jammed = r.read_bool()?;
+        self.bus.load_state(r)?;
+        // the latch isn't in the state, it's polled again
+        self.irq_latch = self.bus.poll_irq_status() && !self.is_interrupt_disable_set();
+        Ok(())
+    }
+}
+
+impl<B: CpuBus> CPU<B> {
+    pub fn new(bus: B) -> Self {
+        CPU {
+            register_a: 0,
+            register_x: 0,
+            register_y: 0,
+            status: 0b0010_0100,
+            program_counter: 0,
+            stack_counter: STACK_RESET,
+            jammed: false,
+            decimal_mode: false,
+            taken_interrupt: None,
+            irq_latch: false,
+            fault_policy: FaultPolicy::new(),
+            halted: None,
+            pc_written: false,
+            fault: None,
+            fault_break: None,
+            last_fault: None,
+            history: History::new(0),
+            bus,
+        }
+    }
+
+    // Every cycle of an instruction is one access to the bus, even the cycles where the CPU is busy
+    // doing something else make a read. The rest of the console catches up before each of them
+    pub fn read(&mut self, addr: u16) -> u8 {
+        self.bus.tick(1);
+        self.bus.mem_read(addr)
+    }
+
+    pub fn write(&mut self, addr: u16, data: u8) {
+        self.bus.tick(1);
+        self.bus.mem_write(addr, data);
+    }
+
+    pub fn read_u16(&mut self, addr: u16) -> u16 {
+        let lo = self.read(addr);
+        let hi = self.read(addr.wrapping_add(1));
+        (hi as u16) << 8 | lo as u16
+    }
+
+    pub fn stack_push(&mut self, data: u8) {
+        if self.stack_counter == 0x00 {
+            self.raise(FaultKind::StackOverflow);
+        }
+        self.write(STACK + self.stack_counter as u16, data);
+        self.stack_counter = self.stack_counter.wrapping_sub(1);
+    }
+
+    pub fn stack_pop(&mut self) -> u8 {
+        if self.stack_counter == 0xFF {
+            self.raise(FaultKind::StackUnderflow);
+        }
+        self.stack_counter = self.stack_counter.wrapping_add(1);
+        self.read(STACK + self.stack_counter as u16)
+    }
+
+    // The cycle spent before a pull (or before the pushes of JSR) reads the top of the stack
+    pub fn stack_dummy_read(&mut self) {
+        self.read(STACK + self.stack_counter as u16);
+    }
+
+    pub fn stack_push_u16(&mut self, data: u16) {
+        self.stack_push((data >> 8) as u8);
+        self.stack_push((data & 0x00FF) as u8);
+    }
+
+    pub fn stack_pop_u16(&mut self) -> u16 {
+        let lo = self.stack_pop() as u16;
+        let hi = self.stack_pop() as u16;
+
+        hi << 8 | lo
+    }
+
+    // NMI, IRQ and BRK share the same 7 cycle sequence, only BRK pushes the status with the B flag.
+    // After the first 2 cycles, which read the opcode and the next byte, PC and the status are pushed and
+    // the vector is read. An NMI that comes up before the vector is chosen hijacks the sequence, so a BRK
+    // or an IRQ can end up in the NMI handler
+    fn interrupt(&mut self, vector: u16, break_flag: bool) -> u16 {
+        self.stack_push_u16(self.program_counter);
+        let mut flag = self.status;
+        flag &= !flags::BREAK;
+        flag |= flags::BREAK2;
+        if break_flag {
+            flag |= flags::BREAK;
+        }
+
+        let vector = if self.bus.poll_nmi_status() { NMI_VECTOR } else { vector };
+        self.stack_push(flag);
+        self.set_interrupt_disable_flag(true);
+
+        self.program_counter = self.read_u16(vector);
+        vector
+    }
+
+    // NMI and IRQ read the opcode twice without executing it
+    fn hardware_interrupt(&mut self, vector: u16) {
+        self.read(self.program_counter);
+        self.read(self.program_counter);
+        self.taken_interrupt = Some(self.interrupt(vector, false));
+    }
+
+    pub fn brk(&mut self) {
+        // BRK skips the byte after it, the return address is the opcode + 2
+        self.program_counter = self.program_counter.wrapping_add(1);
+        self.interrupt(IRQ_VECTOR, true);
+        self.pc_written = true;
+    }
+
+    // PC of the instructions that jump, it isn't moved past their operands afterwards
+    pub fn jump(&mut self, addr: u16) {
+        self.program_counter = addr;
+        self.pc_written = true;
+    }
+
+    pub fn kil(&mut self) {
+        self.raise(FaultKind::Jam);
+    }
+
+    // Only the first fault of an instruction is kept
+    pub fn raise(&mut self, kind: FaultKind) {
+        self.fault.get_or_insert(kind);
+    }
+
+    // The fault the policy wants the debugger to stop on
+    pub fn take_fault_break(&mut self) -> Option<Fault> {
+        self.fault_break.take()
+    }
+
+    pub fn take_last_fault(&mut self) -> Option<Fault> {
+        self.last_fault.take()
+    }
+
+    fn record(&mut self, opscode: u8, scanline: u16, dot: u16) {
+        let pc = self.program_counter;
+        let mut bytes = [opscode, 0, 0];
+        for i in 1..OPS_CODES[opscode as usize].len as u16 {
+            bytes[i as usize] = self.bus.peek(pc.wrapping_add(i));
+        }
+        self.history.push(Executed {
+            pc,
+            bytes,
+            register_a: self.register_a,
+            register_x: self.register_x,
+            register_y: self.register_y,
+            status: self.status,
+            stack_counter: self.stack_counter,
+            scanline,
+            dot,
+        });
+    }
+
+    // Returns false when the policy halts the CPU
+    fn handle_fault(&mut self, fault: Fault) -> bool {
+        let action = self.fault_policy.action(&fault.kind);
+        if action != FaultAction::Ignore {
+            self.last_fault = Some(fault);
+        }
+        match action {
+            FaultAction::Ignore => {}
+            FaultAction::Log => eprintln!("{fault}"),
+            FaultAction::Break => {
+                eprintln!("{fault}");
+                self.fault_break = Some(fault);
+            }
+            FaultAction::Halt => {
+                if fault.kind == FaultKind::Jam {
+                    // the CPU stays on the opcode
+                    self.program_counter = fault.pc;
+                    self.jammed = true;
+                }
+                self.halted = Some(fault);
+                return false;
+            }
+        }
+        true
+    }
+
+    #[cfg(test)]
+    pub fn load_and_run(&mut self, program: Vec<u8>) {
+        self.load(program);
+        self.reset();
+        self.program_counter = 0x0600;
+        self.run_until_brk();
+    }
+
+    // The tests end their programs with a BRK
+    #[cfg(test)]
+    pub fn run_until_brk(&mut self) {
+        while self.bus.peek(self.program_counter) != 0x00 && self.step() {}
+    }
+
+    #[cfg(test)]
+    pub fn load(&mut self, program: Vec<u8>) {
+        for i in 0..(program.len() as u16) {
+            self.mem_write(0x0600 + i, program[i as usize]);
+        }
+        // self.mem_write_u16(0xFFFC, 0x0600);
+    }
+
+    pub fn reset(&mut self) {
+        self.register_a = 0;
+        self.register_x = 0;
+        self.register_y = 0;
+        self.stack_counter = STACK_RESET;
+        self.status = 0b0010_0100;
+        self.jammed = false;
+        self.halted = None;
+        self.irq_latch = false;
+        self.fault = None;
+        self.fault_break = None;
+        self.last_fault = None;
+
+        self.program_counter = self.mem_read_u16(0xFFFC);
+        // the reset sequence takes 7 cycles, like in nestest.log where the first instruction is at CYC:7
+        self.bus.tick(7);
+    }
+
+    pub fn run(&mut self) {
+        self.run_with_callback(|_| {});
+    }
+
+    pub fn run_with_callback<F>(&mut self, mut callback: F)
+    where
+        F: FnMut(&mut Self),
+    {
+        while self.step_with_callback(&mut callback) {}
+    }
+
+    // Executes a single instruction, returns false when the CPU stops
+    pub fn step(&mut self) -> bool {
+        self.step_with_callback(&mut |_| {})
+    }
+
+    // The callback sees the CPU right before the instruction is fetched, after a pending interrupt was taken.
+    // The IRQ line is level triggered, it's serviced for as long as a device holds it and I is clear. Both are
+    // polled before the last cycle of an instruction, so the I flag set by CLI, SEI and PLP on that cycle
+    // counts one instruction later
+    pub fn step_with_callback<F>(&mut self, callback: &mut F) -> bool
+    where
+        F: FnMut(&mut Self),
+    {
+        // a jammed CPU doesn't even answer interrupts
+        if self.jammed || self.halted.is_some() {
+            return false;
+        }
+
+        self.taken_interrupt = None;
+        if self.bus.poll_nmi_status() {
+            self.hardware_interrupt(NMI_VECTOR);
+        } else if self.irq_latch {
+            self.hardware_interrupt(IRQ_VECTOR);
+        }
+
+        callback(self);
+
+        // the PPU has moved on once the opcode is read
+        let position = self.history.is_enabled().then(|| self.bus.ppu_position());
+        let opscode = self.read(self.program_counter);
+        if let Some((scanline, dot)) = position {
+            self.record(opscode, scanline, dot);
+        }
+
+        self.program_counter = self.program_counter.wrapping_add(1);
+        let pc = self.program_counter;
+
+        let ops = &OPS_CODES[opscode as usize];
+        // single byte instructions still read the next one
+        if ops.len == 1 {
+            self.read(self.program_counter);
+        }
+        let interrupt_disable = self.is_interrupt_disable_set();
+        self.pc_written = false;
+        Self::HANDLERS[opscode as usize](self, &ops.mode);
+        if !self.pc_written {
+            self.program_counter = self.program_counter.wrapping_add((ops.len - 1) as u16);
+        }
+
+        let interrupt_disable = match opscode {
+            CLI | SEI | PLP => interrupt_disable,
+            _ => self.is_interrupt_disable_set(),
+        };
+        self.irq_latch = self.bus.poll_irq_status() && !interrupt_disable;
+
+        match self.fault.take().or_else(|| self.bus.take_fault()) {
+            Some(kind) => self.handle_fault(Fault {
+                pc: pc.wrapping_sub(1),
+                kind,
+            }),
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::asm;
+    use crate::controller::Joypad;
+    use crate::cpu::FlatMemory;
+    use crate::cpu::{Fault, FaultAction, FaultKind};
+    use crate::debugger::{Space, Watchpoint};
+    use crate::ppu::NesPPU;
+    use crate::rom::{test, Mirroring, Rom};
+
+    #[test]
+    fn test_0xa9_lda_immidiate_load_data() {
+        let mut cpu = test_cpu();
+        cpu.load_and_run(asm!("LDA #$05 / BRK"));
+        assert_eq!(cpu.register_a, 5);
+        assert!(cpu.status & 0b0000_0010 == 0b00);
+        assert!(cpu.status & 0b1000_0000 == 0);
+    }
+
+    #[test]
+    fn test_0xaa_tax_move_a_to_x() {
+        let mut cpu = test_cpu();
+        cpu.register_a = 10;
+        cpu.load_and_run(asm!("LDA #$0A / TAX / BRK"));
+
+        assert_eq!(cpu.register_x, 10)
+    }
+
+    #[test]
+    fn test_5_ops_working_together() {
+        let mut cpu = test_cpu();
+        cpu.load_and_run(asm!("LDA #$c0 / TAX / INX / BRK"));
+
+        assert_eq!(cpu.register_x, 0xc1)
+    }
+
+    #[test]
+    fn test_inx_overflow() {
+        let mut cpu = test_cpu();
+        cpu.reset();
+        cpu.register_x = 0xff;
+        cpu.program_counter = 0x0600;
+        cpu.load(asm!("INX / INX / BRK"));
+        cpu.run_until_brk();
+
+        assert_eq!(cpu.register_x, 1)
+    }
+
+    #[test]
+    fn test_lda_from_memory() {
+        let mut cpu = test_cpu();
+        cpu.mem_write(0x10, 0x55);
+
+        cpu.load_and_run(asm!("LDA $10 / BRK"));
+
+        assert_eq!(cpu.register_a, 0x55);
+    }
+
+    // a bare 6502, the programs go to $0600 like in the NES RAM
+    fn test_cpu() -> CPU<FlatMemory> {
+        CPU::new(FlatMemory::new())
+    }
+
+    fn nes_cpu() -> NesCPU<'static> {
+        let bus = Bus::new(test::test_rom(), |_ppu: &NesPPU, _joypad: &mut Joypad| {}).unwrap();
+        CPU::new(bus)
+    }
+
+    #[test]
+    fn test_every_opcode_is_defined() {
+        assert!(OPS_CODES.iter().all(|op| op.name != "???"));
+    }
+
+    #[test]
+    fn test_lax_sax() {
+        let mut cpu = test_cpu();
+        cpu.mem_write(0x10, 0x8F);
+        cpu.load_and_run(asm!("LAX $10 / LDA #$F0 / SAX $11 / BRK"));
+
+        assert_eq!(cpu.register_x, 0x8F);
+        assert_eq!(cpu.mem_read(0x11), 0x80);
+    }
+
+    #[test]
+    fn test_dcp_isb() {
+        let mut cpu = test_cpu();
+        cpu.mem_write(0x10, 0x06);
+        cpu.mem_write(0x11, 0x02);
+        cpu.load_and_run(asm!("LDA #$05 / DCP $10 / SEC / ISB $11 / BRK"));
+
+        assert_eq!(cpu.mem_read(0x10), 0x05);
+        assert_eq!(cpu.mem_read(0x11), 0x03);
+        assert_eq!(cpu.register_a, 0x02);
+        assert!(cpu.is_carry_set());
+    }
+
+    #[test]
+    fn test_slo_rla_sre_rra() {
+        let mut cpu = test_cpu();
+        cpu.mem_write(0x10, 0x81);
+        cpu.load_and_run(asm!("SLO $10 / BRK"));
+        assert_eq!(cpu.mem_read(0x10), 0x02);
+        assert_eq!(cpu.register_a, 0x02);
+        assert!(cpu.is_carry_set());
+
+        cpu.mem_write(0x10, 0x81);
+        cpu.load_and_run(asm!("LDA #$FF / SEC / RLA $10 / BRK"));
+        assert_eq!(cpu.mem_read(0x10), 0x03);
+        assert_eq!(cpu.register_a, 0x03);
+        assert!(cpu.is_carry_set());
+
+        cpu.mem_write(0x10, 0x03);
+        cpu.load_and_run(asm!("LDA #$FF / SRE $10 / BRK"));
+        assert_eq!(cpu.mem_read(0x10), 0x01);
+        assert_eq!(cpu.register_a, 0xFE);
+        assert!(cpu.is_carry_set());
+
+        cpu.mem_write(0x10, 0x03);
+        // the carry out of ROR is added
+        cpu.load_and_run(asm!("LDA #$10 / CLC / RRA $10 / BRK"));
+        assert_eq!(cpu.mem_read(0x10), 0x01);
+        assert_eq!(cpu.register_a, 0x12);
+    }
+
+    #[test]
+    fn test_immediate_combinations() {
+        let mut cpu = test_cpu();
+        cpu.load_and_run(asm!("LDA #$F0 / ANC #$80 / BRK"));
+        assert_eq!(cpu.register_a, 0x80);
+        assert!(cpu.is_carry_set() && cpu.is_negative_set());
+
+        cpu.load_and_run(asm!("LDA #$FF / ALR #$03 / BRK"));
+        assert_eq!(cpu.register_a, 0x01);
+        assert!(cpu.is_carry_set());
+
+        cpu.load_and_run(asm!("LDA #$FF / SEC / ARR #$C0 / BRK"));
+        assert_eq!(cpu.register_a, 0xE0);
+        assert!(cpu.is_carry_set());
+        assert!(!cpu.is_overflow_set());
+
+        cpu.load_and_run(asm!("LDA #$0F / LDX #$FC / AXS #$02 / BRK"));
+        assert_eq!(cpu.register_x, 0x0A);
+        assert!(cpu.is_carry_set());
+    }
+
+    #[test]
+    fn test_kil_jams_the_cpu() {
+        let mut cpu = test_cpu();
+        cpu.load_and_run(asm!("INX / KIL / INX"));
+
+        assert_eq!(cpu.register_x, 1);
+        assert_eq!(cpu.program_counter, 0x0601);
+        assert!(!cpu.step());
+        assert_eq!(cpu.program_counter, 0x0601);
+        assert_eq!(
+            cpu.halted,
+            Some(Fault {
+                pc: 0x0601,
+                kind: FaultKind::Jam
+            })
+        );
+    }
+
+    #[test]
+    fn test_ignored_kil() {
+        let mut cpu = test_cpu();
+        cpu.fault_policy.jam = FaultAction::Ignore;
+        cpu.load_and_run(asm!("INX / KIL / INX / BRK"));
+
+        assert_eq!(cpu.register_x, 2);
+        assert!(!cpu.jammed);
+        assert_eq!(cpu.halted, None);
+    }
+
+    #[test]
+    fn test_stack_pointer_wraps() {
+        let mut cpu = test_cpu();
+        cpu.reset();
+        cpu.program_counter = 0x0600;
+        cpu.stack_counter = 0x00;
+        cpu.register_a = 0x42;
+        cpu.load(asm!("PHA / PLA / PLA"));
+
+        assert!(cpu.step());
+        assert_eq!(cpu.stack_counter, 0xFF);
+        assert_eq!(cpu.mem_read(0x0100), 0x42);
+        assert!(cpu.step());
+        assert_eq!(cpu.stack_counter, 0x00);
+
+        cpu.fault_policy.stack_wrap = FaultAction::Halt;
+        cpu.stack_counter = 0xFF;
+        assert!(!cpu.step());
+        assert!(!cpu.step());
+        assert_eq!(cpu.stack_counter, 0x00);
+        assert_eq!(
+            cpu.halted,
+            Some(Fault {
+                pc: 0x0602,
+                kind: FaultKind::StackUnderflow
+            })
+        );
+    }
+
+    #[test]
+    fn test_fault_break() {
+        let mut cpu = nes_cpu();
+        cpu.fault_policy.ppu_status_write = FaultAction::Break;
+        cpu.program_counter = 0x0600;
+        cpu.load(asm!("STA $2002 / NOP"));
+
+        assert!(cpu.step());
+        assert_eq!(
+            cpu.take_fault_break(),
+            Some(Fault {
+                pc: 0x0600,
+                kind: FaultKind::PpuStatusWrite { data: 0 }
+            })
+        );
+        assert!(cpu.step());
+        assert_eq!(cpu.take_fault_break(), None);
+    }
+
+    #[test]
+    fn test_page_cross_only_costs_reads() {
+        let mut cpu = test_cpu();
+        cpu.reset();
+        cpu.program_counter = 0x0600;
+        cpu.register_x = 1;
+        cpu.load(asm!("LDA $02FF,X / STA $02FF,X / *NOP $02FF,X / INC $02FF,X"));
+
+        let mut cycles = vec![];
+        for _ in 0..4 {
+            let before = cpu.bus.cycles;
+            cpu.step();
+            cycles.push(cpu.bus.cycles - before);
+        }
+        assert_eq!(cycles, vec![5, 5, 5, 7]);
+    }
+
+    // NOPs everywhere, NMI at $9000, reset at $8000 and IRQ/BRK at $A000
+    fn interrupt_cpu() -> NesCPU<'static> {
+        let mut prg_rom = vec![0xEA; 0x8000];
+        prg_rom[0x7FFA..].copy_from_slice(&[0x00, 0x90, 0x00, 0x80, 0x00, 0xA0]);
+        let rom = Rom {
+            prg_rom,
+            chr_rom: vec![0; 0x2000],
+            mapper: 0,
+            screen_mirroring: Mirroring::HORIZONTAL,
+            battery: false,
+        };
+
+        let mut cpu = CPU::new(Bus::new(rom, |_ppu: &NesPPU, _joypad: &mut Joypad| {}).unwrap());
+        cpu.reset();
+        cpu
+    }
+
+    #[test]
+    fn test_brk() {
+        let mut cpu = interrupt_cpu();
+        cpu.program_counter = 0x0600;
+        cpu.load(vec![0x00, 0xFF]);
+        cpu.set_carry_flag(true);
+
+        let cycles = cpu.bus.cycles;
+        cpu.step();
+
+        assert_eq!(cpu.program_counter, 0xA000);
+        assert_eq!(cpu.bus.cycles - cycles, 7);
+        assert_eq!(cpu.stack_pop(), 0b0011_0101);
+        assert_eq!(cpu.stack_pop_u16(), 0x0602);
+    }
+
+    #[test]
+    fn test_irq_is_masked_by_interrupt_disable() {
+        let mut cpu = interrupt_cpu();
+        // the APU frame counter raises its interrupt every 29829 cycles
+        while !cpu.bus.poll_irq_status() {
+            cpu.bus.tick(1);
+        }
+
+        cpu.step();
+        assert_eq!(cpu.program_counter, 0x8001);
+
+        // the NOP polled the line with I still set
+        cpu.set_interrupt_disable_flag(false);
+        cpu.step();
+        assert_eq!(cpu.program_counter, 0x8002);
+
+        let cycles = cpu.bus.cycles;
+        cpu.step();
+
+        // the NOP at $A000 runs right after the 7 cycles of the interrupt
+        assert_eq!(cpu.program_counter, 0xA001);
+        assert_eq!(cpu.bus.cycles - cycles, 9);
+        assert!(cpu.is_interrupt_disable_set());
+        assert_eq!(cpu.stack_pop(), 0b0010_0000);
+        assert_eq!(cpu.stack_pop_u16(), 0x8002);
+    }
+
+    #[test]
+    fn test_nmi_hijacks_brk() {
+        let mut cpu = interrupt_cpu();
+        cpu.mem_write(0x2000, 0b1000_0000);
+        // a few dots before the vblank starts
+        while cpu.bus.ppu().scanline() != 240 || cpu.bus.ppu().dot() < 330 {
+            cpu.bus.tick(1);
+        }
+        cpu.program_counter = 0x0600;
+        cpu.load(vec![0x00, 0xFF]);
+
+        cpu.step();
+
+        assert_eq!(cpu.program_counter, 0x9000);
+        assert!(!cpu.bus.poll_nmi_status());
+        assert_eq!(cpu.stack_pop() & 0b0001_0000, 0b0001_0000);
+    }
+
+    // cycles spent by the instruction at $0600
+    fn cycles_of(cpu: &mut NesCPU, program: &[u8]) -> usize {
+        cpu.program_counter = 0x0600;
+        cpu.load(program.to_vec());
+
+        let before = cpu.bus.cycles;
+        cpu.step();
+        cpu.bus.cycles - before
+    }
+
+    #[test]
+    fn test_bus_accesses_match_the_cycle_table() {
+        const BRANCHES: [u8; 8] = [0x10, 0x30, 0x50, 0x70, 0x90, 0xB0, 0xD0, 0xF0];
+
+        for opcode in 0..=255u8 {
+            let op = &OPS_CODES[opcode as usize];
+            if op.name == "*KIL" || BRANCHES.contains(&opcode) {
+                continue;
+            }
+
+            // operands point to $0200 and the zero page pointers to $0000, so no page is crossed
+            let mut cpu = nes_cpu();
+            cpu.reset();
+            assert_eq!(
+                cycles_of(&mut cpu, &[opcode, 0x00, 0x02]),
+                op.cycles as usize,
+                "{} ${opcode:02X}",
+                op.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_branch_cycles() {
+        let mut cpu = nes_cpu();
+        cpu.reset();
+
+        // BNE not taken, taken and taken into the previous page
+        cpu.set_zero_flag(true);
+        assert_eq!(cycles_of(&mut cpu, &[0xd0, 0x02]), 2);
+        cpu.set_zero_flag(false);
+        assert_eq!(cycles_of(&mut cpu, &[0xd0, 0x02]), 3);
+        assert_eq!(cycles_of(&mut cpu, &[0xd0, 0x80]), 4);
+        assert_eq!(cpu.program_counter, 0x0582);
+    }
+
+    #[test]
+    fn test_jumps_into_their_own_operand() {
+        let mut cpu = test_cpu();
+        cpu.reset();
+
+        // BNE $0601, JMP $0601 and JSR $0601 at $0600, the new PC is the byte after the opcode
+        for program in [&[0xD0, 0xFF][..], &[0x4C, 0x01, 0x06], &[0x20, 0x01, 0x06]] {
+            cpu.program_counter = 0x0600;
+            cpu.bus.load(0x0600, program);
+            cpu.step();
+            assert_eq!(cpu.program_counter, 0x0601, "{program:02X?}");
+        }
+    }
+
+    #[test]
+    fn test_jsr_and_rts_across_ffff() {
+        let mut cpu = test_cpu();
+        cpu.reset();
+
+        // JSR $0600 at $FFFE, its last byte is at $0000
+        cpu.bus.load(0xFFFE, &[0x20, 0x00]);
+        cpu.bus.load(0x0000, &[0x06]);
+        cpu.bus.load(0x0600, &[0x60]);
+        cpu.program_counter = 0xFFFE;
+
+        cpu.step();
+        assert_eq!(cpu.program_counter, 0x0600);
+        assert_eq!(cpu.stack_counter, STACK_RESET - 2);
+
+        // RTS returns past the $0000 that was pushed
+        cpu.step();
+        assert_eq!(cpu.program_counter, 0x0001);
+    }
+
+    #[test]
+    fn test_read_modify_write_writes_twice() {
+        let mut cpu = nes_cpu();
+        cpu.reset();
+
+        // INC $2006, the unchanged value (0) goes to the high byte and the result to the low byte
+        cycles_of(&mut cpu, &[0xee, 0x06, 0x20]);
+        assert_eq!(cpu.bus.ppu().vram_addr(), 0x0001);
+    }
+
+    #[test]
+    fn test_indexed_dummy_read() {
+        let mut cpu = nes_cpu();
+        cpu.reset();
+        cpu.register_x = 0x08;
+        cpu.bus.watchpoints.add(Watchpoint {
+            space: Space::Cpu,
+            start: 0x3F02,
+            end: 0x3F02,
+            read: true,
+            write: false,
+        });
+
+        // LDA $3FF0,X stays in the page
+        cycles_of(&mut cpu, &[0xbd, 0xf0, 0x3f]);
+        assert!(cpu.bus.watchpoints.take_hit().is_none());
+
+        // LDA $3FFA,X reads $3F02, a mirror of PPUSTATUS, before $4002
+        cycles_of(&mut cpu, &[0xbd, 0xfa, 0x3f]);
+        assert_eq!(cpu.bus.watchpoints.take_hit().map(|hit| hit.addr), Some(0x3F02));
+    }
+
+    #[test]
+    fn test_oam_dma_halts_the_cpu() {
+        let mut cpu = nes_cpu();
+        cpu.reset();
+
+        // STA $4014
+        let cycles = cycles_of(&mut cpu, &[0x8d, 0x14, 0x40]);
+        assert!(cycles == 4 + 513 || cycles == 4 + 514);
+    }
+
+    #[test]
+    fn test_flat_memory_interrupt_lines() {
+        let mut cpu = test_cpu();
+        cpu.bus.load(0xFFFA, &[0x00, 0x90, 0x00, 0x06, 0x00, 0xA0]);
+        cpu.reset();
+        cpu.bus.load(0x0600, &asm!("CLI / NOP"));
+        cpu.bus.load(0x9000, &[0xEA, 0xEA]);
+        cpu.bus.load(0xA000, &[0xEA]);
+
+        // CLI and the NOP after it, then the IRQ is taken before the NOP of the handler
+        cpu.bus.irq = true;
+        cpu.step();
+        cpu.step();
+        cpu.step();
+        assert_eq!(cpu.program_counter, 0xA001);
+        assert_eq!(cpu.bus.cycles, 7 + 2 + 2 + 7 + 2);
+
+        // the NMI is taken once, even with I set
+        cpu.bus.nmi = true;
+        cpu.step();
+        assert_eq!(cpu.program_counter, 0x9001);
+        cpu.step();
+        assert_eq!(cpu.program_counter, 0x9002);
+    }
+
+    fn irq_cpu(program: &str) -> CPU<FlatMemory> {
+        let mut cpu = test_cpu();
+        cpu.bus.load(0xFFFC, &[0x00, 0x06, 0x00, 0xA0]);
+        cpu.reset();
+        cpu.bus.load(0x0600, &asm!(program));
+        cpu.bus.load(0xA000, &[0xEA]);
+        cpu
+    }
+
+    #[test]
+    fn test_plp_delays_the_irq() {
+        let mut cpu = irq_cpu("LDA #$00 / PHA / PLP / NOP / NOP");
+        cpu.bus.irq = true;
+        for _ in 0..4 {
+            cpu.step();
+        }
+        // the NOP after PLP ran with I clear
+        assert_eq!(cpu.program_counter, 0x0605);
+
+        cpu.step();
+        assert_eq!(cpu.program_counter, 0xA001);
+        assert_eq!(cpu.stack_pop() & 0b0000_0100, 0);
+        assert_eq!(cpu.stack_pop_u16(), 0x0605);
+    }
+
+    #[test]
+    fn test_sei_lets_a_pending_irq_through() {
+        let mut cpu = irq_cpu("CLI / NOP / SEI / NOP");
+        cpu.step();
+        cpu.step();
+        cpu.bus.irq = true;
+        cpu.step();
+
+        // taken after SEI, with I set in the pushed status
+        cpu.step();
+        assert_eq!(cpu.program_counter, 0xA001);
+        assert_eq!(cpu.stack_pop() & 0b0000_0100, 0b0000_0100);
+        assert_eq!(cpu.stack_pop_u16(), 0x0603);
+    }
+
+    #[test]
+    fn test_rti_unmasks_right_away() {
+        let mut cpu = irq_cpu("CLI / NOP");
+        cpu.bus.load(0xA000, &[0x40]);
+        cpu.bus.irq = true;
+        cpu.step();
+        cpu.step();
+
+        // the IRQ with the RTI of its handler, which restores a clear I before the line is polled
+        cpu.step();
+        assert_eq!(cpu.program_counter, 0x0602);
+        cpu.step();
+        assert_eq!(cpu.taken_interrupt, Some(0xFFFE));
+    }
+
+    #[test]
+    fn test_decimal_mode() {
+        let mut cpu = test_cpu();
+        cpu.decimal_mode = true;
+        cpu.load_and_run(asm!("SED / CLC / LDA #$58 / ADC #$46 / BRK"));
+        assert_eq!(cpu.register_a, 0x04);
+        assert!(cpu.is_carry_set());
+
+        cpu.load_and_run(asm!("SED / SEC / LDA #$12 / SBC #$21 / BRK"));
+        assert_eq!(cpu.register_a, 0x91);
+        assert!(!cpu.is_carry_set());
+
+        // Z comes from the binary sum
+        cpu.load_and_run(asm!("SED / CLC / LDA #$99 / ADC #$01 / BRK"));
+        assert_eq!(cpu.register_a, 0x00);
+        assert!(cpu.is_carry_set());
+        assert!(!cpu.is_zero_set());
+    }
+
+    #[test]
+    fn test_nes_ignores_decimal_flag() {
+        let mut cpu = nes_cpu();
+        cpu.load_and_run(asm!("SED / CLC / LDA #$58 / ADC #$46 / BRK"));
+        assert_eq!(cpu.register_a, 0x9E);
+        assert!(cpu.is_decimal_mode_set());
+    }
+}
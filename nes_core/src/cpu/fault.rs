@@ -0,0 +1,156 @@
+use std::{fmt, str::FromStr};
+
+// The conditions a game isn't supposed to run into, the hardware goes on with most of them
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum FaultKind {
+    // one of the KIL opcodes, which lock up the 6502
+    Jam,
+    // a write to 0x8000 - 0xFFFF on a cartridge without registers there
+    RomWrite { addr: u16, data: u8 },
+    PpuStatusWrite { data: u8 },
+    // a push with the stack pointer at 0x00 or a pull with it at 0xFF
+    StackOverflow,
+    StackUnderflow,
+}
+
+// A fault with the address of the instruction that caused it
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Fault {
+    pub pc: u16,
+    pub kind: FaultKind,
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            FaultKind::Jam => write!(f, "CPU jammed at ${:04X}", self.pc),
+            FaultKind::RomWrite { addr, data } => write!(f, "Write of ${data:02X} to ROM at ${addr:04X}"),
+            FaultKind::PpuStatusWrite { data } => write!(f, "Write of ${data:02X} to the PPU status"),
+            FaultKind::StackOverflow => write!(f, "Stack overflow"),
+            FaultKind::StackUnderflow => write!(f, "Stack underflow"),
+        }?;
+        if self.kind != FaultKind::Jam {
+            write!(f, " by the instruction at ${:04X}", self.pc)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum FaultAction {
+    // go on like the hardware does, a jam skips the opcode
+    Ignore,
+    // go on and write the fault to stderr
+    Log,
+    // also stop in the debugger, when there is one
+    Break,
+    // stop the CPU, `CPU::halted` tells why
+    Halt,
+}
+
+impl FromStr for FaultAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "ignore" => Ok(FaultAction::Ignore),
+            "log" => Ok(FaultAction::Log),
+            "break" => Ok(FaultAction::Break),
+            "halt" => Ok(FaultAction::Halt),
+            _ => Err(format!("Unknown fault action {s}, expected ignore, log, break or halt")),
+        }
+    }
+}
+
+const FAULTS: &str = "jam, rom-write, ppu-status-write, stack-wrap or all";
+
+// What the CPU does on each fault. By default a jam halts it like on the hardware, the writes are logged
+// and the stack wraps around silently
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct FaultPolicy {
+    pub jam: FaultAction,
+    pub rom_write: FaultAction,
+    pub ppu_status_write: FaultAction,
+    pub stack_wrap: FaultAction,
+}
+
+impl FaultPolicy {
+    pub fn new() -> Self {
+        FaultPolicy {
+            jam: FaultAction::Halt,
+            rom_write: FaultAction::Log,
+            ppu_status_write: FaultAction::Log,
+            stack_wrap: FaultAction::Ignore,
+        }
+    }
+
+    pub fn action(&self, kind: &FaultKind) -> FaultAction {
+        match kind {
+            FaultKind::Jam => self.jam,
+            FaultKind::RomWrite { .. } => self.rom_write,
+            FaultKind::PpuStatusWrite { .. } => self.ppu_status_write,
+            FaultKind::StackOverflow | FaultKind::StackUnderflow => self.stack_wrap,
+        }
+    }
+
+    // A `<fault>=<action>` setting, like `rom-write=halt`
+    pub fn set(&mut self, setting: &str) -> Result<(), String> {
+        let Some((fault, action)) = setting.split_once('=') else {
+            return Err(format!("Expected <fault>=<action> instead of {setting}"));
+        };
+        let action = action.trim().parse()?;
+
+        match fault.trim().to_ascii_lowercase().as_str() {
+            "jam" => self.jam = action,
+            "rom-write" => self.rom_write = action,
+            "ppu-status-write" => self.ppu_status_write = action,
+            "stack-wrap" => self.stack_wrap = action,
+            "all" => {
+                *self = FaultPolicy {
+                    jam: action,
+                    rom_write: action,
+                    ppu_status_write: action,
+                    stack_wrap: action,
+                }
+            }
+            fault => return Err(format!("Unknown fault {fault}, expected {FAULTS}")),
+        }
+        Ok(())
+    }
+}
+
+impl Default for FaultPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FromStr for FaultPolicy {
+    type Err = String;
+
+    // Comma separated settings over the default policy
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut policy = FaultPolicy::new();
+        for setting in s.split(',').filter(|setting| !setting.trim().is_empty()) {
+            policy.set(setting)?;
+        }
+        Ok(policy)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_policy() {
+        let policy: FaultPolicy = "all=log, jam=break,ROM-WRITE=Halt".parse().unwrap();
+        assert_eq!(policy.jam, FaultAction::Break);
+        assert_eq!(policy.rom_write, FaultAction::Halt);
+        assert_eq!(policy.action(&FaultKind::StackUnderflow), FaultAction::Log);
+
+        assert!("jam".parse::<FaultPolicy>().is_err());
+        assert!("jam=stop".parse::<FaultPolicy>().is_err());
+        assert!("ppu=log".parse::<FaultPolicy>().is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::cpu::cpu::CPU;
+use crate::cpu::{cpu::CPU, memory::CpuBus};
 
 pub const CARRY: u8                = 0b0000_0001;
 pub const ZERO: u8                 = 0b0000_0010;
@@ -9,7 +9,7 @@ pub const BREAK2: u8               = 0b0010_0000;
 pub const OVERFLOW: u8             = 0b0100_0000;
 pub const NEGATIVE: u8             = 0b1000_0000;
 
-impl<'a> CPU<'a> {
+impl<B: CpuBus> CPU<B> {
     pub fn update_zero_and_negative_flags(&mut self, result: u8) {
         self.set_zero_flag(result == 0);
         self.set_negative_flag(result & 0b1000_0000 != 0);
@@ -67,12 +67,12 @@ impl<'a> CPU<'a> {
     // pub fn is_break_set(&self) -> bool {
     //     self.status & BREAK != 0
     // }
-    // pub fn is_decimal_mode_set(&self) -> bool {
-    //     self.status & DECIMAL_MODE != 0
-    // }
-    // pub fn is_interrupt_disable_set(&self) -> bool {
-    //     self.status & INTERRUPT_DISABLE != 0
-    // }
+    pub fn is_decimal_mode_set(&self) -> bool {
+        self.status & DECIMAL_MODE != 0
+    }
+    pub fn is_interrupt_disable_set(&self) -> bool {
+        self.status & INTERRUPT_DISABLE != 0
+    }
     pub fn is_zero_set(&self) -> bool {
         self.status & ZERO != 0
     }
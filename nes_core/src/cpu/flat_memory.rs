@@ -0,0 +1,61 @@
+use super::memory::{CpuBus, Mem};
+
+// A 6502 on its own: 64 KiB of RAM, no other chips to clock and interrupt lines driven by hand.
+// Enough for the CPU test suites and for tools that don't care about the NES
+pub struct FlatMemory {
+    pub data: Box<[u8; 0x10000]>,
+    pub cycles: usize,
+    pub nmi: bool,
+    pub irq: bool,
+}
+
+impl FlatMemory {
+    pub fn new() -> Self {
+        FlatMemory {
+            data: Box::new([0; 0x10000]),
+            cycles: 0,
+            nmi: false,
+            irq: false,
+        }
+    }
+
+    pub fn load(&mut self, addr: u16, program: &[u8]) {
+        for (i, &byte) in program.iter().enumerate() {
+            self.data[addr.wrapping_add(i as u16) as usize] = byte;
+        }
+    }
+}
+
+impl Default for FlatMemory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mem for FlatMemory {
+    fn mem_read(&mut self, addr: u16) -> u8 {
+        self.data[addr as usize]
+    }
+
+    fn mem_write(&mut self, addr: u16, data: u8) {
+        self.data[addr as usize] = data;
+    }
+}
+
+impl CpuBus for FlatMemory {
+    fn tick(&mut self, cycles: u8) {
+        self.cycles += cycles as usize;
+    }
+
+    fn poll_nmi_status(&mut self) -> bool {
+        std::mem::take(&mut self.nmi)
+    }
+
+    fn poll_irq_status(&self) -> bool {
+        self.irq
+    }
+
+    fn peek(&self, addr: u16) -> u8 {
+        self.data[addr as usize]
+    }
+}
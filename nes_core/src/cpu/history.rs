@@ -0,0 +1,100 @@
+// An instruction as the CPU saw it right before running it
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Executed {
+    pub pc: u16,
+    // the opcode and its operand bytes, the ones after the instruction are 0
+    pub bytes: [u8; 3],
+    pub register_a: u8,
+    pub register_x: u8,
+    pub register_y: u8,
+    pub status: u8,
+    pub stack_counter: u8,
+    pub scanline: u16,
+    pub dot: u16,
+}
+
+// Ring buffer of the last executed instructions, for the crash reports. A capacity of 0 turns it off
+pub struct History {
+    entries: Vec<Executed>,
+    capacity: usize,
+    // oldest entry once the buffer is full
+    next: usize,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        History {
+            entries: Vec::with_capacity(capacity),
+            capacity,
+            next: 0,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    pub fn push(&mut self, executed: Executed) {
+        if self.entries.len() < self.capacity {
+            self.entries.push(executed);
+        } else if self.capacity > 0 {
+            self.entries[self.next] = executed;
+            self.next += 1;
+            if self.next == self.capacity {
+                self.next = 0;
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.next = 0;
+    }
+
+    // Oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &Executed> {
+        self.entries[self.next..].iter().chain(&self.entries[..self.next])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn executed(pc: u16) -> Executed {
+        Executed {
+            pc,
+            bytes: [0xEA, 0, 0],
+            register_a: 0,
+            register_x: 0,
+            register_y: 0,
+            status: 0,
+            stack_counter: 0xFD,
+            scanline: 0,
+            dot: 0,
+        }
+    }
+
+    #[test]
+    fn test_keeps_the_last_entries() {
+        let mut history = History::new(3);
+        for pc in 0..5 {
+            history.push(executed(pc));
+        }
+
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.iter().map(|e| e.pc).collect::<Vec<_>>(), vec![2, 3, 4]);
+
+        let mut off = History::new(0);
+        off.push(executed(0));
+        assert!(off.is_empty());
+    }
+}
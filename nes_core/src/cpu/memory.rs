@@ -0,0 +1,42 @@
+use super::fault::FaultKind;
+
+pub trait Mem {
+    fn mem_read(&mut self, addr: u16) -> u8;
+
+    fn mem_write(&mut self, addr: u16, data: u8);
+
+    fn mem_read_u16(&mut self, pos: u16) -> u16 {
+        (self.mem_read(pos.wrapping_add(1)) as u16).swap_bytes() | (self.mem_read(pos) as u16)
+    }
+
+    fn mem_write_u16(&mut self, pos: u16, data: u16) {
+        self.mem_write(pos, (data & 0xFF) as u8);
+        self.mem_write(pos.wrapping_add(1), (data >> 8) as u8);
+    }
+}
+
+// Everything the CPU needs from the machine around it: the memory, a clock for the other chips and the
+// interrupt lines. The NES bus is one, `FlatMemory` is a bare 6502 with 64 KiB of RAM
+pub trait CpuBus: Mem {
+    // called before every access of the CPU, and for the cycles of the reset sequence
+    fn tick(&mut self, cycles: u8);
+
+    // the NMI line is edge triggered, polling takes the pending one
+    fn poll_nmi_status(&mut self) -> bool;
+
+    // the IRQ line is level triggered, it stays asserted until the device is acknowledged
+    fn poll_irq_status(&self) -> bool;
+
+    // Reads memory without side effects, for the tracing tools
+    fn peek(&self, addr: u16) -> u8;
+
+    // Scanline and dot of the PPU, for the instruction history. A bus without one stays at 0
+    fn ppu_position(&self) -> (u16, u16) {
+        (0, 0)
+    }
+
+    // A fault of the devices on the bus since the last call, like a write to ROM
+    fn take_fault(&mut self) -> Option<FaultKind> {
+        None
+    }
+}
@@ -0,0 +1,23 @@
+mod cpu;
+mod addrssing_modes;
+mod operations;
+mod unofficial;
+mod opscodes;
+mod flags;
+mod memory;
+mod flat_memory;
+mod trace;
+mod fault;
+mod history;
+mod assembler;
+
+pub use cpu::CPU;
+pub(crate) use cpu::NesCPU;
+pub use memory::{CpuBus, Mem};
+pub use flat_memory::FlatMemory;
+pub use opscodes::{Handler, OpCode, OPS_CODES};
+pub use addrssing_modes::AddressingMode;
+pub(crate) use trace::trace;
+pub use fault::{Fault, FaultAction, FaultKind, FaultPolicy};
+pub use history::{Executed, History};
+pub use assembler::assemble;
@@ -1,7 +1,56 @@
-use super::{addrssing_modes::AddressingMode, cpu::CPU, Mem};
+use super::{addrssing_modes::AddressingMode, cpu::CPU, memory::CpuBus};
 
-impl<'a> CPU<'a> {
+impl<B: CpuBus> CPU<B> {
     pub fn add_to_a(&mut self, data: u8) {
+        if self.decimal_mode && self.is_decimal_mode_set() {
+            self.add_to_a_decimal(data);
+        } else {
+            self.add_to_a_binary(data);
+        }
+    }
+
+    // SBC is an ADC of the complement, in decimal mode the flags still come from that binary subtraction
+    pub fn subtract_from_a(&mut self, data: u8) {
+        let a = self.register_a;
+        let borrow = !self.is_carry_set() as i16;
+        self.add_to_a_binary(!data);
+
+        if self.decimal_mode && self.is_decimal_mode_set() {
+            let mut lo = (a & 0x0F) as i16 - (data & 0x0F) as i16 - borrow;
+            if lo < 0 {
+                lo = ((lo - 0x06) & 0x0F) - 0x10;
+            }
+            let mut result = (a & 0xF0) as i16 - (data & 0xF0) as i16 + lo;
+            if result < 0 {
+                result -= 0x60;
+            }
+            self.register_a = result as u8;
+        }
+    }
+
+    // Like the NMOS 6502, Z comes from the binary sum and N and V from the sum before the high digit is adjusted
+    fn add_to_a_decimal(&mut self, data: u8) {
+        let a = self.register_a;
+        let carry = self.is_carry_set() as u16;
+
+        let mut lo = (a & 0x0F) as u16 + (data & 0x0F) as u16 + carry;
+        if lo >= 0x0A {
+            lo = ((lo + 0x06) & 0x0F) + 0x10;
+        }
+        let mut sum = (a & 0xF0) as u16 + (data & 0xF0) as u16 + lo;
+
+        self.set_zero_flag(a.wrapping_add(data).wrapping_add(carry as u8) == 0);
+        self.set_negative_flag(sum & 0x80 != 0);
+        self.set_overflow_flag((data ^ sum as u8) & (a ^ sum as u8) & 0x80 != 0);
+
+        if sum >= 0xA0 {
+            sum += 0x60;
+        }
+        self.set_carry_flag(sum > 0xFF);
+        self.register_a = sum as u8;
+    }
+
+    fn add_to_a_binary(&mut self, data: u8) {
         let sum = (self.register_a as u16) + self.is_carry_set() as u16 + data as u16;
 
         self.set_carry_flag(sum > 0xFF);
@@ -14,28 +63,44 @@ impl<'a> CPU<'a> {
         self.update_zero_and_negative_flags(self.register_a);
     }
 
-    fn branch(&mut self) {
-        self.bus.tick(1);
+    // Read-modify-write instructions write the value back unchanged while they compute the result
+    pub fn modify(&mut self, addr: u16, f: impl FnOnce(&mut Self, u8) -> u8) -> u8 {
+        let data = self.read(addr);
+        self.write(addr, data);
 
-        let mem_read = self.mem_read(self.program_counter) as i8 as u16;
-        let jump = self.program_counter.wrapping_add(1).wrapping_add(mem_read);
+        let result = f(self, data);
+        self.write(addr, result);
+        result
+    }
 
-        if self.program_counter.wrapping_add(1) & 0xFF00 != jump & 0xFF00 {
-            self.bus.tick(1);
+    // A taken branch reads the next opcode while it adds the offset, and the opcode in the wrong page
+    // while it fixes the high byte when crossing one
+    fn branch(&mut self, condition: bool) {
+        let offset = self.read(self.program_counter) as i8 as u16;
+        if !condition {
+            return;
         }
 
-        self.program_counter = jump;
+        let next = self.program_counter.wrapping_add(1);
+        let jump = next.wrapping_add(offset);
+        self.read(next);
+
+        if next & 0xFF00 != jump & 0xFF00 {
+            self.read((next & 0xFF00) | (jump & 0x00FF));
+        }
+
+        self.jump(jump);
     }
 
     pub fn adc(&mut self, mode: &AddressingMode) {
         let addr = self.get_operand_address(mode);
-        let data = self.mem_read(addr);
+        let data = self.read(addr);
         self.add_to_a(data);
     }
 
     pub fn and(&mut self, mode: &AddressingMode) {
         let addr = self.get_operand_address(mode);
-        let data = self.mem_read(addr);
+        let data = self.read(addr);
 
         self.register_a &= data;
         self.update_zero_and_negative_flags(self.register_a);
@@ -52,68 +117,50 @@ impl<'a> CPU<'a> {
             return;
         }
 
-        let addr = self.get_operand_address(mode);
-        let mut data = self.mem_read(addr);
-
-        self.set_carry_flag(data & 0b1000_0000 != 0);
-
-        data <<= 1;
-        self.mem_write(addr, data);
+        let addr = self.get_store_address(mode);
+        let data = self.modify(addr, |cpu, data| {
+            cpu.set_carry_flag(data & 0b1000_0000 != 0);
+            data << 1
+        });
 
         self.update_zero_and_negative_flags(data);
     }
 
     pub fn bcs(&mut self) {
-        if self.is_carry_set() {
-            self.branch();
-        }
+        self.branch(self.is_carry_set());
     }
 
     pub fn bcc(&mut self) {
-        if !self.is_carry_set() {
-            self.branch();
-        }
+        self.branch(!self.is_carry_set());
     }
 
     pub fn beq(&mut self) {
-        if self.is_zero_set() {
-            self.branch();
-        }
+        self.branch(self.is_zero_set());
     }
 
     pub fn bne(&mut self) {
-        if !self.is_zero_set() {
-            self.branch();
-        }
+        self.branch(!self.is_zero_set());
     }
 
     pub fn bmi(&mut self) {
-        if self.is_negative_set() {
-            self.branch();
-        }
+        self.branch(self.is_negative_set());
     }
 
     pub fn bpl(&mut self) {
-        if !self.is_negative_set() {
-            self.branch();
-        }
+        self.branch(!self.is_negative_set());
     }
 
     pub fn bvs(&mut self) {
-        if self.is_overflow_set() {
-            self.branch();
-        }
+        self.branch(self.is_overflow_set());
     }
 
     pub fn bvc(&mut self) {
-        if !self.is_overflow_set() {
-            self.branch();
-        }
+        self.branch(!self.is_overflow_set());
     }
 
     pub fn bit(&mut self, mode: &AddressingMode) {
         let addr = self.get_operand_address(mode);
-        let data = self.mem_read(addr);
+        let data = self.read(addr);
 
         self.set_zero_flag(data & self.register_a == 0);
         self.set_overflow_flag(data & 0b0100_0000 != 0);
@@ -142,7 +189,7 @@ impl<'a> CPU<'a> {
 
     pub fn cmp(&mut self, mode: &AddressingMode) {
         let addr = self.get_operand_address(mode);
-        let data = self.mem_read(addr);
+        let data = self.read(addr);
 
         self.set_carry_flag(self.register_a >= data);
         self.update_zero_and_negative_flags(self.register_a.wrapping_sub(data));
@@ -150,7 +197,7 @@ impl<'a> CPU<'a> {
 
     pub fn cpx(&mut self, mode: &AddressingMode) {
         let addr = self.get_operand_address(mode);
-        let data = self.mem_read(addr);
+        let data = self.read(addr);
 
         self.set_carry_flag(self.register_x >= data);
         self.update_zero_and_negative_flags(self.register_x.wrapping_sub(data));
@@ -158,17 +205,16 @@ impl<'a> CPU<'a> {
 
     pub fn cpy(&mut self, mode: &AddressingMode) {
         let addr = self.get_operand_address(mode);
-        let data = self.mem_read(addr);
+        let data = self.read(addr);
 
         self.set_carry_flag(self.register_y >= data);
         self.update_zero_and_negative_flags(self.register_y.wrapping_sub(data));
     }
 
     pub fn dec(&mut self, mode: &AddressingMode) {
-        let addr = self.get_operand_address(mode);
-        let data = self.mem_read(addr).wrapping_sub(1);
+        let addr = self.get_store_address(mode);
+        let data = self.modify(addr, |_, data| data.wrapping_sub(1));
 
-        self.mem_write(addr, data);
         self.update_zero_and_negative_flags(data);
     }
 
@@ -184,17 +230,16 @@ impl<'a> CPU<'a> {
 
     pub fn eor(&mut self, mode: &AddressingMode) {
         let addr = self.get_operand_address(mode);
-        let data = self.mem_read(addr);
+        let data = self.read(addr);
 
         self.register_a ^= data;
         self.update_zero_and_negative_flags(self.register_a);
     }
 
     pub fn inc(&mut self, mode: &AddressingMode) {
-        let addr = self.get_operand_address(mode);
-        let data = self.mem_read(addr).wrapping_add(1);
+        let addr = self.get_store_address(mode);
+        let data = self.modify(addr, |_, data| data.wrapping_add(1));
 
-        self.mem_write(addr, data);
         self.update_zero_and_negative_flags(data);
     }
 
@@ -209,29 +254,34 @@ impl<'a> CPU<'a> {
     }
 
     pub fn jmp(&mut self, mode: &AddressingMode) {
-        let mut addr = self.mem_read_u16(self.program_counter);
+        let mut addr = self.read_u16(self.program_counter);
 
         if matches!(mode, AddressingMode::Indirect) {
             addr = if addr & 0x00FF == 0x00FF {
-                let lo = self.mem_read(addr);
-                let hi = self.mem_read(addr & 0xFF00);
+                let lo = self.read(addr);
+                let hi = self.read(addr & 0xFF00);
                 (hi as u16) << 8 | (lo as u16)
             } else {
-                self.mem_read_u16(addr)
+                self.read_u16(addr)
             };
         }
 
-        self.program_counter = addr;
+        self.jump(addr);
     }
 
+    // The high byte of the target is read last, after the return address was pushed
     pub fn jsr(&mut self) {
-        self.stack_push_u16(self.program_counter + 1);
-        self.program_counter = self.mem_read_u16(self.program_counter);
+        let lo = self.read(self.program_counter);
+        self.stack_dummy_read();
+        self.stack_push_u16(self.program_counter.wrapping_add(1));
+        let hi = self.read(self.program_counter.wrapping_add(1));
+
+        self.jump((hi as u16) << 8 | lo as u16);
     }
 
     pub fn lda(&mut self, mode: &AddressingMode) {
         let addr = self.get_operand_address(mode);
-        let data = self.mem_read(addr);
+        let data = self.read(addr);
 
         self.register_a = data;
         self.update_zero_and_negative_flags(self.register_a);
@@ -239,7 +289,7 @@ impl<'a> CPU<'a> {
 
     pub fn ldx(&mut self, mode: &AddressingMode) {
         let addr = self.get_operand_address(mode);
-        let data = self.mem_read(addr);
+        let data = self.read(addr);
 
         self.register_x = data;
         self.update_zero_and_negative_flags(self.register_x);
@@ -247,7 +297,7 @@ impl<'a> CPU<'a> {
 
     pub fn ldy(&mut self, mode: &AddressingMode) {
         let addr = self.get_operand_address(mode);
-        let data = self.mem_read(addr);
+        let data = self.read(addr);
 
         self.register_y = data;
         self.update_zero_and_negative_flags(self.register_y);
@@ -264,13 +314,11 @@ impl<'a> CPU<'a> {
             return;
         }
 
-        let addr = self.get_operand_address(mode);
-        let mut data = self.mem_read(addr);
-
-        self.set_carry_flag(data & 0b0000_0001 != 0);
-
-        data >>= 1;
-        self.mem_write(addr, data);
+        let addr = self.get_store_address(mode);
+        let data = self.modify(addr, |cpu, data| {
+            cpu.set_carry_flag(data & 0b0000_0001 != 0);
+            data >> 1
+        });
 
         self.update_zero_and_negative_flags(data);
     }
@@ -281,7 +329,7 @@ impl<'a> CPU<'a> {
 
     pub fn ora(&mut self, mode: &AddressingMode) {
         let addr = self.get_operand_address(mode);
-        let data = self.mem_read(addr);
+        let data = self.read(addr);
 
         self.register_a |= data as u8;
 
@@ -297,11 +345,13 @@ impl<'a> CPU<'a> {
     }
 
     pub fn pla(&mut self) {
+        self.stack_dummy_read();
         self.register_a = self.stack_pop();
         self.update_zero_and_negative_flags(self.register_a);
     }
 
     pub fn plp(&mut self) {
+        self.stack_dummy_read();
         self.status = self.stack_pop();
         self.set_break_flag(false);
         self.set_break2_flag(true);
@@ -321,14 +371,11 @@ impl<'a> CPU<'a> {
             return;
         }
 
-        let addr = self.get_operand_address(mode);
-        let mut data = self.mem_read(addr);
-
-        self.set_carry_flag(data & 0b1000_0000 != 0);
-
-        data <<= 1;
-        data |= m;
-        self.mem_write(addr, data);
+        let addr = self.get_store_address(mode);
+        let data = self.modify(addr, |cpu, data| {
+            cpu.set_carry_flag(data & 0b1000_0000 != 0);
+            data << 1 | m
+        });
 
         self.update_zero_and_negative_flags(data);
     }
@@ -347,34 +394,37 @@ impl<'a> CPU<'a> {
             return;
         }
 
-        let addr = self.get_operand_address(mode);
-        let mut data = self.mem_read(addr);
-
-        self.set_carry_flag(data & 0b0000_0001 != 0);
-
-        data >>= 1;
-        data |= m;
-        self.mem_write(addr, data);
+        let addr = self.get_store_address(mode);
+        let data = self.modify(addr, |cpu, data| {
+            cpu.set_carry_flag(data & 0b0000_0001 != 0);
+            data >> 1 | m
+        });
 
         self.update_zero_and_negative_flags(data);
     }
 
     pub fn rti(&mut self) {
+        self.stack_dummy_read();
         self.status = self.stack_pop();
         self.set_break_flag(false);
         self.set_break2_flag(true);
-        self.program_counter = self.stack_pop_u16();
+        let addr = self.stack_pop_u16();
+        self.jump(addr);
     }
 
+    // Returns to the last byte of the JSR, then spends a cycle reading it while moving past it
     pub fn rts(&mut self) {
-        self.program_counter = self.stack_pop_u16() + 1;
+        self.stack_dummy_read();
+        let addr = self.stack_pop_u16();
+        self.read(addr);
+        self.jump(addr.wrapping_add(1));
     }
 
     pub fn sbc(&mut self, mode: &AddressingMode) {
         let addr = self.get_operand_address(mode);
-        let data = self.mem_read(addr);
+        let data = self.read(addr);
 
-        self.add_to_a(!data);
+        self.subtract_from_a(data);
     }
 
     pub fn sec(&mut self) {
@@ -390,18 +440,18 @@ impl<'a> CPU<'a> {
     }
 
     pub fn sta(&mut self, mode: &AddressingMode) {
-        let addr = self.get_operand_address(mode);
-        self.mem_write(addr, self.register_a);
+        let addr = self.get_store_address(mode);
+        self.write(addr, self.register_a);
     }
 
     pub fn stx(&mut self, mode: &AddressingMode) {
-        let addr = self.get_operand_address(mode);
-        self.mem_write(addr, self.register_x);
+        let addr = self.get_store_address(mode);
+        self.write(addr, self.register_x);
     }
 
     pub fn sty(&mut self, mode: &AddressingMode) {
-        let addr = self.get_operand_address(mode);
-        self.mem_write(addr, self.register_y);
+        let addr = self.get_store_address(mode);
+        self.write(addr, self.register_y);
     }
 
     pub fn tax(&mut self) {
@@ -0,0 +1,390 @@
+use super::{
+    addrssing_modes::AddressingMode::{self, *},
+    cpu::CPU,
+    flat_memory::FlatMemory,
+    memory::CpuBus,
+};
+
+pub type Handler<B> = fn(&mut CPU<B>, &AddressingMode);
+
+#[derive(Clone, Copy)]
+pub struct OpCode {
+    pub name: &'static str,
+    pub mode: AddressingMode,
+    pub len: u8,
+    // without page crossings or taken branches. The CPU doesn't use it, the cycles come from the bus
+    // accesses the handler makes
+    pub cycles: u8,
+}
+
+impl OpCode {
+    pub const fn new(name: &'static str, mode: AddressingMode, len: u8, cycles: u8) -> Self {
+        Self {
+            name,
+            mode,
+            len,
+            cycles,
+        }
+    }
+}
+
+// The tools only need the names and addressing modes, those are the same whatever the bus is
+pub static OPS_CODES: [OpCode; 256] = table::<FlatMemory>().0;
+
+impl<B: CpuBus> CPU<B> {
+    // one copy of the handlers per bus, so the calls into it are never dynamic
+    pub const HANDLERS: [Handler<B>; 256] = table::<B>().1;
+}
+
+// Indexed by the opcode byte and built at compile time, so decoding an instruction is a single array access
+#[rustfmt::skip]
+const fn table<B: CpuBus>() -> ([OpCode; 256], [Handler<B>; 256]) {
+    // replaced below, `test_every_opcode_is_defined` makes sure none is left
+    let mut table = [OpCode::new("???", NoneAddressing, 1, 0); 256];
+    let undefined: Handler<B> = |_, _| unreachable!();
+    let mut handlers = [undefined; 256];
+
+    // `adc(mode)` calls `cpu.adc(mode)`, `clc()` is for the instructions without an operand
+    macro_rules! op {
+        ($opcode:literal, $name:literal, $mode:ident, $len:literal, $cycles:literal, $call:ident(mode)) => {
+            table[$opcode] = OpCode::new($name, $mode, $len, $cycles);
+            handlers[$opcode] = |cpu, mode| cpu.$call(mode);
+        };
+        ($opcode:literal, $name:literal, $mode:ident, $len:literal, $cycles:literal, $call:ident()) => {
+            table[$opcode] = OpCode::new($name, $mode, $len, $cycles);
+            handlers[$opcode] = |cpu, _| cpu.$call();
+        };
+    }
+
+    op!(0x00, "BRK", NoneAddressing, 1, 7, brk());
+
+    op!(0x69, "ADC", Immediate,  2, 2, adc(mode));
+    op!(0x65, "ADC", ZeroPage,   2, 3, adc(mode));
+    op!(0x75, "ADC", ZeroPage_X, 2, 4, adc(mode));
+    op!(0x6D, "ADC", Absolute,   3, 4, adc(mode));
+    op!(0x7D, "ADC", Absolute_X, 3, 4, adc(mode)); // +1 if page crossed
+    op!(0x79, "ADC", Absolute_Y, 3, 4, adc(mode)); // +1 if page crossed
+    op!(0x61, "ADC", Indirect_X, 2, 6, adc(mode));
+    op!(0x71, "ADC", Indirect_Y, 2, 5, adc(mode)); // +1 if page crossed
+
+    op!(0x29, "AND", Immediate,  2, 2, and(mode));
+    op!(0x25, "AND", ZeroPage,   2, 3, and(mode));
+    op!(0x35, "AND", ZeroPage_X, 2, 4, and(mode));
+    op!(0x2D, "AND", Absolute,   3, 4, and(mode));
+    op!(0x3D, "AND", Absolute_X, 3, 4, and(mode)); // +1 if page crossed
+    op!(0x39, "AND", Absolute_Y, 3, 4, and(mode)); // +1 if page crossed
+    op!(0x21, "AND", Indirect_X, 2, 6, and(mode));
+    op!(0x31, "AND", Indirect_Y, 2, 5, and(mode)); // +1 if page crossed
+
+    op!(0x0A, "ASL", Accumulator, 1, 2, asl(mode));
+    op!(0x06, "ASL", ZeroPage,    2, 5, asl(mode));
+    op!(0x16, "ASL", ZeroPage_X,  2, 6, asl(mode));
+    op!(0x0E, "ASL", Absolute,    3, 6, asl(mode));
+    op!(0x1E, "ASL", Absolute_X,  3, 7, asl(mode));
+
+    op!(0x90, "BCC", NoneAddressing, 2, 2, bcc()); // +1 if branch succeeds, +2 if to a new page
+
+    op!(0xB0, "BCS", NoneAddressing, 2, 2, bcs()); // +1 if branch succeeds, +2 if to a new page
+
+    op!(0xF0, "BEQ", NoneAddressing, 2, 2, beq()); // +1 if branch succeeds, +2 if to a new page
+
+    op!(0x30, "BMI", NoneAddressing, 2, 2, bmi()); // +1 if branch succeeds, +2 if to a new page
+
+    op!(0xD0, "BNE", NoneAddressing, 2, 2, bne()); // +1 if branch succeeds, +2 if to a new page
+
+    op!(0x10, "BPL", NoneAddressing, 2, 2, bpl()); // +1 if branch succeeds, +2 if to a new page
+
+    op!(0x50, "BVC", NoneAddressing, 2, 2, bvc()); // +1 if branch succeeds, +2 if to a new page
+
+    op!(0x70, "BVS", NoneAddressing, 2, 2, bvs()); // +1 if branch succeeds, +2 if to a new page
+
+    op!(0x24, "BIT", ZeroPage, 2, 3, bit(mode));
+    op!(0x2C, "BIT", Absolute, 3, 4, bit(mode));
+
+    op!(0x18, "CLC", NoneAddressing, 1, 2, clc());
+
+    op!(0xD8, "CLD", NoneAddressing, 1, 2, cld());
+
+    op!(0x58, "CLI", NoneAddressing, 1, 2, cli());
+
+    op!(0xB8, "CLV", NoneAddressing, 1, 2, clv());
+
+    op!(0xC9, "CMP", Immediate,  2, 2, cmp(mode));
+    op!(0xC5, "CMP", ZeroPage,   2, 3, cmp(mode));
+    op!(0xD5, "CMP", ZeroPage_X, 2, 4, cmp(mode));
+    op!(0xCD, "CMP", Absolute,   3, 4, cmp(mode));
+    op!(0xDD, "CMP", Absolute_X, 3, 4, cmp(mode)); // +1 if page crossed
+    op!(0xD9, "CMP", Absolute_Y, 3, 4, cmp(mode)); // +1 if page crossed
+    op!(0xC1, "CMP", Indirect_X, 2, 6, cmp(mode));
+    op!(0xD1, "CMP", Indirect_Y, 2, 5, cmp(mode)); // +1 if page crossed
+
+    op!(0xE0, "CPX", Immediate, 2, 2, cpx(mode));
+    op!(0xE4, "CPX", ZeroPage,  2, 3, cpx(mode));
+    op!(0xEC, "CPX", Absolute,  3, 4, cpx(mode));
+
+    op!(0xC0, "CPY", Immediate, 2, 2, cpy(mode));
+    op!(0xC4, "CPY", ZeroPage,  2, 3, cpy(mode));
+    op!(0xCC, "CPY", Absolute,  3, 4, cpy(mode));
+
+    op!(0xC6, "DEC", ZeroPage,   2, 5, dec(mode));
+    op!(0xD6, "DEC", ZeroPage_X, 2, 6, dec(mode));
+    op!(0xCE, "DEC", Absolute,   3, 6, dec(mode));
+    op!(0xDE, "DEC", Absolute_X, 3, 7, dec(mode));
+
+    op!(0xCA, "DEX", NoneAddressing, 1, 2, dex());
+
+    op!(0x88, "DEY", NoneAddressing, 1, 2, dey());
+
+    op!(0x49, "EOR", Immediate,  2, 2, eor(mode));
+    op!(0x45, "EOR", ZeroPage,   2, 3, eor(mode));
+    op!(0x55, "EOR", ZeroPage_X, 2, 4, eor(mode));
+    op!(0x4D, "EOR", Absolute,   3, 4, eor(mode));
+    op!(0x5D, "EOR", Absolute_X, 3, 4, eor(mode)); // +1 if page crossed
+    op!(0x59, "EOR", Absolute_Y, 3, 4, eor(mode)); // +1 if page crossed
+    op!(0x41, "EOR", Indirect_X, 2, 6, eor(mode));
+    op!(0x51, "EOR", Indirect_Y, 2, 5, eor(mode)); // +1 if page crossed
+
+    op!(0xE6, "INC", ZeroPage,   2, 5, inc(mode));
+    op!(0xF6, "INC", ZeroPage_X, 2, 6, inc(mode));
+    op!(0xEE, "INC", Absolute,   3, 6, inc(mode));
+    op!(0xFE, "INC", Absolute_X, 3, 7, inc(mode));
+
+    op!(0xE8, "INX", NoneAddressing, 1, 2, inx());
+
+    op!(0xC8, "INY", NoneAddressing, 1, 2, iny());
+
+    op!(0x4C, "JMP", Absolute, 3, 3, jmp(mode));
+    op!(0x6C, "JMP", Indirect, 3, 5, jmp(mode));
+
+    op!(0x20, "JSR", NoneAddressing, 3, 6, jsr());
+
+    op!(0xA9, "LDA", Immediate,  2, 2, lda(mode));
+    op!(0xA5, "LDA", ZeroPage,   2, 3, lda(mode));
+    op!(0xB5, "LDA", ZeroPage_X, 2, 4, lda(mode));
+    op!(0xAD, "LDA", Absolute,   3, 4, lda(mode));
+    op!(0xBD, "LDA", Absolute_X, 3, 4, lda(mode)); // +1 if page crossed
+    op!(0xB9, "LDA", Absolute_Y, 3, 4, lda(mode)); // +1 if page crossed
+    op!(0xA1, "LDA", Indirect_X, 2, 6, lda(mode));
+    op!(0xB1, "LDA", Indirect_Y, 2, 5, lda(mode)); // +1 if page crossed
+
+    op!(0xA2, "LDX", Immediate,  2, 2, ldx(mode));
+    op!(0xA6, "LDX", ZeroPage,   2, 3, ldx(mode));
+    op!(0xB6, "LDX", ZeroPage_Y, 2, 4, ldx(mode));
+    op!(0xAE, "LDX", Absolute,   3, 4, ldx(mode));
+    op!(0xBE, "LDX", Absolute_Y, 3, 4, ldx(mode)); // +1 if page crossed
+
+    op!(0xA0, "LDY", Immediate,  2, 2, ldy(mode));
+    op!(0xA4, "LDY", ZeroPage,   2, 3, ldy(mode));
+    op!(0xB4, "LDY", ZeroPage_X, 2, 4, ldy(mode));
+    op!(0xAC, "LDY", Absolute,   3, 4, ldy(mode));
+    op!(0xBC, "LDY", Absolute_X, 3, 4, ldy(mode)); // +1 if page crossed
+
+    op!(0x4A, "LSR", Accumulator, 1, 2, lsr(mode));
+    op!(0x46, "LSR", ZeroPage,    2, 5, lsr(mode));
+    op!(0x56, "LSR", ZeroPage_X,  2, 6, lsr(mode));
+    op!(0x4E, "LSR", Absolute,    3, 6, lsr(mode));
+    op!(0x5E, "LSR", Absolute_X,  3, 7, lsr(mode));
+
+    op!(0xEA, "NOP", NoneAddressing, 1, 2, nop());
+
+    op!(0x09, "ORA", Immediate,  2, 2, ora(mode));
+    op!(0x05, "ORA", ZeroPage,   2, 3, ora(mode));
+    op!(0x15, "ORA", ZeroPage_X, 2, 4, ora(mode));
+    op!(0x0D, "ORA", Absolute,   3, 4, ora(mode));
+    op!(0x1D, "ORA", Absolute_X, 3, 4, ora(mode)); // +1 if page crossed
+    op!(0x19, "ORA", Absolute_Y, 3, 4, ora(mode)); // +1 if page crossed
+    op!(0x01, "ORA", Indirect_X, 2, 6, ora(mode));
+    op!(0x11, "ORA", Indirect_Y, 2, 5, ora(mode)); // +1 if page crossed
+
+    op!(0x48, "PHA", NoneAddressing, 1, 3, pha());
+
+    op!(0x08, "PHP", NoneAddressing, 1, 3, php());
+
+    op!(0x68, "PLA", NoneAddressing, 1, 4, pla());
+
+    op!(0x28, "PLP", NoneAddressing, 1, 4, plp());
+
+    op!(0x2A, "ROL", Accumulator, 1, 2, rol(mode));
+    op!(0x26, "ROL", ZeroPage,    2, 5, rol(mode));
+    op!(0x36, "ROL", ZeroPage_X,  2, 6, rol(mode));
+    op!(0x2E, "ROL", Absolute,    3, 6, rol(mode));
+    op!(0x3E, "ROL", Absolute_X,  3, 7, rol(mode));
+
+    op!(0x6A, "ROR", Accumulator, 1, 2, ror(mode));
+    op!(0x66, "ROR", ZeroPage,    2, 5, ror(mode));
+    op!(0x76, "ROR", ZeroPage_X,  2, 6, ror(mode));
+    op!(0x6E, "ROR", Absolute,    3, 6, ror(mode));
+    op!(0x7E, "ROR", Absolute_X,  3, 7, ror(mode));
+
+    op!(0x40, "RTI", NoneAddressing, 1, 6, rti());
+
+    op!(0x60, "RTS", NoneAddressing, 1, 6, rts());
+
+    op!(0xE9, "SBC", Immediate,  2, 2, sbc(mode));
+    op!(0xE5, "SBC", ZeroPage,   2, 3, sbc(mode));
+    op!(0xF5, "SBC", ZeroPage_X, 2, 4, sbc(mode));
+    op!(0xED, "SBC", Absolute,   3, 4, sbc(mode));
+    op!(0xFD, "SBC", Absolute_X, 3, 4, sbc(mode)); // +1 if page crossed
+    op!(0xF9, "SBC", Absolute_Y, 3, 4, sbc(mode)); // +1 if page crossed
+    op!(0xE1, "SBC", Indirect_X, 2, 6, sbc(mode));
+    op!(0xF1, "SBC", Indirect_Y, 2, 5, sbc(mode)); // +1 if page crossed
+
+    op!(0x38, "SEC", NoneAddressing, 1, 2, sec());
+
+    op!(0xF8, "SED", NoneAddressing, 1, 2, sed());
+
+    op!(0x78, "SEI", NoneAddressing, 1, 2, sei());
+
+    op!(0x85, "STA", ZeroPage,   2, 3, sta(mode));
+    op!(0x95, "STA", ZeroPage_X, 2, 4, sta(mode));
+    op!(0x8D, "STA", Absolute,   3, 4, sta(mode));
+    op!(0x9D, "STA", Absolute_X, 3, 5, sta(mode));
+    op!(0x99, "STA", Absolute_Y, 3, 5, sta(mode));
+    op!(0x81, "STA", Indirect_X, 2, 6, sta(mode));
+    op!(0x91, "STA", Indirect_Y, 2, 6, sta(mode));
+
+    op!(0x86, "STX", ZeroPage,   2, 3, stx(mode));
+    op!(0x96, "STX", ZeroPage_Y, 2, 4, stx(mode));
+    op!(0x8E, "STX", Absolute,   3, 4, stx(mode));
+
+    op!(0x84, "STY", ZeroPage,   2, 3, sty(mode));
+    op!(0x94, "STY", ZeroPage_X, 2, 4, sty(mode));
+    op!(0x8C, "STY", Absolute,   3, 4, sty(mode));
+
+    op!(0xAA, "TAX", NoneAddressing, 1, 2, tax());
+
+    op!(0xA8, "TAY", NoneAddressing, 1, 2, tay());
+
+    op!(0xBA, "TSX", NoneAddressing, 1, 2, tsx());
+
+    op!(0x8A, "TXA", NoneAddressing, 1, 2, txa());
+
+    op!(0x9A, "TXS", NoneAddressing, 1, 2, txs());
+
+    op!(0x98, "TYA", NoneAddressing, 1, 2, tya());
+
+    // Unofficial opcodes, named like in nestest.log
+    op!(0x1A, "*NOP", NoneAddressing, 1, 2, nop_read(mode));
+    op!(0x3A, "*NOP", NoneAddressing, 1, 2, nop_read(mode));
+    op!(0x5A, "*NOP", NoneAddressing, 1, 2, nop_read(mode));
+    op!(0x7A, "*NOP", NoneAddressing, 1, 2, nop_read(mode));
+    op!(0xDA, "*NOP", NoneAddressing, 1, 2, nop_read(mode));
+    op!(0xFA, "*NOP", NoneAddressing, 1, 2, nop_read(mode));
+    op!(0x80, "*NOP", Immediate,  2, 2, nop_read(mode));
+    op!(0x82, "*NOP", Immediate,  2, 2, nop_read(mode));
+    op!(0x89, "*NOP", Immediate,  2, 2, nop_read(mode));
+    op!(0xC2, "*NOP", Immediate,  2, 2, nop_read(mode));
+    op!(0xE2, "*NOP", Immediate,  2, 2, nop_read(mode));
+    op!(0x04, "*NOP", ZeroPage,   2, 3, nop_read(mode));
+    op!(0x44, "*NOP", ZeroPage,   2, 3, nop_read(mode));
+    op!(0x64, "*NOP", ZeroPage,   2, 3, nop_read(mode));
+    op!(0x14, "*NOP", ZeroPage_X, 2, 4, nop_read(mode));
+    op!(0x34, "*NOP", ZeroPage_X, 2, 4, nop_read(mode));
+    op!(0x54, "*NOP", ZeroPage_X, 2, 4, nop_read(mode));
+    op!(0x74, "*NOP", ZeroPage_X, 2, 4, nop_read(mode));
+    op!(0xD4, "*NOP", ZeroPage_X, 2, 4, nop_read(mode));
+    op!(0xF4, "*NOP", ZeroPage_X, 2, 4, nop_read(mode));
+    op!(0x0C, "*NOP", Absolute,   3, 4, nop_read(mode));
+    op!(0x1C, "*NOP", Absolute_X, 3, 4, nop_read(mode)); // +1 if page crossed
+    op!(0x3C, "*NOP", Absolute_X, 3, 4, nop_read(mode)); // +1 if page crossed
+    op!(0x5C, "*NOP", Absolute_X, 3, 4, nop_read(mode)); // +1 if page crossed
+    op!(0x7C, "*NOP", Absolute_X, 3, 4, nop_read(mode)); // +1 if page crossed
+    op!(0xDC, "*NOP", Absolute_X, 3, 4, nop_read(mode)); // +1 if page crossed
+    op!(0xFC, "*NOP", Absolute_X, 3, 4, nop_read(mode)); // +1 if page crossed
+
+    op!(0x02, "*KIL", NoneAddressing, 1, 2, kil());
+    op!(0x12, "*KIL", NoneAddressing, 1, 2, kil());
+    op!(0x22, "*KIL", NoneAddressing, 1, 2, kil());
+    op!(0x32, "*KIL", NoneAddressing, 1, 2, kil());
+    op!(0x42, "*KIL", NoneAddressing, 1, 2, kil());
+    op!(0x52, "*KIL", NoneAddressing, 1, 2, kil());
+    op!(0x62, "*KIL", NoneAddressing, 1, 2, kil());
+    op!(0x72, "*KIL", NoneAddressing, 1, 2, kil());
+    op!(0x92, "*KIL", NoneAddressing, 1, 2, kil());
+    op!(0xB2, "*KIL", NoneAddressing, 1, 2, kil());
+    op!(0xD2, "*KIL", NoneAddressing, 1, 2, kil());
+    op!(0xF2, "*KIL", NoneAddressing, 1, 2, kil());
+
+    op!(0xA7, "*LAX", ZeroPage,   2, 3, lax(mode));
+    op!(0xB7, "*LAX", ZeroPage_Y, 2, 4, lax(mode));
+    op!(0xAF, "*LAX", Absolute,   3, 4, lax(mode));
+    op!(0xBF, "*LAX", Absolute_Y, 3, 4, lax(mode)); // +1 if page crossed
+    op!(0xA3, "*LAX", Indirect_X, 2, 6, lax(mode));
+    op!(0xB3, "*LAX", Indirect_Y, 2, 5, lax(mode)); // +1 if page crossed
+
+    op!(0x87, "*SAX", ZeroPage,   2, 3, sax(mode));
+    op!(0x97, "*SAX", ZeroPage_Y, 2, 4, sax(mode));
+    op!(0x8F, "*SAX", Absolute,   3, 4, sax(mode));
+    op!(0x83, "*SAX", Indirect_X, 2, 6, sax(mode));
+
+    op!(0xEB, "*SBC", Immediate, 2, 2, sbc(mode));
+
+    op!(0xC7, "*DCP", ZeroPage,   2, 5, dcp(mode));
+    op!(0xD7, "*DCP", ZeroPage_X, 2, 6, dcp(mode));
+    op!(0xCF, "*DCP", Absolute,   3, 6, dcp(mode));
+    op!(0xDF, "*DCP", Absolute_X, 3, 7, dcp(mode));
+    op!(0xDB, "*DCP", Absolute_Y, 3, 7, dcp(mode));
+    op!(0xC3, "*DCP", Indirect_X, 2, 8, dcp(mode));
+    op!(0xD3, "*DCP", Indirect_Y, 2, 8, dcp(mode));
+
+    op!(0xE7, "*ISB", ZeroPage,   2, 5, isb(mode));
+    op!(0xF7, "*ISB", ZeroPage_X, 2, 6, isb(mode));
+    op!(0xEF, "*ISB", Absolute,   3, 6, isb(mode));
+    op!(0xFF, "*ISB", Absolute_X, 3, 7, isb(mode));
+    op!(0xFB, "*ISB", Absolute_Y, 3, 7, isb(mode));
+    op!(0xE3, "*ISB", Indirect_X, 2, 8, isb(mode));
+    op!(0xF3, "*ISB", Indirect_Y, 2, 8, isb(mode));
+
+    op!(0x07, "*SLO", ZeroPage,   2, 5, slo(mode));
+    op!(0x17, "*SLO", ZeroPage_X, 2, 6, slo(mode));
+    op!(0x0F, "*SLO", Absolute,   3, 6, slo(mode));
+    op!(0x1F, "*SLO", Absolute_X, 3, 7, slo(mode));
+    op!(0x1B, "*SLO", Absolute_Y, 3, 7, slo(mode));
+    op!(0x03, "*SLO", Indirect_X, 2, 8, slo(mode));
+    op!(0x13, "*SLO", Indirect_Y, 2, 8, slo(mode));
+
+    op!(0x27, "*RLA", ZeroPage,   2, 5, rla(mode));
+    op!(0x37, "*RLA", ZeroPage_X, 2, 6, rla(mode));
+    op!(0x2F, "*RLA", Absolute,   3, 6, rla(mode));
+    op!(0x3F, "*RLA", Absolute_X, 3, 7, rla(mode));
+    op!(0x3B, "*RLA", Absolute_Y, 3, 7, rla(mode));
+    op!(0x23, "*RLA", Indirect_X, 2, 8, rla(mode));
+    op!(0x33, "*RLA", Indirect_Y, 2, 8, rla(mode));
+
+    op!(0x47, "*SRE", ZeroPage,   2, 5, sre(mode));
+    op!(0x57, "*SRE", ZeroPage_X, 2, 6, sre(mode));
+    op!(0x4F, "*SRE", Absolute,   3, 6, sre(mode));
+    op!(0x5F, "*SRE", Absolute_X, 3, 7, sre(mode));
+    op!(0x5B, "*SRE", Absolute_Y, 3, 7, sre(mode));
+    op!(0x43, "*SRE", Indirect_X, 2, 8, sre(mode));
+    op!(0x53, "*SRE", Indirect_Y, 2, 8, sre(mode));
+
+    op!(0x67, "*RRA", ZeroPage,   2, 5, rra(mode));
+    op!(0x77, "*RRA", ZeroPage_X, 2, 6, rra(mode));
+    op!(0x6F, "*RRA", Absolute,   3, 6, rra(mode));
+    op!(0x7F, "*RRA", Absolute_X, 3, 7, rra(mode));
+    op!(0x7B, "*RRA", Absolute_Y, 3, 7, rra(mode));
+    op!(0x63, "*RRA", Indirect_X, 2, 8, rra(mode));
+    op!(0x73, "*RRA", Indirect_Y, 2, 8, rra(mode));
+
+    op!(0x0B, "*ANC", Immediate, 2, 2, anc(mode));
+    op!(0x2B, "*ANC", Immediate, 2, 2, anc(mode));
+
+    op!(0x4B, "*ALR", Immediate, 2, 2, alr(mode));
+
+    op!(0x6B, "*ARR", Immediate, 2, 2, arr(mode));
+
+    op!(0xCB, "*AXS", Immediate, 2, 2, axs(mode));
+
+    // unstable on the real chips, these follow the most common behaviour
+    op!(0x8B, "*XAA", Immediate,  2, 2, xaa(mode));
+    op!(0xAB, "*LXA", Immediate,  2, 2, lxa(mode));
+    op!(0x93, "*SHA", Indirect_Y, 2, 6, sha(mode));
+    op!(0x9F, "*SHA", Absolute_Y, 3, 5, sha(mode));
+    op!(0x9E, "*SHX", Absolute_Y, 3, 5, shx(mode));
+    op!(0x9C, "*SHY", Absolute_X, 3, 5, shy(mode));
+    op!(0x9B, "*TAS", Absolute_Y, 3, 5, tas(mode));
+    op!(0xBB, "*LAS", Absolute_Y, 3, 4, las(mode)); // +1 if page crossed
+
+    (table, handlers)
+}
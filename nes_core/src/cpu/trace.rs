@@ -0,0 +1,204 @@
+use super::{addrssing_modes::AddressingMode, cpu::NesCPU, memory::CpuBus, opscodes::OPS_CODES};
+
+// One line in the format of nestest.log for the instruction about to run at PC:
+//
+// C72A  B1 89     LDA ($89),Y = 0300 @ 0300 = 89   A:00 X:00 Y:00 P:26 SP:FB PPU:  9,120 CYC:1043
+//
+// Operands show the effective address and the value found there before the instruction runs.
+// Memory is peeked so tracing never triggers the side effects of the I/O registers
+pub fn trace(cpu: &NesCPU) -> String {
+    let bus = &cpu.bus;
+    let pc = cpu.program_counter;
+    let opcode = bus.peek(pc);
+
+    let op = &OPS_CODES[opcode as usize];
+    // unofficial opcodes are marked with a `*` in the column before the mnemonic
+    let name = match op.name.strip_prefix('*') {
+        Some(name) => format!("*{name}"),
+        None => format!(" {}", op.name),
+    };
+    let operand = operand(cpu, &op.mode, op.len, op.name);
+    let asm = format!("{name} {operand}").trim_end().to_string();
+    let len = op.len as u16;
+
+    let bytes = (0..len)
+        .map(|i| format!("{:02X}", bus.peek(pc.wrapping_add(i))))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    format!(
+        "{pc:04X}  {bytes:<8} {asm:<32} A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} PPU:{:>3},{:>3} CYC:{}",
+        cpu.register_a,
+        cpu.register_x,
+        cpu.register_y,
+        cpu.status,
+        cpu.stack_counter,
+        bus.ppu().scanline(),
+        bus.ppu().dot(),
+        bus.cycles,
+    )
+}
+
+fn operand(cpu: &NesCPU, mode: &AddressingMode, len: u8, name: &str) -> String {
+    let bus = &cpu.bus;
+    let pc = cpu.program_counter;
+    let lo = bus.peek(pc.wrapping_add(1));
+    let word = u16::from_le_bytes([lo, bus.peek(pc.wrapping_add(2))]);
+    // pointers in the zero page wrap around inside it
+    let zero_page_u16 = |addr: u8| u16::from_le_bytes([bus.peek(addr as u16), bus.peek(addr.wrapping_add(1) as u16)]);
+
+    match mode {
+        AddressingMode::Accumulator => "A".to_string(),
+        AddressingMode::Immediate => format!("#${lo:02X}"),
+        AddressingMode::ZeroPage => format!("${lo:02X} = {:02X}", bus.peek(lo as u16)),
+        AddressingMode::ZeroPage_X => {
+            let addr = lo.wrapping_add(cpu.register_x);
+            format!("${lo:02X},X @ {addr:02X} = {:02X}", bus.peek(addr as u16))
+        }
+        AddressingMode::ZeroPage_Y => {
+            let addr = lo.wrapping_add(cpu.register_y);
+            format!("${lo:02X},Y @ {addr:02X} = {:02X}", bus.peek(addr as u16))
+        }
+        AddressingMode::Absolute if name == "JMP" => format!("${word:04X}"),
+        AddressingMode::Absolute => format!("${word:04X} = {:02X}", bus.peek(word)),
+        AddressingMode::Absolute_X => {
+            let addr = word.wrapping_add(cpu.register_x as u16);
+            format!("${word:04X},X @ {addr:04X} = {:02X}", bus.peek(addr))
+        }
+        AddressingMode::Absolute_Y => {
+            let addr = word.wrapping_add(cpu.register_y as u16);
+            format!("${word:04X},Y @ {addr:04X} = {:02X}", bus.peek(addr))
+        }
+        AddressingMode::Indirect => {
+            // JMP ($xxFF) reads the high byte from the start of the same page
+            let hi = bus.peek((word & 0xFF00) | (word.wrapping_add(1) & 0x00FF));
+            format!("(${word:04X}) = {:04X}", u16::from_le_bytes([bus.peek(word), hi]))
+        }
+        AddressingMode::Indirect_X => {
+            let ptr = lo.wrapping_add(cpu.register_x);
+            let addr = zero_page_u16(ptr);
+            format!("(${lo:02X},X) @ {ptr:02X} = {addr:04X} = {:02X}", bus.peek(addr))
+        }
+        AddressingMode::Indirect_Y => {
+            let base = zero_page_u16(lo);
+            let addr = base.wrapping_add(cpu.register_y as u16);
+            format!("(${lo:02X}),Y = {base:04X} @ {addr:04X} = {:02X}", bus.peek(addr))
+        }
+        AddressingMode::NoneAddressing => match len {
+            // branches are relative to the next instruction
+            2 => format!("${:04X}", pc.wrapping_add(2).wrapping_add(lo as i8 as u16)),
+            3 => format!("${word:04X}"),
+            _ => String::new(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bus::Bus;
+    use crate::controller::Joypad;
+    use crate::cpu::{Mem, CPU};
+    use crate::ppu::NesPPU;
+    use crate::rom::test::test_rom;
+    use crate::rom::Rom;
+
+    fn test_cpu() -> NesCPU<'static> {
+        let bus = Bus::new(test_rom(), |_ppu: &NesPPU, _joypad: &mut Joypad| {}).unwrap();
+        let mut cpu = CPU::new(bus);
+        cpu.program_counter = 0x0064;
+        cpu.register_a = 1;
+        cpu.register_x = 2;
+        cpu.register_y = 3;
+        cpu
+    }
+
+    #[test]
+    fn test_format_trace() {
+        let mut cpu = test_cpu();
+        cpu.mem_write(100, 0xa2);
+        cpu.mem_write(101, 0x01);
+        cpu.mem_write(102, 0xca);
+        cpu.mem_write(103, 0x88);
+        cpu.mem_write(104, 0x00);
+
+        let mut result: Vec<String> = vec![];
+        for _ in 0..3 {
+            cpu.step_with_callback(&mut |cpu: &mut NesCPU| result.push(trace(cpu)));
+        }
+
+        assert_eq!(
+            "0064  A2 01     LDX #$01                        A:01 X:02 Y:03 P:24 SP:FD PPU:  0,  0 CYC:0",
+            result[0]
+        );
+        assert_eq!(
+            "0066  CA        DEX                             A:01 X:01 Y:03 P:24 SP:FD PPU:  0,  6 CYC:2",
+            result[1]
+        );
+        assert_eq!(
+            "0067  88        DEY                             A:01 X:00 Y:03 P:26 SP:FD PPU:  0, 12 CYC:4",
+            result[2]
+        );
+    }
+
+    #[test]
+    fn test_format_mem_access() {
+        let mut cpu = test_cpu();
+        // ORA ($33),Y
+        cpu.mem_write(100, 0x11);
+        cpu.mem_write(101, 0x33);
+
+        cpu.mem_write(0x33, 00);
+        cpu.mem_write(0x34, 04);
+        cpu.mem_write(0x400, 0xAA);
+
+        assert_eq!(
+            "0064  11 33     ORA ($33),Y = 0400 @ 0403 = 00  A:01 X:02 Y:03 P:24 SP:FD PPU:  0,  0 CYC:0",
+            trace(&cpu)
+        );
+
+        // LDA ($33,X) / STA $0400,X / JMP ($02FF)
+        cpu.mem_write(0x35, 0x00);
+        cpu.mem_write(0x36, 0x04);
+        cpu.mem_write(100, 0xA1);
+        assert!(trace(&cpu).contains("LDA ($33,X) @ 35 = 0400 = AA"));
+
+        cpu.mem_write(100, 0x9D);
+        cpu.mem_write(101, 0x00);
+        cpu.mem_write(102, 0x04);
+        assert!(trace(&cpu).contains("STA $0400,X @ 0402 = 00"));
+
+        cpu.mem_write(100, 0x6C);
+        cpu.mem_write(101, 0xFF);
+        cpu.mem_write(102, 0x02);
+        cpu.mem_write(0x2FF, 0x34);
+        cpu.mem_write(0x200, 0x12);
+        assert!(trace(&cpu).contains("JMP ($02FF) = 1234"));
+    }
+
+    // The ROM isn't in the repository, run it with
+    // NESTEST=path/to/nestest.nes cargo test -p nes_core -- --ignored
+    // and `nestest.log` next to it
+    #[test]
+    #[ignore]
+    fn test_nestest() {
+        let path = std::path::PathBuf::from(std::env::var("NESTEST").expect("NESTEST is not set"));
+        let rom = Rom::new(&std::fs::read(&path).unwrap()).unwrap();
+        let log = std::fs::read_to_string(path.with_extension("log")).unwrap();
+
+        let bus = Bus::new(rom, |_ppu: &NesPPU, _joypad: &mut Joypad| {}).unwrap();
+        let mut cpu = CPU::new(bus);
+        cpu.reset();
+        // automated mode, without the menu of the reset vector
+        cpu.program_counter = 0xC000;
+
+        for (i, expected) in log.lines().enumerate() {
+            assert_eq!(expected.trim_end(), trace(&cpu), "line {}", i + 1);
+            cpu.step();
+        }
+
+        // $02 and $03 hold the number of the first failed test
+        assert_eq!(0, cpu.mem_read(0x02));
+        assert_eq!(0, cpu.mem_read(0x03));
+    }
+}
@@ -0,0 +1,197 @@
+use super::{addrssing_modes::AddressingMode, cpu::CPU, memory::CpuBus};
+
+// Opcodes left out of the 6502 documentation. Most of them are two official instructions sharing
+// one decoding, like SLO being ASL followed by ORA on the same address
+impl<B: CpuBus> CPU<B> {
+    // The NOPs with an operand still read it
+    pub fn nop_read(&mut self, mode: &AddressingMode) {
+        if !matches!(mode, AddressingMode::NoneAddressing) {
+            let addr = self.get_operand_address(mode);
+            self.read(addr);
+        }
+    }
+
+    pub fn lax(&mut self, mode: &AddressingMode) {
+        let addr = self.get_operand_address(mode);
+        let data = self.read(addr);
+
+        self.register_a = data;
+        self.register_x = data;
+        self.update_zero_and_negative_flags(data);
+    }
+
+    pub fn sax(&mut self, mode: &AddressingMode) {
+        let addr = self.get_store_address(mode);
+        self.write(addr, self.register_a & self.register_x);
+    }
+
+    // DEC + CMP
+    pub fn dcp(&mut self, mode: &AddressingMode) {
+        let addr = self.get_store_address(mode);
+        let data = self.modify(addr, |_, data| data.wrapping_sub(1));
+
+        self.set_carry_flag(self.register_a >= data);
+        self.update_zero_and_negative_flags(self.register_a.wrapping_sub(data));
+    }
+
+    // INC + SBC
+    pub fn isb(&mut self, mode: &AddressingMode) {
+        let addr = self.get_store_address(mode);
+        let data = self.modify(addr, |_, data| data.wrapping_add(1));
+
+        self.subtract_from_a(data);
+    }
+
+    // ASL + ORA
+    pub fn slo(&mut self, mode: &AddressingMode) {
+        let addr = self.get_store_address(mode);
+        let data = self.modify(addr, |cpu, data| {
+            cpu.set_carry_flag(data & 0b1000_0000 != 0);
+            data << 1
+        });
+
+        self.register_a |= data;
+        self.update_zero_and_negative_flags(self.register_a);
+    }
+
+    // ROL + AND
+    pub fn rla(&mut self, mode: &AddressingMode) {
+        let m = self.is_carry_set() as u8;
+        let addr = self.get_store_address(mode);
+        let data = self.modify(addr, |cpu, data| {
+            cpu.set_carry_flag(data & 0b1000_0000 != 0);
+            data << 1 | m
+        });
+
+        self.register_a &= data;
+        self.update_zero_and_negative_flags(self.register_a);
+    }
+
+    // LSR + EOR
+    pub fn sre(&mut self, mode: &AddressingMode) {
+        let addr = self.get_store_address(mode);
+        let data = self.modify(addr, |cpu, data| {
+            cpu.set_carry_flag(data & 0b0000_0001 != 0);
+            data >> 1
+        });
+
+        self.register_a ^= data;
+        self.update_zero_and_negative_flags(self.register_a);
+    }
+
+    // ROR + ADC, the carry out of the rotation goes into the addition
+    pub fn rra(&mut self, mode: &AddressingMode) {
+        let m = if self.is_carry_set() { 0b1000_0000 } else { 0 };
+        let addr = self.get_store_address(mode);
+        let data = self.modify(addr, |cpu, data| {
+            cpu.set_carry_flag(data & 0b0000_0001 != 0);
+            data >> 1 | m
+        });
+
+        self.add_to_a(data);
+    }
+
+    // AND, then the carry is a copy of the negative flag
+    pub fn anc(&mut self, mode: &AddressingMode) {
+        self.and(mode);
+        self.set_carry_flag(self.is_negative_set());
+    }
+
+    // AND + LSR A
+    pub fn alr(&mut self, mode: &AddressingMode) {
+        self.and(mode);
+        self.lsr(&AddressingMode::Accumulator);
+    }
+
+    // AND + ROR A, but the carry comes from bit 6 and the overflow from bit 6 xor bit 5
+    pub fn arr(&mut self, mode: &AddressingMode) {
+        self.and(mode);
+        self.ror(&AddressingMode::Accumulator);
+
+        let bit6 = self.register_a & 0b0100_0000 != 0;
+        let bit5 = self.register_a & 0b0010_0000 != 0;
+        self.set_carry_flag(bit6);
+        self.set_overflow_flag(bit6 ^ bit5);
+    }
+
+    // X = (A & X) - operand, a compare without borrow
+    pub fn axs(&mut self, mode: &AddressingMode) {
+        let addr = self.get_operand_address(mode);
+        let data = self.read(addr);
+        let and = self.register_a & self.register_x;
+
+        self.set_carry_flag(and >= data);
+        self.register_x = and.wrapping_sub(data);
+        self.update_zero_and_negative_flags(self.register_x);
+    }
+
+    // The ones below are unstable, the result depends on the chip and even its temperature.
+    // These follow what most emulators and test ROMs expect
+
+    // A = (A | magic) & X & operand
+    pub fn xaa(&mut self, mode: &AddressingMode) {
+        let addr = self.get_operand_address(mode);
+        let data = self.read(addr);
+
+        self.register_a = (self.register_a | 0xEE) & self.register_x & data;
+        self.update_zero_and_negative_flags(self.register_a);
+    }
+
+    // A = X = (A | magic) & operand
+    pub fn lxa(&mut self, mode: &AddressingMode) {
+        let addr = self.get_operand_address(mode);
+        let data = self.read(addr);
+
+        self.register_a = (self.register_a | 0xEE) & data;
+        self.register_x = self.register_a;
+        self.update_zero_and_negative_flags(self.register_a);
+    }
+
+    // A = X = S = S & memory
+    pub fn las(&mut self, mode: &AddressingMode) {
+        let addr = self.get_operand_address(mode);
+        let data = self.read(addr) & self.stack_counter;
+
+        self.register_a = data;
+        self.register_x = data;
+        self.stack_counter = data;
+        self.update_zero_and_negative_flags(data);
+    }
+
+    pub fn sha(&mut self, mode: &AddressingMode) {
+        self.store_and_high(mode, self.register_a & self.register_x);
+    }
+
+    pub fn shx(&mut self, mode: &AddressingMode) {
+        self.store_and_high(mode, self.register_x);
+    }
+
+    pub fn shy(&mut self, mode: &AddressingMode) {
+        self.store_and_high(mode, self.register_y);
+    }
+
+    pub fn tas(&mut self, mode: &AddressingMode) {
+        self.stack_counter = self.register_a & self.register_x;
+        self.store_and_high(mode, self.stack_counter);
+    }
+
+    // SHA, SHX, SHY and TAS store the value ANDed with the high byte of the base address plus one.
+    // When the indexing crosses a page that same value replaces the high byte of the address
+    fn store_and_high(&mut self, mode: &AddressingMode, data: u8) {
+        let index = match mode {
+            AddressingMode::Absolute_X => self.register_x,
+            _ => self.register_y,
+        };
+        let addr = self.get_store_address(mode);
+        let base = addr.wrapping_sub(index as u16);
+
+        let data = data & ((base >> 8) as u8).wrapping_add(1);
+        let addr = if base & 0xFF00 != addr & 0xFF00 {
+            (data as u16) << 8 | (addr & 0x00FF)
+        } else {
+            addr
+        };
+
+        self.write(addr, data);
+    }
+}
@@ -0,0 +1,230 @@
+use std::path::Path;
+
+use crate::{
+    cpu::{AddressingMode, CpuBus, NesCPU, OPS_CODES},
+    ppu::NesPPU,
+};
+
+// Flags of a PRG ROM byte. Bits 2 and 3 hold the 8KB window of 0x8000 - 0xFFFF it was last seen through
+pub const CODE: u8 = 0x01;
+pub const DATA: u8 = 0x02;
+pub const INDIRECT_CODE: u8 = 0x10;
+pub const INDIRECT_DATA: u8 = 0x20;
+pub const PCM: u8 = 0x40;
+
+// Flags of a CHR ROM byte
+pub const RENDERED: u8 = 0x01;
+pub const READ: u8 = 0x02;
+
+const STORES: [&str; 8] = ["STA", "STX", "STY", "*SAX", "*SHA", "*SHX", "*SHY", "*TAS"];
+
+// Code/Data Logger: records how the game used every byte of its ROM, in the .cdl format of FCEUX. The file
+// has one byte of flags for each byte of PRG ROM followed by one for each byte of CHR ROM, so the logs of
+// several sessions are merged by OR-ing them
+pub struct CodeDataLogger {
+    pub prg: Vec<u8>,
+    pub chr: Vec<u8>,
+}
+
+impl CodeDataLogger {
+    // Sizes of the PRG and CHR ROM, CHR RAM isn't part of the ROM and has no flags
+    pub fn new(prg_size: usize, chr_size: usize) -> Self {
+        CodeDataLogger {
+            prg: vec![0; prg_size],
+            chr: vec![0; chr_size],
+        }
+    }
+
+    // Starts with the flags already logged to `path`, when it exists
+    pub fn open(prg_size: usize, chr_size: usize, path: &Path) -> Result<Self, String> {
+        let mut cdl = CodeDataLogger::new(prg_size, chr_size);
+        if path.exists() {
+            let data = std::fs::read(path).map_err(|err| format!("Could not read {}: {err}", path.display()))?;
+            cdl.merge(&data)
+                .map_err(|err| format!("Could not merge {}: {err}", path.display()))?;
+        }
+        Ok(cdl)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        std::fs::write(path, self.to_bytes()).map_err(|err| format!("Could not write {}: {err}", path.display()))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [self.prg.as_slice(), &self.chr].concat()
+    }
+
+    pub fn merge(&mut self, cdl: &[u8]) -> Result<(), String> {
+        let len = self.prg.len() + self.chr.len();
+        if cdl.len() != len {
+            return Err(format!("the log has {} bytes and the ROM {len}", cdl.len()));
+        }
+
+        for (flags, logged) in self.prg.iter_mut().chain(self.chr.iter_mut()).zip(cdl) {
+            *flags |= logged;
+        }
+        Ok(())
+    }
+
+    // Called from the CPU callback, before the instruction at PC runs
+    pub fn log_instruction(&mut self, cpu: &mut NesCPU) {
+        if let Some(addr) = cpu.bus.take_dmc_fetch() {
+            self.log_prg(cpu, addr, PCM);
+        }
+
+        let pc = cpu.program_counter;
+        let op = &OPS_CODES[cpu.bus.peek(pc) as usize];
+        for i in 0..op.len as u16 {
+            self.log_prg(cpu, pc.wrapping_add(i), CODE);
+        }
+
+        let Some(addr) = cpu.peek_operand_address(&op.mode) else {
+            return;
+        };
+        match op.mode {
+            AddressingMode::Indirect => {
+                // JMP ($xxFF) reads the high byte from the start of the same page
+                let hi_addr = (addr & 0xFF00) | (addr.wrapping_add(1) & 0x00FF);
+                let target = u16::from_le_bytes([cpu.bus.peek(addr), cpu.bus.peek(hi_addr)]);
+                self.log_prg(cpu, addr, DATA);
+                self.log_prg(cpu, hi_addr, DATA);
+                self.log_prg(cpu, target, INDIRECT_CODE);
+            }
+            _ if op.name == "JMP" || STORES.contains(&op.name) => {}
+            // PPUDATA reads the pattern tables below 0x2000
+            _ if (0x2000..0x4000).contains(&addr) && addr & 0x7 == 0x7 => {
+                let ppu_addr = cpu.bus.ppu().vram_addr();
+                if ppu_addr < 0x2000 {
+                    self.log_chr(cpu.bus.ppu(), ppu_addr, READ);
+                }
+            }
+            AddressingMode::Indirect_X | AddressingMode::Indirect_Y => self.log_prg(cpu, addr, DATA | INDIRECT_DATA),
+            _ => self.log_prg(cpu, addr, DATA),
+        }
+    }
+
+    // Called with the CHR offset of every pattern byte the PPU fetched to draw the screen
+    pub fn log_pattern_fetch(&mut self, offset: usize) {
+        if !self.chr.is_empty() {
+            let len = self.chr.len();
+            self.chr[offset % len] |= RENDERED;
+        }
+    }
+
+    fn log_prg(&mut self, cpu: &NesCPU, addr: u16, flags: u8) {
+        if addr < 0x8000 {
+            return;
+        }
+        let offset = cpu.bus.ppu().mapper.borrow().prg_addr(addr);
+        let window = ((addr >> 13) & 0b11) as u8;
+        if let Some(logged) = self.prg.get_mut(offset) {
+            *logged |= flags | window << 2;
+        }
+    }
+
+    fn log_chr(&mut self, ppu: &NesPPU, addr: u16, flags: u8) {
+        if self.chr.is_empty() {
+            return;
+        }
+        let offset = ppu.mapper.borrow().chr_addr(addr) % self.chr.len();
+        self.chr[offset] |= flags;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        cpu::{assemble, Mem},
+        rom::{Mirroring, Rom},
+        Nes,
+    };
+
+    fn test_rom() -> Rom {
+        let program = assemble(
+            "LDA $C100          ; data
+             LDA #$01 / STA $00 / LDA #$C1 / STA $01 / LDY #$01
+             LDA ($00),Y        ; indirect data at $C102
+             STA $C103
+             LDA #$00 / STA $2006 / STA $2006 / LDA $2007
+             JMP ($C104)
+             .org $C080
+             loop: JMP loop",
+            0xC000,
+        );
+        let program = program.unwrap();
+
+        let mut prg_rom = vec![0; 0x4000];
+        prg_rom[..program.len() - 3].copy_from_slice(&program[..program.len() - 3]);
+        prg_rom[0x80..0x83].copy_from_slice(&program[program.len() - 3..]);
+        prg_rom[0x104..0x106].copy_from_slice(&[0x80, 0xC0]);
+        prg_rom[0x3FFC..0x3FFE].copy_from_slice(&[0x00, 0xC0]);
+
+        Rom {
+            prg_rom,
+            chr_rom: vec![0; 0x2000],
+            mapper: 0,
+            screen_mirroring: Mirroring::HORIZONTAL,
+            battery: false,
+        }
+    }
+
+    // the bytes seen through 0xC000 - 0xDFFF have 2 in the window bits
+    const WINDOW: u8 = 2 << 2;
+
+    #[test]
+    fn test_logs_code_and_data() {
+        let rom = test_rom();
+        let cdl = CodeDataLogger::new(rom.prg_rom.len(), rom.chr_rom.len());
+        let mut nes = Nes::from_rom(rom).unwrap();
+        nes.set_code_data_logger(Some(cdl));
+        for _ in 0..14 {
+            nes.step();
+        }
+        let cdl = nes.code_data_logger().unwrap();
+
+        assert_eq!(cdl.prg[0x00..0x03], [CODE | WINDOW; 3]);
+        assert_eq!(cdl.prg[0x100], DATA | WINDOW);
+        assert_eq!(cdl.prg[0x101], 0);
+        assert_eq!(cdl.prg[0x102], DATA | INDIRECT_DATA | WINDOW);
+        assert_eq!(cdl.prg[0x103], 0);
+        assert_eq!(cdl.prg[0x104..0x106], [DATA | WINDOW; 2]);
+        assert_eq!(cdl.prg[0x80], CODE | INDIRECT_CODE | WINDOW);
+        assert_eq!(cdl.prg[0x3FFC], 0);
+        assert_eq!(cdl.chr[0], READ);
+        assert_eq!(cdl.chr[1], 0);
+    }
+
+    #[test]
+    fn test_logs_rendered_tiles() {
+        let rom = test_rom();
+        let cdl = CodeDataLogger::new(rom.prg_rom.len(), rom.chr_rom.len());
+        let mut nes = Nes::from_rom(rom).unwrap();
+        nes.set_code_data_logger(Some(cdl));
+        nes.cpu_mut().mem_write(0x2001, 0b1_1000);
+        nes.step_frame();
+        let cdl = nes.code_data_logger().unwrap();
+
+        // every name table entry and sprite is tile 0, its first byte was also read through PPUDATA
+        assert_eq!(cdl.chr[0], RENDERED | READ);
+        assert_eq!(cdl.chr[1..16], [RENDERED; 15]);
+        assert_eq!(cdl.chr[16], 0);
+    }
+
+    #[test]
+    fn test_merge() {
+        let rom = test_rom();
+        let mut cdl = CodeDataLogger::new(rom.prg_rom.len(), rom.chr_rom.len());
+        cdl.prg[0] = CODE;
+        cdl.chr[0] = RENDERED;
+
+        let mut other = CodeDataLogger::new(rom.prg_rom.len(), rom.chr_rom.len());
+        other.prg[0] = DATA;
+        other.merge(&cdl.to_bytes()).unwrap();
+
+        assert_eq!(other.prg[0], CODE | DATA);
+        assert_eq!(other.chr[0], RENDERED);
+        assert_eq!(other.to_bytes().len(), 0x6000);
+        assert!(other.merge(&[0; 16]).is_err());
+    }
+}
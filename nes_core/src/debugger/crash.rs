@@ -0,0 +1,252 @@
+use std::{
+    fmt::Write,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+};
+
+use super::disasm::disassemble_line;
+use crate::{
+    cpu::{CpuBus, Executed, Mem},
+    nes::Nes,
+};
+
+// The bytes of a recorded instruction, at the address it ran from
+struct Recorded<'a>(&'a Executed);
+
+impl Mem for Recorded<'_> {
+    fn mem_read(&mut self, addr: u16) -> u8 {
+        self.peek(addr)
+    }
+
+    fn mem_write(&mut self, _addr: u16, _data: u8) {}
+}
+
+impl CpuBus for Recorded<'_> {
+    fn tick(&mut self, _cycles: u8) {}
+
+    fn poll_nmi_status(&mut self) -> bool {
+        false
+    }
+
+    fn poll_irq_status(&self) -> bool {
+        false
+    }
+
+    fn peek(&self, addr: u16) -> u8 {
+        let offset = addr.wrapping_sub(self.0.pc) as usize;
+        self.0.bytes.get(offset).copied().unwrap_or(0)
+    }
+}
+
+// A report of the state of the console for bug reports: why it stopped, which ROM it ran, the last
+// instructions with the registers before each one and the RAM, VRAM, palette and OAM
+pub fn crash_report(nes: &Nes, reason: &str) -> String {
+    let cpu = nes.cpu();
+    let ppu = cpu.bus.ppu();
+    let mut report = String::new();
+
+    writeln!(report, "NES crash report").unwrap();
+    writeln!(report, "Reason: {reason}").unwrap();
+    writeln!(report, "ROM CRC32: {:08X}", cpu.bus.rom_hash()).unwrap();
+    writeln!(report, "Frame: {}  CPU cycle: {}", nes.frame_count(), cpu.bus.cycles).unwrap();
+    writeln!(
+        report,
+        "PC:{:04X} A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} PPU:{:>3},{:>3}",
+        cpu.program_counter,
+        cpu.register_a,
+        cpu.register_x,
+        cpu.register_y,
+        cpu.status,
+        cpu.stack_counter,
+        ppu.scanline(),
+        ppu.dot(),
+    )
+    .unwrap();
+
+    writeln!(report, "\nLast {} instructions, oldest first", cpu.history.len()).unwrap();
+    for executed in cpu.history.iter() {
+        let (line, _) = disassemble_line(&Recorded(executed), executed.pc);
+        writeln!(
+            report,
+            "{line:<30}  A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} PPU:{:>3},{:>3}",
+            executed.register_a,
+            executed.register_x,
+            executed.register_y,
+            executed.status,
+            executed.stack_counter,
+            executed.scanline,
+            executed.dot,
+        )
+        .unwrap();
+    }
+
+    let ram: Vec<u8> = (0..0x800).map(|addr| cpu.bus.peek(addr)).collect();
+    hexdump(&mut report, "RAM", &ram, 0);
+    hexdump(&mut report, "VRAM", &ppu.vram, 0x2000);
+    hexdump(&mut report, "Palette", &ppu.palette_table, 0x3F00);
+    hexdump(&mut report, "OAM", &ppu.oam_data, 0);
+
+    report
+}
+
+pub fn write_crash_report(nes: &Nes, reason: &str, path: &Path) -> Result<(), String> {
+    std::fs::write(path, crash_report(nes, reason)).map_err(|err| format!("Could not write {}: {err}", path.display()))
+}
+
+// Runs `f`, turning a panic into an error with its message so a crash report can still be written
+pub fn catch_panic<T>(f: impl FnOnce() -> T) -> Result<T, String> {
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+        let message = match payload.downcast_ref::<&str>() {
+            Some(message) => message.to_string(),
+            None => payload.downcast_ref::<String>().cloned().unwrap_or_default(),
+        };
+        format!("Emulator panicked: {message}")
+    })
+}
+
+// Writes the crash reports of a session to `path`: when the emulator stops on a halt or a panic and for the
+// first fault the policy logs or breaks on, which leaves the game running
+pub struct CrashReporter {
+    path: PathBuf,
+    reported: bool,
+}
+
+impl CrashReporter {
+    pub fn new(path: PathBuf) -> Self {
+        CrashReporter { path, reported: false }
+    }
+
+    // Runs `step`, usually a frame, which returns false when the CPU stops. The error has the reason
+    pub fn run(&mut self, nes: &mut Nes, step: impl FnOnce(&mut Nes) -> bool) -> Result<(), String> {
+        let reason = match catch_panic(|| step(nes)) {
+            Ok(true) => None,
+            Ok(false) => Some(match nes.cpu().halted {
+                Some(fault) => fault.to_string(),
+                None => format!("CPU stopped at 0x{:04X}", nes.cpu().program_counter),
+            }),
+            Err(err) => Some(err),
+        };
+        let fault = nes.cpu_mut().take_last_fault();
+
+        if let Some(reason) = reason {
+            self.reported = true;
+            return Err(match write_crash_report(nes, &reason, &self.path) {
+                Ok(()) => format!("{reason}, crash report written to {}", self.path.display()),
+                Err(err) => format!("{reason}, {err}"),
+            });
+        }
+        if let Some(fault) = fault.filter(|_| !self.reported) {
+            self.reported = true;
+            match write_crash_report(nes, &fault.to_string(), &self.path) {
+                Ok(()) => eprintln!("Crash report written to {}", self.path.display()),
+                Err(err) => eprintln!("{err}"),
+            }
+        }
+        Ok(())
+    }
+}
+
+// Rows of 16 bytes, runs of rows equal to the one before are written as a single `*`
+fn hexdump(report: &mut String, title: &str, data: &[u8], start: usize) {
+    writeln!(report, "\n{title}").unwrap();
+    let mut repeated = false;
+    for (i, row) in data.chunks(16).enumerate() {
+        if i > 0 && data[(i - 1) * 16..i * 16] == *row {
+            if !repeated {
+                writeln!(report, "*").unwrap();
+            }
+            repeated = true;
+            continue;
+        }
+        repeated = false;
+
+        let hex = row.iter().map(|b| format!("{b:02X}")).collect::<Vec<_>>().join(" ");
+        writeln!(report, "{:04X}  {hex}", start + i * 16).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        cpu::assemble,
+        rom::{Mirroring, Rom},
+    };
+
+    fn crashing_rom() -> Rom {
+        let program = assemble("LDX #$05 / loop: STX $10 / DEX / BNE loop / .byte $02", 0x8000).unwrap();
+
+        let mut prg_rom = vec![0; 0x4000];
+        prg_rom[..program.len()].copy_from_slice(&program);
+        prg_rom[0x3FFC..0x3FFE].copy_from_slice(&[0x00, 0x80]);
+
+        Rom {
+            prg_rom,
+            chr_rom: vec![0; 0x2000],
+            mapper: 0,
+            screen_mirroring: Mirroring::HORIZONTAL,
+            battery: false,
+        }
+    }
+
+    #[test]
+    fn test_crash_report() {
+        let rom = crashing_rom();
+        let hash = rom.hash();
+        let mut nes = Nes::from_rom(rom).unwrap();
+        assert!(!nes.step_frame());
+
+        let report = crash_report(&nes, &nes.cpu().halted.unwrap().to_string());
+        let lines: Vec<&str> = report.lines().collect();
+
+        assert_eq!(lines[1], "Reason: CPU jammed at $8007");
+        assert_eq!(lines[2], format!("ROM CRC32: {hash:08X}"));
+        assert_eq!(lines[6], "Last 17 instructions, oldest first");
+        assert!(lines[7].starts_with("8000  A2 05     LDX #$05        A:00 X:00 Y:00 P:24 SP:FD PPU:"));
+        assert!(lines[8].starts_with("8002  86 10     STX $10         A:00 X:05"));
+        assert!(lines[23].starts_with("8007  02        *KIL            A:00 X:00 Y:00 P:26"));
+
+        let ram = lines.iter().position(|line| *line == "RAM").unwrap();
+        assert_eq!(lines[ram + 1], "0000  00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00");
+        assert_eq!(lines[ram + 2], "0010  01 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00");
+        assert_eq!(lines[ram + 3], "0020  00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00");
+        assert_eq!(lines[ram + 4], "*");
+        assert_eq!(lines[ram + 5], "");
+        assert_eq!(lines[ram + 6], "VRAM");
+    }
+
+    #[test]
+    fn test_crash_reporter() {
+        let path = std::env::temp_dir().join("nes_crash_reporter.txt");
+        let _ = std::fs::remove_file(&path);
+        let mut reporter = CrashReporter::new(path.clone());
+
+        // the write to ROM is only logged
+        let mut rom = crashing_rom();
+        rom.prg_rom[..3].copy_from_slice(&[0x8D, 0x00, 0x80]);
+        let mut nes = Nes::from_rom(rom).unwrap();
+        assert_eq!(reporter.run(&mut nes, |nes| nes.step()), Ok(()));
+        let report = std::fs::read_to_string(&path).unwrap();
+        assert!(report.starts_with("NES crash report\nReason: Write of $00 to ROM at $8000"));
+
+        assert_eq!(
+            reporter.run(&mut nes, |_| panic!("bad mapper")),
+            Err(format!(
+                "Emulator panicked: bad mapper, crash report written to {}",
+                path.display()
+            ))
+        );
+        assert!(std::fs::read_to_string(&path)
+            .unwrap()
+            .contains("Reason: Emulator panicked: bad mapper"));
+    }
+
+    #[test]
+    fn test_catch_panic() {
+        assert_eq!(catch_panic(|| 1), Ok(1));
+        assert_eq!(
+            catch_panic(|| panic!("bad {}", "mapper")),
+            Err::<(), _>("Emulator panicked: bad mapper".to_string())
+        );
+    }
+}
@@ -0,0 +1,529 @@
+use std::collections::BTreeSet;
+
+use super::{
+    disasm::{disassemble_line, listing_start},
+    watchpoint::{Access, Space, WatchHit, Watchpoint},
+};
+use crate::{cpu::CpuBus, Nes};
+
+const JSR: u8 = 0x20;
+const RTS: u8 = 0x60;
+const RTI: u8 = 0x40;
+
+const HELP: &str = "\
+c, continue              run until a breakpoint or watchpoint
+s, step [n]              execute n instructions (default 1)
+n, next                  step over subroutine calls
+o, out                   run until the current subroutine returns
+b, break [addr]          set a breakpoint, list them without an address
+delete <addr>            remove a breakpoint
+w, watch [cpu|ppu] <addr>[-<end>] [r|w|rw]
+                         stop on accesses to an address range, list them without arguments
+unwatch <n>              remove the watchpoint number n
+r, regs                  show the registers
+x, mem <addr> [len]      hexdump CPU memory
+d, dis [addr] [count]    disassemble around PC or from an address
+q, quit                  exit the emulator
+Addresses are hex, an empty line repeats the last command";
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Mode {
+    Paused,
+    Running,
+    // a JSR is being stepped over, stop when it returns to the next instruction
+    StepOver { return_addr: u16, stack: u8 },
+    // stop when a RTS or RTI pops the current stack frame
+    StepOut { stack: u8 },
+    Quit,
+}
+
+// Command line monitor. The frontend calls `run_frame` instead of stepping the Nes by itself and feeds
+// the lines typed by the user to `execute` while the debugger is paused
+pub struct Debugger {
+    breakpoints: BTreeSet<u16>,
+    mode: Mode,
+    last_command: String,
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        Debugger {
+            breakpoints: BTreeSet::new(),
+            mode: Mode::Paused,
+            last_command: String::new(),
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.mode == Mode::Paused
+    }
+
+    pub fn quit_requested(&self) -> bool {
+        self.mode == Mode::Quit
+    }
+
+    pub fn pause(&mut self) {
+        if self.mode != Mode::Quit {
+            self.mode = Mode::Paused;
+        }
+    }
+
+    // Runs until the end of the frame, returns why the execution stopped before that
+    pub fn run_frame(&mut self, nes: &mut Nes) -> Option<String> {
+        let frames = nes.frame_count();
+        while !self.is_paused() && !self.quit_requested() && nes.frame_count() == frames {
+            if let Some(reason) = self.step(nes) {
+                return Some(reason);
+            }
+        }
+        None
+    }
+
+    // Instruction at PC, shown when the monitor takes over
+    pub fn location(&self, nes: &Nes) -> String {
+        let pc = nes.cpu().program_counter;
+        format!("> {}", disassemble_line(&nes.cpu().bus, pc).0)
+    }
+
+    pub fn execute(&mut self, nes: &mut Nes, line: &str) -> String {
+        let line = match line.trim() {
+            "" => self.last_command.clone(),
+            line => line.to_string(),
+        };
+        self.last_command = line.clone();
+
+        let mut args = line.split_whitespace();
+        let Some(command) = args.next() else {
+            return String::new();
+        };
+        let args: Vec<&str> = args.collect();
+
+        let result = match command {
+            "h" | "help" => Ok(HELP.to_string()),
+            "c" | "continue" => {
+                self.mode = Mode::Running;
+                Ok(String::new())
+            }
+            "s" | "step" => self.step_into(nes, &args),
+            "n" | "next" => self.step_over(nes),
+            "o" | "out" => {
+                self.mode = Mode::StepOut {
+                    stack: nes.cpu().stack_counter,
+                };
+                Ok(String::new())
+            }
+            "b" | "break" => self.add_breakpoint(&args),
+            "delete" => self.remove_breakpoint(&args),
+            "w" | "watch" => self.add_watchpoint(nes, &args),
+            "unwatch" => self.remove_watchpoint(nes, &args),
+            "r" | "regs" => Ok(registers(nes)),
+            "x" | "mem" => hexdump(nes, &args),
+            "d" | "dis" => self.disassembly(nes, &args),
+            "q" | "quit" => {
+                self.mode = Mode::Quit;
+                Ok(String::new())
+            }
+            _ => Err(format!("Unknown command {command}, type help for the list")),
+        };
+
+        result.unwrap_or_else(|err| err)
+    }
+
+    // Executes one instruction, returns the reason when it has to stop
+    fn step(&mut self, nes: &mut Nes) -> Option<String> {
+        let pc = nes.cpu().program_counter;
+        let opcode = nes.cpu().bus.peek(pc);
+
+        if !nes.step() {
+            self.mode = Mode::Paused;
+            return Some(match nes.cpu().halted {
+                Some(fault) => fault.to_string(),
+                None => format!("CPU jammed at ${pc:04X}"),
+            });
+        }
+
+        if let Some(fault) = nes.cpu_mut().take_fault_break() {
+            self.mode = Mode::Paused;
+            return Some(fault.to_string());
+        }
+
+        if let Some(hit) = nes.cpu_mut().bus.watchpoints.take_hit() {
+            self.mode = Mode::Paused;
+            return Some(describe_hit(&hit, pc));
+        }
+
+        let cpu = nes.cpu();
+        match self.mode {
+            Mode::StepOver { return_addr, stack }
+                if cpu.program_counter == return_addr && cpu.stack_counter == stack =>
+            {
+                self.mode = Mode::Paused;
+                return Some(String::new());
+            }
+            Mode::StepOut { stack: frame } if matches!(opcode, RTS | RTI) && cpu.stack_counter > frame => {
+                self.mode = Mode::Paused;
+                return Some(String::new());
+            }
+            _ => {}
+        }
+
+        if self.mode != Mode::Paused && self.breakpoints.contains(&cpu.program_counter) {
+            self.mode = Mode::Paused;
+            return Some(format!("Breakpoint at ${:04X}", cpu.program_counter));
+        }
+
+        None
+    }
+
+    fn step_into(&mut self, nes: &mut Nes, args: &[&str]) -> Result<String, String> {
+        let count = args.first().map_or(Ok(1), |count| parse_count(count))?;
+
+        self.mode = Mode::Running;
+        for _ in 0..count {
+            if let Some(reason) = self.step(nes) {
+                self.mode = Mode::Paused;
+                return Ok(reason);
+            }
+        }
+        self.mode = Mode::Paused;
+        Ok(String::new())
+    }
+
+    fn step_over(&mut self, nes: &mut Nes) -> Result<String, String> {
+        let pc = nes.cpu().program_counter;
+        if nes.cpu().bus.peek(pc) != JSR {
+            return self.step_into(nes, &[]);
+        }
+
+        self.mode = Mode::StepOver {
+            return_addr: pc.wrapping_add(3),
+            stack: nes.cpu().stack_counter,
+        };
+        Ok(String::new())
+    }
+
+    fn add_breakpoint(&mut self, args: &[&str]) -> Result<String, String> {
+        match args.first() {
+            Some(addr) => {
+                let addr = parse_addr(addr)?;
+                self.breakpoints.insert(addr);
+                Ok(format!("Breakpoint at ${addr:04X}"))
+            }
+            None if self.breakpoints.is_empty() => Ok("No breakpoints".to_string()),
+            None => Ok(self
+                .breakpoints
+                .iter()
+                .map(|addr| format!("${addr:04X}"))
+                .collect::<Vec<_>>()
+                .join("\n")),
+        }
+    }
+
+    fn remove_breakpoint(&mut self, args: &[&str]) -> Result<String, String> {
+        let addr = parse_addr(args.first().ok_or("Missing address")?)?;
+        if self.breakpoints.remove(&addr) {
+            Ok(format!("Removed breakpoint at ${addr:04X}"))
+        } else {
+            Err(format!("No breakpoint at ${addr:04X}"))
+        }
+    }
+
+    fn add_watchpoint(&mut self, nes: &mut Nes, args: &[&str]) -> Result<String, String> {
+        let watchpoints = &mut nes.cpu_mut().bus.watchpoints;
+        if args.is_empty() {
+            if watchpoints.list().is_empty() {
+                return Ok("No watchpoints".to_string());
+            }
+            return Ok(watchpoints
+                .list()
+                .iter()
+                .enumerate()
+                .map(|(i, w)| format!("{i}: {}", describe_watchpoint(w)))
+                .collect::<Vec<_>>()
+                .join("\n"));
+        }
+
+        let mut args = args.iter().peekable();
+        let space = match args.peek() {
+            Some(&&"cpu") => {
+                args.next();
+                Space::Cpu
+            }
+            Some(&&"ppu") => {
+                args.next();
+                Space::Ppu
+            }
+            _ => Space::Cpu,
+        };
+
+        let range = args.next().ok_or("Missing address")?;
+        let (start, end) = match range.split_once('-') {
+            Some((start, end)) => (parse_addr(start)?, parse_addr(end)?),
+            None => (parse_addr(range)?, parse_addr(range)?),
+        };
+        if end < start {
+            return Err(format!("Invalid range {range}"));
+        }
+
+        let (read, write) = match args.next().copied() {
+            None | Some("rw") => (true, true),
+            Some("r") => (true, false),
+            Some("w") => (false, true),
+            Some(access) => return Err(format!("Invalid access {access}, expected r, w or rw")),
+        };
+
+        let watchpoint = Watchpoint {
+            space,
+            start,
+            end,
+            read,
+            write,
+        };
+        watchpoints.add(watchpoint);
+        Ok(format!(
+            "Watchpoint {}: {}",
+            watchpoints.list().len() - 1,
+            describe_watchpoint(&watchpoint)
+        ))
+    }
+
+    fn remove_watchpoint(&mut self, nes: &mut Nes, args: &[&str]) -> Result<String, String> {
+        let index = parse_count(args.first().ok_or("Missing watchpoint number")?)?;
+        match nes.cpu_mut().bus.watchpoints.remove(index) {
+            Some(w) => Ok(format!("Removed watchpoint {}", describe_watchpoint(&w))),
+            None => Err(format!("No watchpoint {index}")),
+        }
+    }
+
+    fn disassembly(&self, nes: &Nes, args: &[&str]) -> Result<String, String> {
+        let bus = &nes.cpu().bus;
+        let pc = nes.cpu().program_counter;
+        let count = args.get(1).map_or(Ok(10), |count| parse_count(count))?;
+        let mut addr = match args.first() {
+            Some(addr) => parse_addr(addr)?,
+            None => listing_start(bus, pc, 3),
+        };
+
+        let mut lines = Vec::new();
+        for _ in 0..count {
+            let (line, len) = disassemble_line(bus, addr);
+            let marker = if addr == pc { ">" } else { " " };
+            lines.push(format!("{marker} {line}"));
+            addr = addr.wrapping_add(len);
+        }
+
+        Ok(lines.join("\n"))
+    }
+}
+
+impl Default for Debugger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn registers(nes: &Nes) -> String {
+    let cpu = nes.cpu();
+    let flags: String = "NV-BDIZC"
+        .chars()
+        .enumerate()
+        .map(|(i, flag)| match cpu.status & (0b1000_0000 >> i) {
+            0 => flag.to_ascii_lowercase(),
+            _ => flag,
+        })
+        .collect();
+
+    format!(
+        "PC:{:04X} A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X}  {flags}  CYC:{} SL:{}",
+        cpu.program_counter,
+        cpu.register_a,
+        cpu.register_x,
+        cpu.register_y,
+        cpu.status,
+        cpu.stack_counter,
+        cpu.bus.cycles,
+        cpu.bus.ppu().scanline(),
+    )
+}
+
+// The I/O registers read as 0, reading them would change the state of the devices
+fn hexdump(nes: &Nes, args: &[&str]) -> Result<String, String> {
+    let start = parse_addr(args.first().ok_or("Missing address")?)?;
+    let len = args.get(1).map_or(Ok(0x40), |len| parse_count(len))?;
+
+    let bus = &nes.cpu().bus;
+    let mut lines = Vec::new();
+    for row in (0..len).step_by(16) {
+        let addr = start.wrapping_add(row as u16);
+        let bytes: Vec<u8> = (0..16.min(len - row))
+            .map(|i| bus.peek(addr.wrapping_add(i as u16)))
+            .collect();
+
+        let hex = bytes.iter().map(|b| format!("{b:02X}")).collect::<Vec<_>>().join(" ");
+        let ascii: String = bytes
+            .iter()
+            .map(|&b| if b.is_ascii_graphic() { b as char } else { '.' })
+            .collect();
+        lines.push(format!("{addr:04X}  {hex:<47}  {ascii}"));
+    }
+
+    Ok(lines.join("\n"))
+}
+
+fn describe_watchpoint(w: &Watchpoint) -> String {
+    let space = match w.space {
+        Space::Cpu => "cpu",
+        Space::Ppu => "ppu",
+    };
+    let access = match (w.read, w.write) {
+        (true, false) => "r",
+        (false, true) => "w",
+        _ => "rw",
+    };
+
+    if w.start == w.end {
+        format!("{space} ${:04X} {access}", w.start)
+    } else {
+        format!("{space} ${:04X}-${:04X} {access}", w.start, w.end)
+    }
+}
+
+fn describe_hit(hit: &WatchHit, pc: u16) -> String {
+    let space = match hit.space {
+        Space::Cpu => "CPU",
+        Space::Ppu => "PPU",
+    };
+    let access = match hit.access {
+        Access::Read => "read",
+        Access::Write => "write",
+    };
+    format!(
+        "Watchpoint: {space} {access} ${:04X} = ${:02X} by the instruction at ${pc:04X}",
+        hit.addr, hit.data
+    )
+}
+
+fn parse_addr(addr: &str) -> Result<u16, String> {
+    let digits = addr.trim_start_matches('$').trim_start_matches("0x");
+    u16::from_str_radix(digits, 16).map_err(|_| format!("Invalid address {addr}"))
+}
+
+fn parse_count(count: &str) -> Result<usize, String> {
+    count.parse().map_err(|_| format!("Invalid number {count}"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        cpu::Mem,
+        rom::{Mirroring, Rom},
+    };
+
+    // 8000: JSR $8008 / INX / JMP $8000
+    // 8008: STA $0300 / INY / RTS
+    fn test_nes() -> Nes {
+        let program = [
+            0x20, 0x08, 0x80, 0xE8, 0x4C, 0x00, 0x80, 0xEA, 0x8D, 0x00, 0x03, 0xC8, 0x60,
+        ];
+        let mut prg_rom = vec![0; 0x4000];
+        prg_rom[..program.len()].copy_from_slice(&program);
+        prg_rom[0x3FFC..0x3FFE].copy_from_slice(&[0x00, 0x80]);
+
+        Nes::from_rom(Rom {
+            prg_rom,
+            chr_rom: vec![0; 0x2000],
+            mapper: 0,
+            screen_mirroring: Mirroring::HORIZONTAL,
+            battery: false,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_breakpoint_and_continue() {
+        let mut nes = test_nes();
+        let mut debugger = Debugger::new();
+        debugger.execute(&mut nes, "b 800B");
+        debugger.execute(&mut nes, "c");
+
+        assert_eq!(debugger.run_frame(&mut nes).unwrap(), "Breakpoint at $800B");
+        assert!(debugger.is_paused());
+        assert_eq!(nes.cpu().program_counter, 0x800B);
+    }
+
+    #[test]
+    fn test_step_over_and_out() {
+        let mut nes = test_nes();
+        let mut debugger = Debugger::new();
+
+        debugger.execute(&mut nes, "next");
+        debugger.run_frame(&mut nes);
+        assert_eq!(nes.cpu().program_counter, 0x8003);
+        assert_eq!(nes.cpu().register_y, 1);
+
+        debugger.execute(&mut nes, "s 3");
+        assert_eq!(nes.cpu().program_counter, 0x8008);
+        debugger.execute(&mut nes, "out");
+        debugger.run_frame(&mut nes);
+        assert_eq!(nes.cpu().program_counter, 0x8003);
+        assert_eq!(nes.cpu().register_y, 2);
+    }
+
+    #[test]
+    fn test_watchpoint_stops_on_write() {
+        let mut nes = test_nes();
+        let mut debugger = Debugger::new();
+
+        assert_eq!(debugger.execute(&mut nes, "watch 0300 w"), "Watchpoint 0: cpu $0300 w");
+        debugger.execute(&mut nes, "c");
+
+        assert_eq!(
+            debugger.run_frame(&mut nes).unwrap(),
+            "Watchpoint: CPU write $0300 = $00 by the instruction at $8008"
+        );
+        assert_eq!(nes.cpu().program_counter, 0x800B);
+    }
+
+    #[test]
+    fn test_listings_are_not_watched() {
+        let mut nes = test_nes();
+        let mut debugger = Debugger::new();
+        debugger.execute(&mut nes, "watch 8000 r");
+
+        debugger.location(&nes);
+        debugger.execute(&mut nes, "d");
+        debugger.execute(&mut nes, "x 8000 4");
+        assert!(nes.cpu_mut().bus.watchpoints.take_hit().is_none());
+    }
+
+    #[test]
+    fn test_registers_and_memory() {
+        let mut nes = test_nes();
+        let mut debugger = Debugger::new();
+        nes.cpu_mut().mem_write(0x0010, b'N');
+
+        assert!(debugger
+            .execute(&mut nes, "r")
+            .starts_with("PC:8000 A:00 X:00 Y:00 P:24 SP:FD  nv-bdIzc"));
+        assert_eq!(
+            debugger.execute(&mut nes, "x 10 4"),
+            "0010  4E 00 00 00                                      N..."
+        );
+        assert_eq!(
+            debugger.execute(&mut nes, "d 8000 2"),
+            "> 8000  20 08 80  JSR $8008\n  8003  E8        INX"
+        );
+    }
+
+    #[test]
+    fn test_empty_line_repeats_last_command() {
+        let mut nes = test_nes();
+        let mut debugger = Debugger::new();
+
+        debugger.execute(&mut nes, "step");
+        debugger.execute(&mut nes, "");
+        assert_eq!(nes.cpu().program_counter, 0x800B);
+        assert!(debugger.execute(&mut nes, "bogus").starts_with("Unknown command"));
+    }
+}
@@ -0,0 +1,110 @@
+use crate::cpu::{AddressingMode, CpuBus, OPS_CODES};
+
+// Decodes the instruction at `addr`, returns its text (`LDA $0200,X`) and length in bytes. The bytes are
+// peeked, so the registers and the watchpoints don't see the reads
+pub fn disassemble<B: CpuBus>(bus: &B, addr: u16) -> (String, u16) {
+    let op = &OPS_CODES[bus.peek(addr) as usize];
+
+    let lo = bus.peek(addr.wrapping_add(1));
+    let hi = bus.peek(addr.wrapping_add(2));
+    let word = u16::from_le_bytes([lo, hi]);
+
+    let operand = match (&op.mode, op.len) {
+        (AddressingMode::Accumulator, _) => "A".to_string(),
+        (AddressingMode::Immediate, _) => format!("#${lo:02X}"),
+        (AddressingMode::ZeroPage, _) => format!("${lo:02X}"),
+        (AddressingMode::ZeroPage_X, _) => format!("${lo:02X},X"),
+        (AddressingMode::ZeroPage_Y, _) => format!("${lo:02X},Y"),
+        (AddressingMode::Absolute, _) => format!("${word:04X}"),
+        (AddressingMode::Absolute_X, _) => format!("${word:04X},X"),
+        (AddressingMode::Absolute_Y, _) => format!("${word:04X},Y"),
+        (AddressingMode::Indirect, _) => format!("(${word:04X})"),
+        (AddressingMode::Indirect_X, _) => format!("(${lo:02X},X)"),
+        (AddressingMode::Indirect_Y, _) => format!("(${lo:02X}),Y"),
+        // branches, the operand is relative to the next instruction
+        (AddressingMode::NoneAddressing, 2) => {
+            format!("${:04X}", addr.wrapping_add(2).wrapping_add(lo as i8 as u16))
+        }
+        (AddressingMode::NoneAddressing, 3) => format!("${word:04X}"),
+        (AddressingMode::NoneAddressing, _) => String::new(),
+    };
+
+    let text = if operand.is_empty() {
+        op.name.to_string()
+    } else {
+        format!("{} {operand}", op.name)
+    };
+    (text, op.len as u16)
+}
+
+// One listing line: address, raw bytes and the instruction
+pub fn disassemble_line<B: CpuBus>(bus: &B, addr: u16) -> (String, u16) {
+    let (text, len) = disassemble(bus, addr);
+    let bytes = (0..len)
+        .map(|i| format!("{:02X}", bus.peek(addr.wrapping_add(i))))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    (format!("{addr:04X}  {bytes:<8}  {text}"), len)
+}
+
+// Instructions can't be decoded backwards, so decoding starts further before `pc` to give it room to fall
+// in step with the code, and the listing starts `before` instructions ahead of `pc`
+pub fn listing_start<B: CpuBus>(bus: &B, pc: u16, before: usize) -> u16 {
+    let lookback = before as u16 * 3 + 16;
+    for back in (1..=lookback).rev() {
+        let mut addr = pc.wrapping_sub(back);
+        let mut starts = vec![];
+        while addr != pc && pc.wrapping_sub(addr) <= lookback {
+            starts.push(addr);
+            addr = addr.wrapping_add(disassemble(bus, addr).1);
+        }
+        if addr == pc {
+            return starts[starts.len().saturating_sub(before)];
+        }
+    }
+    pc
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cpu::FlatMemory;
+
+    fn test_mem(program: &[u8]) -> FlatMemory {
+        let mut mem = FlatMemory::new();
+        mem.load(0x8000, program);
+        mem
+    }
+
+    #[test]
+    fn test_disassemble_addressing_modes() {
+        let mem = test_mem(&[
+            0xA9, 0x05, 0xBD, 0x00, 0x02, 0xB1, 0x10, 0x0A, 0xD0, 0xFC, 0x20, 0x34, 0x12, 0x02,
+        ]);
+
+        assert_eq!(disassemble(&mem, 0x8000), ("LDA #$05".to_string(), 2));
+        assert_eq!(disassemble(&mem, 0x8002), ("LDA $0200,X".to_string(), 3));
+        assert_eq!(disassemble(&mem, 0x8005), ("LDA ($10),Y".to_string(), 2));
+        assert_eq!(disassemble(&mem, 0x8007), ("ASL A".to_string(), 1));
+        assert_eq!(disassemble(&mem, 0x8008), ("BNE $8006".to_string(), 2));
+        assert_eq!(disassemble(&mem, 0x800A), ("JSR $1234".to_string(), 3));
+        assert_eq!(disassemble(&mem, 0x800D), ("*KIL".to_string(), 1));
+    }
+
+    #[test]
+    fn test_disassemble_line() {
+        let mem = test_mem(&[0x8D, 0x00, 0x03]);
+
+        assert_eq!(disassemble_line(&mem, 0x8000).0, "8000  8D 00 03  STA $0300");
+    }
+
+    #[test]
+    fn test_listing_start_lines_up_with_pc() {
+        // LDA #$05 / STA $0300 / INX / INX
+        let mem = test_mem(&[0xA9, 0x05, 0x8D, 0x00, 0x03, 0xE8, 0xE8]);
+
+        assert_eq!(listing_start(&mem, 0x8006, 3), 0x8000);
+        assert_eq!(listing_start(&mem, 0x8006, 1), 0x8005);
+    }
+}
@@ -0,0 +1,14 @@
+mod cdl;
+mod crash;
+mod debugger;
+mod disasm;
+mod prg_disasm;
+mod profiler;
+mod watchpoint;
+
+pub use cdl::CodeDataLogger;
+pub use crash::CrashReporter;
+pub use debugger::Debugger;
+pub use prg_disasm::disassemble_prg;
+pub use profiler::Profiler;
+pub use watchpoint::*;
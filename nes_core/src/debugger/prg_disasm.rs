@@ -0,0 +1,413 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{
+    cpu::{AddressingMode, OPS_CODES},
+    rom::Rom,
+};
+
+const SWITCHED: u16 = 0x8000;
+const VECTORS: u16 = 0xFFFA;
+const VECTOR_NAMES: [&str; 3] = ["nmi", "reset", "irq"];
+
+// The PRG ROM as banks of the mapper, the last one fixed at the end of the address space and the others
+// switched in at $8000. A single bank is mirrored in both halves
+struct Banks<'a> {
+    prg: &'a [u8],
+    size: usize,
+    count: usize,
+    fixed: u16,
+}
+
+impl<'a> Banks<'a> {
+    // 16 KiB banks with the last one at $C000 like in UxROM, and MMC1 in its power up mode. MMC3 has 8 KiB
+    // banks with the last one at $E000, the code of the others is only followed in the $8000 window
+    fn new(rom: &'a Rom) -> Result<Self, String> {
+        let (size, fixed) = match rom.mapper {
+            0..=3 => (0x4000, 0xC000),
+            4 => (0x2000, 0xE000),
+            mapper => return Err(format!("Mapper {mapper} is not supported by the disassembler")),
+        };
+        Ok(Banks {
+            prg: &rom.prg_rom,
+            size,
+            count: rom.prg_rom.len() / size,
+            fixed,
+        })
+    }
+
+    fn base(&self, bank: usize) -> u16 {
+        if bank == self.count - 1 {
+            self.fixed
+        } else {
+            SWITCHED
+        }
+    }
+
+    fn addr(&self, offset: usize) -> u16 {
+        self.base(offset / self.size) + (offset % self.size) as u16
+    }
+
+    // PRG offset of an address in the fixed bank
+    fn fixed_offset(&self, addr: u16) -> usize {
+        (self.count - 1) * self.size + (addr - self.fixed) as usize
+    }
+
+    // PRG offsets `addr` can land on when the code in `bank` accesses it, more than one when it
+    // depends on the bank switched in at $8000
+    fn resolve(&self, bank: usize, addr: u16) -> Vec<usize> {
+        let last = self.count - 1;
+        let offset = addr.wrapping_sub(SWITCHED) as usize;
+        match addr {
+            _ if addr >= self.fixed => vec![self.fixed_offset(addr)],
+            _ if addr < SWITCHED || offset >= self.size => vec![],
+            _ if self.count == 1 => vec![offset],
+            _ if bank != last => vec![bank * self.size + offset],
+            _ => (0..last).map(|bank| bank * self.size + offset).collect(),
+        }
+    }
+}
+
+struct Disassembler<'a> {
+    banks: Banks<'a>,
+    // length of the instruction starting at each byte, 0 for data and for the operands
+    starts: Vec<u8>,
+    code: Vec<bool>,
+    targets: BTreeSet<usize>,
+    labels: HashMap<usize, String>,
+}
+
+impl Disassembler<'_> {
+    // Follows the code from `entry` until it jumps away or returns, queueing the targets of branches,
+    // JSR and JMP. Bytes that would overlap an instruction already found, run off the bank or jam the
+    // CPU stay data
+    fn trace(&mut self, entry: usize) {
+        let mut queue = vec![entry];
+        while let Some(mut pc) = queue.pop() {
+            loop {
+                let bank = pc / self.banks.size;
+                let opcode = self.banks.prg[pc];
+                let op = &OPS_CODES[opcode as usize];
+                let len = op.len as usize;
+                if self.starts[pc] != 0
+                    || op.name == "*KIL"
+                    || pc + len > (bank + 1) * self.banks.size
+                    || self.code[pc..pc + len].iter().any(|&code| code)
+                {
+                    break;
+                }
+
+                self.starts[pc] = op.len;
+                self.code[pc..pc + len].fill(true);
+
+                let addr = self.banks.addr(pc);
+                let lo = self.banks.prg.get(pc + 1).copied().unwrap_or(0);
+                let word = u16::from_le_bytes([lo, self.banks.prg.get(pc + 2).copied().unwrap_or(0)]);
+                let target = match (opcode, op.mode, op.len) {
+                    // JSR and JMP
+                    (0x20 | 0x4C, _, _) => Some(word),
+                    (_, AddressingMode::NoneAddressing, 2) => Some(branch_target(addr, lo)),
+                    _ => None,
+                };
+                if let Some(target) = target {
+                    for target in self.banks.resolve(bank, target) {
+                        self.targets.insert(target);
+                        queue.push(target);
+                    }
+                }
+
+                match opcode {
+                    // JMP, JMP (ind), RTS, RTI and BRK don't fall through
+                    0x4C | 0x6C | 0x60 | 0x40 | 0x00 => break,
+                    _ => pc += len,
+                }
+            }
+        }
+    }
+
+    fn name_labels(&mut self) {
+        for &target in &self.targets {
+            if self.starts[target] == 0 {
+                continue;
+            }
+            let bank = target / self.banks.size;
+            let addr = self.banks.addr(target);
+            // a window shared by several banks needs the bank in the name
+            let name = if bank == self.banks.count - 1 || self.banks.count <= 2 {
+                format!("L{addr:04X}")
+            } else {
+                format!("B{bank}_{addr:04X}")
+            };
+            self.labels.insert(target, name);
+        }
+
+        // when vectors share a handler the first name wins
+        for (i, name) in VECTOR_NAMES.iter().enumerate().rev() {
+            let vector = self.banks.fixed_offset(VECTORS) + i * 2;
+            let addr = u16::from_le_bytes([self.banks.prg[vector], self.banks.prg[vector + 1]]);
+            for target in self.banks.resolve(self.banks.count - 1, addr) {
+                if self.starts[target] != 0 {
+                    self.labels.insert(target, name.to_string());
+                }
+            }
+        }
+    }
+
+    // The label at `addr` as seen from `bank`, only when it assembles back to the same address
+    fn label(&self, bank: usize, addr: u16) -> Option<&str> {
+        match self.banks.resolve(bank, addr)[..] {
+            [target] if self.banks.addr(target) == addr => self.labels.get(&target).map(|name| name.as_str()),
+            _ => None,
+        }
+    }
+
+    fn operand(&self, bank: usize, addr: u16, absolute: bool) -> String {
+        match self.label(bank, addr) {
+            Some(name) => name.to_string(),
+            // ca65 would pick the zero page mode, which is one byte shorter
+            None if absolute && addr < 0x100 => format!("a:${addr:04X}"),
+            None => format!("${addr:04X}"),
+        }
+    }
+
+    fn instruction(&self, pc: usize) -> String {
+        let bank = pc / self.banks.size;
+        let op = &OPS_CODES[self.banks.prg[pc] as usize];
+        let bytes = &self.banks.prg[pc..pc + op.len as usize];
+        let lo = bytes.get(1).copied().unwrap_or(0);
+        let word = u16::from_le_bytes([lo, bytes.get(2).copied().unwrap_or(0)]);
+
+        let operand = match op.mode {
+            AddressingMode::Accumulator => "A".to_string(),
+            AddressingMode::Immediate => format!("#${lo:02X}"),
+            AddressingMode::ZeroPage => format!("${lo:02X}"),
+            AddressingMode::ZeroPage_X => format!("${lo:02X},X"),
+            AddressingMode::ZeroPage_Y => format!("${lo:02X},Y"),
+            AddressingMode::Absolute => self.operand(bank, word, true),
+            AddressingMode::Absolute_X => format!("{},X", self.operand(bank, word, true)),
+            AddressingMode::Absolute_Y => format!("{},Y", self.operand(bank, word, true)),
+            AddressingMode::Indirect => format!("({})", self.operand(bank, word, false)),
+            AddressingMode::Indirect_X => format!("(${lo:02X},X)"),
+            AddressingMode::Indirect_Y => format!("(${lo:02X}),Y"),
+            AddressingMode::NoneAddressing if op.len == 2 => {
+                self.operand(bank, branch_target(self.banks.addr(pc), lo), false)
+            }
+            AddressingMode::NoneAddressing if op.len == 3 => self.operand(bank, word, false),
+            AddressingMode::NoneAddressing => String::new(),
+        };
+        let text = if operand.is_empty() {
+            op.name.to_string()
+        } else {
+            format!("{} {operand}", op.name)
+        };
+
+        // the unofficial opcodes are spelled differently by each assembler, the bytes are safer
+        if op.name.starts_with('*') {
+            format!("{} ; {text}", byte_list(bytes))
+        } else {
+            text
+        }
+    }
+
+    fn source(&self) -> String {
+        let prg = self.banks.prg;
+        let mut out = format!(
+            "; {} KiB of PRG ROM, reassemble with\n; ca65 game.s && ld65 -t none game.o -o game.prg\n",
+            prg.len() / 1024
+        );
+
+        for bank in 0..self.banks.count {
+            let base = self.banks.base(bank);
+            out += &format!(
+                "\n; bank {bank}, ${base:04X}-${:04X}\n.org ${base:04X}\n",
+                base as usize + self.banks.size - 1
+            );
+
+            let end = (bank + 1) * self.banks.size;
+            let vectors = self.banks.fixed_offset(VECTORS);
+            let mut pc = bank * self.banks.size;
+            while pc < end {
+                if self.starts[pc] != 0 {
+                    if let Some(name) = self.labels.get(&pc) {
+                        out += &format!("{name}:\n");
+                    }
+                    out += &format!("    {}\n", self.instruction(pc));
+                    pc += self.starts[pc] as usize;
+                } else if pc == vectors && !self.code[pc..end].iter().any(|&code| code) {
+                    let words = prg[pc..end]
+                        .chunks(2)
+                        .map(|word| self.operand(bank, u16::from_le_bytes([word[0], word[1]]), false))
+                        .collect::<Vec<_>>();
+                    out += &format!("    .word {}\n", words.join(", "));
+                    pc = end;
+                } else {
+                    // up to 16 bytes a line, stopping at the next instruction or the vectors
+                    let mut data_end = pc + 1;
+                    while data_end < end && data_end - pc < 16 && self.starts[data_end] == 0 && data_end != vectors {
+                        data_end += 1;
+                    }
+                    out += &format!("    {}\n", byte_list(&prg[pc..data_end]));
+                    pc = data_end;
+                }
+            }
+        }
+        out
+    }
+}
+
+fn branch_target(addr: u16, offset: u8) -> u16 {
+    addr.wrapping_add(2).wrapping_add(offset as i8 as u16)
+}
+
+fn byte_list(bytes: &[u8]) -> String {
+    let bytes = bytes.iter().map(|byte| format!("${byte:02X}")).collect::<Vec<_>>();
+    format!(".byte {}", bytes.join(", "))
+}
+
+// Turns the PRG ROM back into ca65 source. The code is found by following the flow from the NMI, reset
+// and IRQ vectors, everything it never reaches is written as `.byte`. Jumps from the fixed bank into
+// the switched window are tried in every bank that can be switched there
+pub fn disassemble_prg(rom: &Rom) -> Result<String, String> {
+    let prg = &rom.prg_rom;
+    let mut disasm = Disassembler {
+        banks: Banks::new(rom)?,
+        starts: vec![0; prg.len()],
+        code: vec![false; prg.len()],
+        targets: BTreeSet::new(),
+        labels: HashMap::new(),
+    };
+
+    let last = disasm.banks.count - 1;
+    for i in 0..VECTOR_NAMES.len() {
+        let vector = disasm.banks.fixed_offset(VECTORS) + i * 2;
+        let addr = u16::from_le_bytes([prg[vector], prg[vector + 1]]);
+        for entry in disasm.banks.resolve(last, addr) {
+            disasm.trace(entry);
+        }
+    }
+
+    disasm.name_labels();
+    Ok(disasm.source())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cpu::assemble;
+    use crate::rom::Mirroring;
+
+    const BANK_SIZE: usize = 0x4000;
+
+    fn test_rom(prg_rom: Vec<u8>) -> Rom {
+        Rom {
+            prg_rom,
+            chr_rom: vec![0; 0x2000],
+            mapper: 0,
+            screen_mirroring: Mirroring::HORIZONTAL,
+            battery: false,
+        }
+    }
+
+    fn nrom() -> Rom {
+        let program = assemble(
+            "reset: LDX #$00
+             loop:  INX
+                    BNE loop
+                    LDA a:$0010
+                    JSR sub
+                    JMP reset
+                    .byte $FF, $02
+             sub:   *NOP $10
+                    RTS",
+            0xC000,
+        );
+        let mut prg = vec![0xFF; BANK_SIZE];
+        prg[..0x13].copy_from_slice(&program.unwrap());
+        prg[0x3FFA..].copy_from_slice(&[0x12, 0xC0, 0x00, 0xC0, 0x00, 0xC0]);
+        test_rom(prg)
+    }
+
+    #[test]
+    fn test_follows_code_from_the_vectors() {
+        let source = disassemble_prg(&nrom()).unwrap();
+
+        assert!(source.contains(
+            "\
+.org $C000
+reset:
+    LDX #$00
+LC002:
+    INX
+    BNE LC002
+    LDA a:$0010
+    JSR LC010
+    JMP reset
+    .byte $FF, $02
+LC010:
+    .byte $04, $10 ; *NOP $10
+nmi:
+    RTS
+    .byte $FF, $FF, $FF, $FF, $FF, $FF, $FF, $FF, $FF, $FF, $FF, $FF, $FF, $FF, $FF, $FF
+"
+        ));
+        assert!(source.ends_with(
+            "    .byte $FF, $FF, $FF, $FF, $FF, $FF, $FF
+    .word nmi, reset, reset
+"
+        ));
+    }
+
+    #[test]
+    fn test_switchable_banks() {
+        // UxROM with 4 banks, the fixed one jumps to $8000 which could be any of the other three
+        let mut prg = vec![0x02; 4 * BANK_SIZE];
+        prg[3 * BANK_SIZE..3 * BANK_SIZE + 3].copy_from_slice(&[0x4C, 0x00, 0x80]);
+        prg[4 * BANK_SIZE - 6..].copy_from_slice(&[0x00, 0xC0, 0x00, 0xC0, 0x00, 0xC0]);
+        prg[BANK_SIZE..BANK_SIZE + 3].copy_from_slice(&[0x4C, 0x00, 0x80]);
+        let source = disassemble_prg(&test_rom(prg)).unwrap();
+
+        assert!(source.contains("nmi:\n    JMP $8000\n"));
+        assert!(source.contains("B1_8000:\n    JMP B1_8000\n"));
+        assert!(!source.contains("B0_8000"));
+    }
+
+    // MMC3 with 8 banks of 8 KiB, the fixed one at $E000 jumps to $8000 and bank 2 jumps to itself
+    fn mmc3() -> Rom {
+        let mut prg = vec![0x02; 8 * 0x2000];
+        prg[7 * 0x2000..7 * 0x2000 + 3].copy_from_slice(&[0x4C, 0x00, 0x80]);
+        prg[8 * 0x2000 - 6..].copy_from_slice(&[0x00, 0xE0, 0x00, 0xE0, 0x00, 0xE0]);
+        prg[2 * 0x2000..2 * 0x2000 + 3].copy_from_slice(&[0x4C, 0x00, 0x80]);
+        let mut rom = test_rom(prg);
+        rom.mapper = 4;
+        rom
+    }
+
+    #[test]
+    fn test_mmc3_banks() {
+        let source = disassemble_prg(&mmc3()).unwrap();
+
+        assert!(source.contains("; bank 7, $E000-$FFFF\n.org $E000\nnmi:\n    JMP $8000\n"));
+        assert!(source.contains("; bank 2, $8000-$9FFF\n.org $8000\nB2_8000:\n    JMP B2_8000\n"));
+        assert!(source.ends_with("    .word nmi, nmi, nmi\n"));
+    }
+
+    #[test]
+    fn test_unsupported_mapper() {
+        let mut rom = nrom();
+        rom.mapper = 7;
+        assert_eq!(
+            disassemble_prg(&rom),
+            Err("Mapper 7 is not supported by the disassembler".to_string())
+        );
+    }
+
+    #[test]
+    fn test_reassembles_to_the_same_rom() {
+        let mut prg = vec![0x02; 4 * BANK_SIZE];
+        prg[3 * BANK_SIZE..3 * BANK_SIZE + 3].copy_from_slice(&[0x4C, 0x00, 0x80]);
+        prg[4 * BANK_SIZE - 6..].copy_from_slice(&[0x00, 0xC0, 0x00, 0xC0, 0x00, 0xC0]);
+
+        for rom in [nrom(), test_rom(prg), mmc3()] {
+            assert_eq!(assemble(&disassemble_prg(&rom).unwrap(), 0), Ok(rom.prg_rom));
+        }
+    }
+}
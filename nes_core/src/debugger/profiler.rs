@@ -0,0 +1,381 @@
+use std::{collections::HashMap, fmt::Write, path::Path};
+
+use crate::cpu::{CpuBus, NesCPU};
+
+const JSR: u8 = 0x20;
+const RTS: u8 = 0x60;
+const RTI: u8 = 0x40;
+const BRK: u8 = 0x00;
+// the sequence of an NMI or IRQ, it counts as part of the handler
+const INTERRUPT_CYCLES: u64 = 7;
+const HOT_ADDRESSES: usize = 20;
+
+// A routine reached through a given chain of calls
+struct Node {
+    routine: u16,
+    parent: usize,
+    children: HashMap<u16, usize>,
+    cycles: u64,
+    calls: u64,
+}
+
+struct Frame {
+    node: usize,
+    // stack pointer before the call pushed anything, the routine returned once it's back there
+    stack: u8,
+}
+
+// Counts the cycles spent at every address and in every routine. Routines are entered with JSR, BRK and
+// the interrupts and left with RTS and RTI, the frames are matched by the stack pointer so routines that
+// jump through an RTS don't unbalance the call stack
+pub struct Profiler {
+    pub cycles: Vec<u64>,
+    nodes: Vec<Node>,
+    stack: Vec<Frame>,
+    labels: HashMap<u16, String>,
+    // address and bus cycles of the instruction running since the last callback
+    last: Option<(u16, usize)>,
+    // JSR, BRK, RTS or RTI about to run, with the stack pointer before it
+    pending: Option<(u8, u8)>,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Profiler {
+            cycles: vec![0; 0x10000],
+            nodes: Vec::new(),
+            stack: Vec::new(),
+            labels: HashMap::new(),
+            last: None,
+            pending: None,
+        }
+    }
+
+    // Names the routines with a label file, from `ld65 -Ln` (`al 00C000 .reset`) or FCEUX (`$C000#reset#`)
+    pub fn add_labels(&mut self, labels: &str) -> Result<(), String> {
+        for (i, line) in labels.lines().enumerate() {
+            let line = line.trim();
+            let label = if let Some(rest) = line.strip_prefix("al ") {
+                rest.split_once(' ').map(|(addr, name)| {
+                    let addr = addr.rsplit(':').next().unwrap();
+                    (addr, name.trim().trim_start_matches('.'))
+                })
+            } else if let Some(rest) = line.strip_prefix('$') {
+                rest.split_once('#')
+                    .map(|(addr, rest)| (addr, rest.split('#').next().unwrap()))
+            } else if line.is_empty() || line.starts_with(';') {
+                continue;
+            } else {
+                None
+            };
+
+            let (addr, name) = label.ok_or(format!("line {}: {line} is not a label", i + 1))?;
+            let addr =
+                u32::from_str_radix(addr, 16).map_err(|_| format!("line {}: ${addr} is not an address", i + 1))?;
+            if !name.is_empty() {
+                // the first label of an address names it
+                self.labels.entry(addr as u16).or_insert(name.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn load_labels(&mut self, path: &Path) -> Result<(), String> {
+        let labels =
+            std::fs::read_to_string(path).map_err(|err| format!("Could not read {}: {err}", path.display()))?;
+        self.add_labels(&labels)
+            .map_err(|err| format!("{}: {err}", path.display()))
+    }
+
+    // Called from the CPU callback, before the instruction at PC runs
+    pub fn log_instruction(&mut self, cpu: &NesCPU) {
+        let pc = cpu.program_counter;
+        let cycles = cpu.bus.cycles;
+        let interrupt = cpu.taken_interrupt.is_some();
+
+        let mut interrupt_cycles = 0;
+        if let Some((last_pc, last_cycles)) = self.last {
+            let spent = (cycles - last_cycles) as u64;
+            interrupt_cycles = if interrupt { spent.min(INTERRUPT_CYCLES) } else { 0 };
+            self.cycles[last_pc as usize] += spent - interrupt_cycles;
+            let node = self.stack.last().unwrap().node;
+            self.nodes[node].cycles += spent - interrupt_cycles;
+        }
+
+        match self.pending.take() {
+            Some((JSR | BRK, stack)) => self.call(pc, stack),
+            Some((_, _)) => {
+                while self.stack.len() > 1 && self.stack.last().unwrap().stack <= cpu.stack_counter {
+                    self.stack.pop();
+                }
+            }
+            None => {}
+        }
+
+        if self.stack.is_empty() {
+            self.nodes.push(Node {
+                routine: pc,
+                parent: 0,
+                children: HashMap::new(),
+                cycles: 0,
+                calls: 1,
+            });
+            self.stack.push(Frame { node: 0, stack: 0 });
+        }
+        if interrupt {
+            self.call(pc, cpu.stack_counter.wrapping_add(3));
+            self.cycles[pc as usize] += interrupt_cycles;
+            self.nodes[self.stack.last().unwrap().node].cycles += interrupt_cycles;
+        }
+
+        let opcode = cpu.bus.peek(pc);
+        if matches!(opcode, JSR | BRK | RTS | RTI) {
+            self.pending = Some((opcode, cpu.stack_counter));
+        }
+        self.last = Some((pc, cycles));
+    }
+
+    fn call(&mut self, routine: u16, stack: u8) {
+        let parent = self.stack.last().map_or(0, |frame| frame.node);
+        let node = match self.nodes[parent].children.get(&routine) {
+            Some(&node) => node,
+            None => {
+                self.nodes.push(Node {
+                    routine,
+                    parent,
+                    children: HashMap::new(),
+                    cycles: 0,
+                    calls: 0,
+                });
+                let node = self.nodes.len() - 1;
+                self.nodes[parent].children.insert(routine, node);
+                node
+            }
+        };
+        self.nodes[node].calls += 1;
+        self.stack.push(Frame { node, stack });
+    }
+
+    fn name(&self, addr: u16) -> String {
+        self.labels.get(&addr).cloned().unwrap_or(format!("${addr:04X}"))
+    }
+
+    // cycles of every node with the ones of its callees, children always come after their parent
+    fn totals(&self) -> Vec<u64> {
+        let mut totals: Vec<u64> = self.nodes.iter().map(|node| node.cycles).collect();
+        for i in (1..self.nodes.len()).rev() {
+            totals[self.nodes[i].parent] += totals[i];
+        }
+        totals
+    }
+
+    fn is_recursive(&self, mut node: usize) -> bool {
+        let routine = self.nodes[node].routine;
+        while node != 0 {
+            node = self.nodes[node].parent;
+            if self.nodes[node].routine == routine {
+                return true;
+            }
+        }
+        false
+    }
+
+    // Tables of the routines, the call tree and the hottest addresses, sorted by cycles
+    pub fn report(&self) -> String {
+        let totals = self.totals();
+        let all = totals.first().copied().unwrap_or(0);
+        let percent = |cycles: u64| cycles as f64 * 100.0 / all.max(1) as f64;
+        let mut out = format!("{all} cycles profiled\n");
+
+        // self cycles, total cycles and calls
+        let mut routines: HashMap<u16, (u64, u64, u64)> = HashMap::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            let routine = routines.entry(node.routine).or_default();
+            routine.0 += node.cycles;
+            routine.2 += node.calls;
+            if !self.is_recursive(i) {
+                routine.1 += totals[i];
+            }
+        }
+        let mut routines: Vec<_> = routines.into_iter().collect();
+        routines.sort_by_key(|&(addr, (cycles, ..))| (std::cmp::Reverse(cycles), addr));
+
+        out += "\nRoutines\n      self       %      total       %    calls  routine\n";
+        for (addr, (cycles, total, calls)) in routines {
+            let _ = writeln!(
+                out,
+                "{cycles:>10} {:>6.2}% {total:>10} {:>6.2}% {calls:>8}  {}",
+                percent(cycles),
+                percent(total),
+                self.name(addr)
+            );
+        }
+
+        out += "\nCall tree\n     total       %       self    calls  routine\n";
+        let mut nodes = if self.nodes.is_empty() { vec![] } else { vec![(0, 0)] };
+        while let Some((node, depth)) = nodes.pop() {
+            let _ = writeln!(
+                out,
+                "{:>10} {:>6.2}% {:>10} {:>8}  {:indent$}{}",
+                totals[node],
+                percent(totals[node]),
+                self.nodes[node].cycles,
+                self.nodes[node].calls,
+                "",
+                self.name(self.nodes[node].routine),
+                indent = depth * 2
+            );
+            let mut children: Vec<usize> = self.nodes[node].children.values().copied().collect();
+            // the stack pops the biggest child first
+            children.sort_by_key(|&child| (totals[child], std::cmp::Reverse(self.nodes[child].routine)));
+            nodes.extend(children.into_iter().map(|child| (child, depth + 1)));
+        }
+
+        let mut addresses: Vec<usize> = (0..self.cycles.len()).filter(|&addr| self.cycles[addr] > 0).collect();
+        addresses.sort_by_key(|&addr| (std::cmp::Reverse(self.cycles[addr]), addr));
+
+        out += "\nAddresses\n    cycles       %  address\n";
+        for addr in addresses.into_iter().take(HOT_ADDRESSES) {
+            let label = self
+                .labels
+                .get(&(addr as u16))
+                .map_or(String::new(), |label| format!(" {label}"));
+            let _ = writeln!(
+                out,
+                "{:>10} {:>6.2}%  ${addr:04X}{label}",
+                self.cycles[addr],
+                percent(self.cycles[addr])
+            );
+        }
+        out
+    }
+
+    // The self cycles of every call chain, one `outer;inner cycles` line each, for flamegraph tools
+    pub fn collapsed_stacks(&self) -> String {
+        let mut lines: Vec<String> = Vec::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            if node.cycles == 0 {
+                continue;
+            }
+
+            let mut path = vec![self.name(node.routine)];
+            let mut parent = i;
+            while parent != 0 {
+                parent = self.nodes[parent].parent;
+                path.push(self.name(self.nodes[parent].routine));
+            }
+            path.reverse();
+            lines.push(format!("{} {}\n", path.join(";"), node.cycles));
+        }
+        lines.sort();
+        lines.concat()
+    }
+
+    pub fn write(&self, report: &Path, collapsed_stacks: Option<&Path>) -> Result<(), String> {
+        std::fs::write(report, self.report()).map_err(|err| format!("Could not write {}: {err}", report.display()))?;
+        if let Some(path) = collapsed_stacks {
+            std::fs::write(path, self.collapsed_stacks())
+                .map_err(|err| format!("Could not write {}: {err}", path.display()))?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{cpu::assemble, rom::Mirroring, rom::Rom, Nes};
+
+    fn test_nes(program: &str, nmi: u16, irq: u16, profiler: Profiler) -> Nes {
+        let mut prg_rom = vec![0; 0x4000];
+        let program = assemble(program, 0xC000).unwrap();
+        prg_rom[..program.len()].copy_from_slice(&program);
+        for (i, vector) in [nmi, 0xC000, irq].into_iter().enumerate() {
+            prg_rom[0x3FFA + i * 2..0x3FFC + i * 2].copy_from_slice(&vector.to_le_bytes());
+        }
+
+        let mut nes = Nes::from_rom(Rom {
+            prg_rom,
+            chr_rom: vec![0; 0x2000],
+            mapper: 0,
+            screen_mirroring: Mirroring::HORIZONTAL,
+            battery: false,
+        })
+        .unwrap();
+        nes.set_profiler(Some(profiler));
+        nes
+    }
+
+    #[test]
+    fn test_cycles_per_routine() {
+        let program = "reset: JSR outer / JMP reset
+                       outer: JSR inner / JSR inner / RTS
+                       inner: NOP / RTS";
+        let mut profiler = Profiler::new();
+        profiler
+            .add_labels("al 00C000 .reset\n$C006#outer#called once\n")
+            .unwrap();
+        let mut nes = test_nes(program, 0, 0, profiler);
+        // 10 times around the loop of 9 instructions, the last JMP is counted when the next one starts
+        for _ in 0..91 {
+            nes.step();
+        }
+        let profiler = nes.profiler().unwrap();
+
+        assert_eq!(profiler.cycles[0xC000], 60);
+        assert_eq!(profiler.cycles[0xC00D], 40);
+        assert_eq!(
+            profiler.collapsed_stacks(),
+            "reset 90\nreset;outer 180\nreset;outer;$C00D 160\n"
+        );
+
+        let report = profiler.report();
+        assert!(report.starts_with("430 cycles profiled\n"));
+        assert!(report.contains("       180  41.86%        340  79.07%       10  outer\n"));
+        assert!(report.contains(
+            "       430 100.00%         90        1  reset\n\
+             \x20      340  79.07%        180       10    outer\n\
+             \x20      160  37.21%        160       20      $C00D\n"
+        ));
+        assert!(report.contains("        60  13.95%  $C000 reset\n"));
+    }
+
+    #[test]
+    fn test_brk_and_jumps_through_rts() {
+        let program = "reset: BRK / .byte 0
+                              JSR jump
+                              JMP reset
+                       jump:  LDA #$C0 / PHA / LDA #$0F / PHA / RTS
+                              .byte $EA
+                              NOP / RTS
+                       irq:   NOP / RTI";
+        let mut nes = test_nes(program, 0, 0xC012, Profiler::new());
+        for _ in 0..12 * 5 + 1 {
+            nes.step();
+        }
+
+        assert_eq!(
+            nes.profiler().unwrap().collapsed_stacks(),
+            "$C000 80\n$C000;$C008 120\n$C000;$C012 40\n"
+        );
+    }
+
+    #[test]
+    fn test_nmi() {
+        let program = "LDA #$80 / STA $2000
+                       loop: JMP loop
+                       nmi:  INX / RTI";
+        let mut nes = test_nes(program, 0xC008, 0, Profiler::new());
+        nes.step_frame();
+        nes.step_frame();
+
+        // the 7 cycles of the interrupt are part of the handler
+        assert!(nes.profiler().unwrap().collapsed_stacks().contains("$C000;$C008 30\n"));
+    }
+}
@@ -0,0 +1,92 @@
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Space {
+    Cpu,
+    Ppu,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Watchpoint {
+    pub space: Space,
+    pub start: u16,
+    pub end: u16,
+    pub read: bool,
+    pub write: bool,
+}
+
+impl Watchpoint {
+    fn matches(&self, space: Space, addr: u16, access: Access) -> bool {
+        let access = match access {
+            Access::Read => self.read,
+            Access::Write => self.write,
+        };
+        access && self.space == space && (self.start..=self.end).contains(&addr)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct WatchHit {
+    pub space: Space,
+    pub addr: u16,
+    pub access: Access,
+    pub data: u8,
+}
+
+// Checked by the bus on every access. PPU addresses are seen when the CPU goes through PPUDATA (0x2007),
+// the fetches made by the PPU itself while rendering are not watched
+pub struct Watchpoints {
+    list: Vec<Watchpoint>,
+    hit: Option<WatchHit>,
+}
+
+impl Watchpoints {
+    pub fn new() -> Self {
+        Watchpoints {
+            list: Vec::new(),
+            hit: None,
+        }
+    }
+
+    pub fn list(&self) -> &[Watchpoint] {
+        &self.list
+    }
+
+    pub fn add(&mut self, watchpoint: Watchpoint) {
+        self.list.push(watchpoint);
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<Watchpoint> {
+        (index < self.list.len()).then(|| self.list.remove(index))
+    }
+
+    pub fn check(&mut self, space: Space, addr: u16, access: Access, data: u8) {
+        if self.list.is_empty() || self.hit.is_some() {
+            return;
+        }
+
+        if self.list.iter().any(|w| w.matches(space, addr, access)) {
+            self.hit = Some(WatchHit {
+                space,
+                addr,
+                access,
+                data,
+            });
+        }
+    }
+
+    // First access that matched since the last call
+    pub fn take_hit(&mut self) -> Option<WatchHit> {
+        self.hit.take()
+    }
+}
+
+impl Default for Watchpoints {
+    fn default() -> Self {
+        Self::new()
+    }
+}
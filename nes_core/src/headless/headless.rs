@@ -0,0 +1,134 @@
+use std::path::PathBuf;
+
+use super::input_script::InputScript;
+use crate::{
+    debugger::CrashReporter,
+    nes::{Nes, Options},
+    render::ImageFormat,
+    rom::Rom,
+};
+
+pub struct HeadlessConfig {
+    pub frames: usize,
+    pub input: InputScript,
+    // frames written to disk, counted from 0
+    pub dump_frames: Vec<usize>,
+    pub output_dir: PathBuf,
+    // prefix of the written files, `<name>_<frame>.<ext>`
+    pub name: String,
+    pub format: ImageFormat,
+    pub options: Options,
+}
+
+// Runs the game without a window or audio device, feeding the scripted input at the start of every
+// frame and writing the requested frames to disk. Returns the paths of the written images
+pub fn run_headless(rom: Rom, config: &HeadlessConfig) -> Result<Vec<PathBuf>, String> {
+    std::fs::create_dir_all(&config.output_dir)
+        .map_err(|err| format!("Could not create {}: {err}", config.output_dir.display()))?;
+
+    let mut nes = Nes::from_rom(rom)?;
+    nes.configure(&config.options)?;
+    let mut written = Vec::new();
+    let mut crashes = CrashReporter::new(config.output_dir.join(format!("{}_crash.txt", config.name)));
+
+    for n in 0..config.frames {
+        nes.set_buttons(config.input.buttons_at(n));
+        crashes
+            .run(&mut nes, |nes| nes.step_frame())
+            .map_err(|err| format!("Frame {n}: {err}"))?;
+
+        if config.dump_frames.contains(&n) {
+            let path = config
+                .output_dir
+                .join(format!("{}_{n:05}.{}", config.name, config.format.extension()));
+            std::fs::write(&path, config.format.encode(nes.frame()))
+                .map_err(|err| format!("Could not write {}: {err}", path.display()))?;
+            written.push(path);
+        }
+    }
+
+    nes.finish()?;
+    Ok(written)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rom::Mirroring;
+
+    // JMP $8000 forever
+    fn looping_rom() -> Rom {
+        let mut prg_rom = vec![0; 0x4000];
+        prg_rom[..3].copy_from_slice(&[0x4C, 0x00, 0x80]);
+        prg_rom[0x3FFC..0x3FFE].copy_from_slice(&[0x00, 0x80]);
+
+        Rom {
+            prg_rom,
+            chr_rom: vec![0; 0x2000],
+            mapper: 0,
+            screen_mirroring: Mirroring::HORIZONTAL,
+            battery: false,
+        }
+    }
+
+    fn config(dir: &str, frames: usize, dump_frames: Vec<usize>) -> HeadlessConfig {
+        HeadlessConfig {
+            frames,
+            input: InputScript::new(),
+            dump_frames,
+            output_dir: std::env::temp_dir().join(dir),
+            name: "test".to_string(),
+            format: ImageFormat::Ppm,
+            options: Options::new(),
+        }
+    }
+
+    #[test]
+    fn test_writes_requested_frames() {
+        let config = config("nes_headless_frames", 3, vec![0, 2, 5]);
+        let written = run_headless(looping_rom(), &config).unwrap();
+
+        assert_eq!(
+            written,
+            vec![
+                config.output_dir.join("test_00000.ppm"),
+                config.output_dir.join("test_00002.ppm")
+            ]
+        );
+        assert_eq!(std::fs::read(&written[1]).unwrap().len(), 15 + 256 * 240 * 3);
+    }
+
+    #[test]
+    fn test_writes_logs_at_the_end() {
+        let mut config = config("nes_headless_logs", 2, vec![]);
+        config.options.cdl = Some(config.output_dir.join("test.cdl"));
+        config.options.profile = Some(config.output_dir.join("test_profile.txt"));
+        config.options.trace = Some(config.output_dir.join("test_trace.txt"));
+        run_headless(looping_rom(), &config).unwrap();
+
+        assert_eq!(std::fs::read(config.output_dir.join("test.cdl")).unwrap().len(), 0x6000);
+        assert!(std::fs::read_to_string(config.output_dir.join("test_profile.txt"))
+            .unwrap()
+            .contains("$8000"));
+        let trace = std::fs::read_to_string(config.output_dir.join("test_trace.txt")).unwrap();
+        assert!(trace.starts_with("8000  4C 00 80  JMP $8000"));
+    }
+
+    #[test]
+    fn test_stops_on_jam() {
+        let mut rom = looping_rom();
+        rom.prg_rom[0] = 0x02;
+
+        let config = config("nes_headless_jam", 1, vec![]);
+        let err = run_headless(rom, &config).unwrap_err();
+        let path = config.output_dir.join("test_crash.txt");
+        assert_eq!(
+            err,
+            format!(
+                "Frame 0: CPU jammed at $8000, crash report written to {}",
+                path.display()
+            )
+        );
+        assert!(std::fs::read_to_string(path).unwrap().contains("8000  02        *KIL"));
+    }
+}
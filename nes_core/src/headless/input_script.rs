@@ -0,0 +1,119 @@
+use crate::controller::Joypad;
+
+// Controller input for a headless run. Every line holds a frame number and the buttons that are
+// held from that frame on, until the next line changes them:
+//
+//   # wait for the title screen, then press start for 5 frames
+//   60  START
+//   65  -
+//   120 RIGHT+A
+//
+// `-` releases every button, `#` starts a comment
+pub struct InputScript {
+    changes: Vec<(usize, u8)>,
+}
+
+impl InputScript {
+    pub fn new() -> Self {
+        InputScript { changes: Vec::new() }
+    }
+
+    pub fn parse(script: &str) -> Result<Self, String> {
+        let mut changes: Vec<(usize, u8)> = Vec::new();
+
+        for (i, line) in script.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let err = |msg: String| format!("Input script line {}: {msg}", i + 1);
+
+            let mut parts = line.split_whitespace();
+            let frame = parts.next().unwrap();
+            let frame = frame
+                .parse::<usize>()
+                .map_err(|_| err(format!("invalid frame number {frame}")))?;
+            let buttons = match parts.next() {
+                Some(buttons) => parse_buttons(buttons).map_err(err)?,
+                None => return Err(err("missing buttons".to_string())),
+            };
+            if let Some(extra) = parts.next() {
+                return Err(err(format!("unexpected {extra}")));
+            }
+
+            if changes.last().is_some_and(|&(last, _)| last >= frame) {
+                return Err(err(format!("frame {frame} is not after the previous line")));
+            }
+            changes.push((frame, buttons));
+        }
+
+        Ok(InputScript { changes })
+    }
+
+    // Buttons held during the given frame, as the Joypad bits
+    pub fn buttons_at(&self, frame: usize) -> u8 {
+        self.changes
+            .iter()
+            .take_while(|&&(start, _)| start <= frame)
+            .last()
+            .map_or(0, |&(_, buttons)| buttons)
+    }
+}
+
+impl Default for InputScript {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn parse_buttons(buttons: &str) -> Result<u8, String> {
+    if buttons == "-" {
+        return Ok(0);
+    }
+
+    buttons.split('+').try_fold(0, |acc, button| {
+        let bit = match button.to_ascii_uppercase().as_str() {
+            "A" => Joypad::A,
+            "B" => Joypad::B,
+            "SELECT" => Joypad::SELECT,
+            "START" => Joypad::START,
+            "UP" => Joypad::UP,
+            "DOWN" => Joypad::DOWN,
+            "LEFT" => Joypad::LEFT,
+            "RIGHT" => Joypad::RIGHT,
+            _ => return Err(format!("unknown button {button}")),
+        };
+        Ok(acc | bit)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_buttons_are_held_until_next_line() {
+        let script = InputScript::parse("# title\n10 START\n12 -\n\n20 right+A  # jump\n").unwrap();
+
+        assert_eq!(script.buttons_at(0), 0);
+        assert_eq!(script.buttons_at(10), Joypad::START);
+        assert_eq!(script.buttons_at(11), Joypad::START);
+        assert_eq!(script.buttons_at(12), 0);
+        assert_eq!(script.buttons_at(500), Joypad::RIGHT | Joypad::A);
+    }
+
+    #[test]
+    fn test_invalid_scripts() {
+        assert_eq!(
+            InputScript::parse("10 START\n5 A").err().unwrap(),
+            "Input script line 2: frame 5 is not after the previous line"
+        );
+        assert_eq!(
+            InputScript::parse("10 JUMP").err().unwrap(),
+            "Input script line 1: unknown button JUMP"
+        );
+        assert!(InputScript::parse("ten A").is_err());
+        assert!(InputScript::parse("10").is_err());
+    }
+}
@@ -0,0 +1,5 @@
+mod headless;
+mod input_script;
+
+pub use headless::*;
+pub use input_script::InputScript;
\ No newline at end of file
@@ -0,0 +1,24 @@
+// Every module is split as `foo/mod.rs` re-exporting the items of `foo/foo.rs`
+#![allow(clippy::module_inception)]
+
+mod apu;
+mod bus;
+mod controller;
+pub mod cpu;
+mod debugger;
+mod headless;
+mod mapper;
+mod nes;
+mod ppu;
+mod render;
+mod rom;
+mod savestate;
+
+pub use apu::SAMPLE_RATE;
+pub use controller::Joypad;
+pub use cpu::FaultPolicy;
+pub use debugger::{disassemble_prg, CodeDataLogger, CrashReporter, Debugger, Profiler};
+pub use headless::{run_headless, HeadlessConfig, InputScript};
+pub use nes::{Nes, Options};
+pub use render::{Frame, ImageFormat};
+pub use rom::{Mirroring, Rom};
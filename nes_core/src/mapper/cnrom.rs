@@ -0,0 +1,88 @@
+use super::mapper::{ChrMemory, Mapper};
+use crate::{
+    rom::Mirroring,
+    savestate::{Snapshot, StateReader, StateWriter},
+};
+
+const CHR_BANK_SIZE: usize = 0x2000;
+
+// Mapper 3: fixed PRG ROM like NROM, any write to 0x8000 - 0xFFFF selects an 8KB CHR bank
+pub struct Cnrom {
+    prg_rom: Vec<u8>,
+    chr: ChrMemory,
+    mirroring: Mirroring,
+    chr_bank: usize,
+}
+
+impl Cnrom {
+    pub fn new(prg_rom: Vec<u8>, chr: ChrMemory, mirroring: Mirroring) -> Self {
+        Cnrom {
+            prg_rom,
+            chr,
+            mirroring,
+            chr_bank: 0,
+        }
+    }
+}
+
+impl Mapper for Cnrom {
+    fn read_prg(&self, addr: u16) -> u8 {
+        self.prg_rom[self.prg_addr(addr)]
+    }
+
+    fn write_prg(&mut self, _addr: u16, data: u8) {
+        let banks = (self.chr.data.len() / CHR_BANK_SIZE).max(1);
+        self.chr_bank = data as usize % banks;
+    }
+
+    fn read_chr(&self, addr: u16) -> u8 {
+        self.chr.read(self.chr_addr(addr))
+    }
+
+    fn write_chr(&mut self, addr: u16, data: u8) {
+        self.chr.write(self.chr_addr(addr), data);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn prg_addr(&self, addr: u16) -> usize {
+        (addr - 0x8000) as usize % self.prg_rom.len()
+    }
+
+    fn chr_addr(&self, addr: u16) -> usize {
+        self.chr_bank * CHR_BANK_SIZE + addr as usize
+    }
+}
+
+impl Snapshot for Cnrom {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.chr.save_state(w);
+        w.write_u64(self.chr_bank as u64);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        self.chr.load_state(r)?;
+        self.chr_bank = r.read_u64()? as usize;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_chr_bank_switching() {
+        let mut chr_rom = vec![0; 4 * CHR_BANK_SIZE];
+        for bank in 0..4 {
+            chr_rom[bank * CHR_BANK_SIZE + 0x10] = bank as u8;
+        }
+        let mut cnrom = Cnrom::new(vec![0; 0x8000], ChrMemory::new(chr_rom), Mirroring::HORIZONTAL);
+
+        assert_eq!(cnrom.read_chr(0x10), 0);
+        cnrom.write_prg(0x8000, 3);
+        assert_eq!(cnrom.read_chr(0x10), 3);
+    }
+}
@@ -0,0 +1,103 @@
+use std::{cell::RefCell, rc::Rc};
+
+use super::{cnrom::Cnrom, mmc1::Mmc1, mmc3::Mmc3, nrom::Nrom, uxrom::Uxrom};
+use crate::{
+    rom::{Mirroring, Rom},
+    savestate::{Snapshot, StateReader, StateWriter},
+};
+
+pub const SUPPORTED_MAPPERS: [u8; 5] = [0, 1, 2, 3, 4];
+
+const CHR_RAM_SIZE: usize = 0x2000;
+
+// The cartridge side of the console: every access to PRG ROM (0x8000 - 0xFFFF on the CPU bus)
+// and to the pattern tables (0x0000 - 0x1FFF on the PPU bus) goes through the mapper, which
+// decides what bank is visible and reacts to the writes made to its registers
+pub trait Mapper: Snapshot {
+    fn read_prg(&self, addr: u16) -> u8;
+    fn write_prg(&mut self, addr: u16, data: u8);
+    fn read_chr(&self, addr: u16) -> u8;
+    fn write_chr(&mut self, addr: u16, data: u8);
+    fn mirroring(&self) -> Mirroring;
+
+    // Offsets in PRG ROM and in CHR memory of a CPU and a PPU address with the banks selected now
+    fn prg_addr(&self, addr: u16) -> usize;
+    fn chr_addr(&self, addr: u16) -> usize;
+
+    // Whether writes to 0x8000 - 0xFFFF reach registers, NROM has none
+    fn has_prg_registers(&self) -> bool {
+        true
+    }
+
+    // Clocked by the PPU at the end of every scanline it renders, for the mappers that count them
+    fn scanline(&mut self) {}
+
+    // State of the IRQ line the cartridge can pull
+    fn irq(&self) -> bool {
+        false
+    }
+}
+
+pub fn new_mapper(rom: Rom) -> Result<Rc<RefCell<dyn Mapper>>, String> {
+    let chr = ChrMemory::new(rom.chr_rom);
+
+    Ok(match rom.mapper {
+        0 => Rc::new(RefCell::new(Nrom::new(rom.prg_rom, chr, rom.screen_mirroring))),
+        1 => Rc::new(RefCell::new(Mmc1::new(rom.prg_rom, chr))),
+        2 => Rc::new(RefCell::new(Uxrom::new(rom.prg_rom, chr, rom.screen_mirroring))),
+        3 => Rc::new(RefCell::new(Cnrom::new(rom.prg_rom, chr, rom.screen_mirroring))),
+        4 => Rc::new(RefCell::new(Mmc3::new(rom.prg_rom, chr, rom.screen_mirroring))),
+        x => return Err(format!("Mapper {x} is not supported")),
+    })
+}
+
+// Cartridges without CHR ROM come with 8KB of CHR RAM that the game fills by itself
+pub struct ChrMemory {
+    pub data: Vec<u8>,
+    pub is_ram: bool,
+}
+
+impl ChrMemory {
+    pub fn new(chr_rom: Vec<u8>) -> Self {
+        if chr_rom.is_empty() {
+            ChrMemory {
+                data: vec![0; CHR_RAM_SIZE],
+                is_ram: true,
+            }
+        } else {
+            ChrMemory {
+                data: chr_rom,
+                is_ram: false,
+            }
+        }
+    }
+
+    pub fn read(&self, addr: usize) -> u8 {
+        self.data[addr % self.data.len()]
+    }
+
+    pub fn write(&mut self, addr: usize, data: u8) {
+        if self.is_ram {
+            let len = self.data.len();
+            self.data[addr % len] = data;
+        } else {
+            println!("attempt to write to chr rom space {}", addr);
+        }
+    }
+}
+
+// CHR ROM never changes, only CHR RAM is part of the state
+impl Snapshot for ChrMemory {
+    fn save_state(&self, w: &mut StateWriter) {
+        if self.is_ram {
+            w.write_bytes(&self.data);
+        }
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        if self.is_ram {
+            r.read_into(&mut self.data)?;
+        }
+        Ok(())
+    }
+}
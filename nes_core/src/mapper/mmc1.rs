@@ -0,0 +1,203 @@
+use super::mapper::{ChrMemory, Mapper};
+use crate::{
+    rom::Mirroring,
+    savestate::{Snapshot, StateReader, StateWriter},
+};
+
+const PRG_BANK_SIZE: usize = 0x4000;
+const CHR_BANK_SIZE: usize = 0x1000;
+
+const SHIFT_RESET: u8 = 0b1_0000;
+
+// Mapper 1: registers are loaded one bit at a time through a 5 bit serial port
+//
+// Control (0x8000 - 0x9FFF)
+// 4bit0
+// -----
+// CPPMM
+// |||||
+// |||++- Mirroring (0: one-screen, lower bank; 1: one-screen, upper bank;
+// |||               2: vertical; 3: horizontal)
+// |++--- PRG ROM bank mode (0, 1: switch 32 KB at $8000, ignoring low bit of bank number;
+// |                         2: fix first bank at $8000 and switch 16 KB bank at $C000;
+// |                         3: fix last bank at $C000 and switch 16 KB bank at $8000)
+// +----- CHR ROM bank mode (0: switch 8 KB at a time; 1: switch two separate 4 KB banks)
+pub struct Mmc1 {
+    prg_rom: Vec<u8>,
+    chr: ChrMemory,
+
+    shift_register: u8,
+    control: u8,
+    chr_bank0: u8,
+    chr_bank1: u8,
+    prg_bank: u8,
+}
+
+impl Mmc1 {
+    pub fn new(prg_rom: Vec<u8>, chr: ChrMemory) -> Self {
+        Mmc1 {
+            prg_rom,
+            chr,
+            shift_register: SHIFT_RESET,
+            control: 0b0_1100,
+            chr_bank0: 0,
+            chr_bank1: 0,
+            prg_bank: 0,
+        }
+    }
+
+    fn prg_banks(&self) -> usize {
+        self.prg_rom.len() / PRG_BANK_SIZE
+    }
+
+    fn write_register(&mut self, addr: u16, data: u8) {
+        match addr {
+            0x8000..=0x9FFF => self.control = data,
+            0xA000..=0xBFFF => self.chr_bank0 = data,
+            0xC000..=0xDFFF => self.chr_bank1 = data,
+            _ => self.prg_bank = data & 0b0_1111,
+        }
+    }
+}
+
+impl Mapper for Mmc1 {
+    fn read_prg(&self, addr: u16) -> u8 {
+        self.prg_rom[self.prg_addr(addr)]
+    }
+
+    fn write_prg(&mut self, addr: u16, data: u8) {
+        if data & 0b1000_0000 != 0 {
+            self.shift_register = SHIFT_RESET;
+            self.control |= 0b0_1100;
+            return;
+        }
+
+        let is_last_write = self.shift_register & 1 == 1;
+        self.shift_register = (self.shift_register >> 1) | ((data & 1) << 4);
+
+        if is_last_write {
+            self.write_register(addr, self.shift_register);
+            self.shift_register = SHIFT_RESET;
+        }
+    }
+
+    fn read_chr(&self, addr: u16) -> u8 {
+        self.chr.read(self.chr_addr(addr))
+    }
+
+    fn write_chr(&mut self, addr: u16, data: u8) {
+        self.chr.write(self.chr_addr(addr), data);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        match self.control & 0b11 {
+            0 => Mirroring::SINGLE_SCREEN_LOWER,
+            1 => Mirroring::SINGLE_SCREEN_UPPER,
+            2 => Mirroring::VERTICAL,
+            _ => Mirroring::HORIZONTAL,
+        }
+    }
+
+    fn prg_addr(&self, addr: u16) -> usize {
+        let prg_bank = self.prg_bank as usize;
+        let bank = match ((self.control >> 2) & 0b11, addr) {
+            (0 | 1, 0x8000..=0xBFFF) => prg_bank & !1,
+            (0 | 1, _) => prg_bank | 1,
+            (2, 0x8000..=0xBFFF) => 0,
+            (2, _) => prg_bank,
+            (_, 0x8000..=0xBFFF) => prg_bank,
+            (_, _) => self.prg_banks() - 1,
+        };
+        (bank % self.prg_banks()) * PRG_BANK_SIZE + (addr as usize & 0x3FFF)
+    }
+
+    fn chr_addr(&self, addr: u16) -> usize {
+        let addr = addr as usize;
+        if self.control & 0b1_0000 == 0 {
+            // 8KB mode ignores the low bit of the bank number
+            (self.chr_bank0 & 0b1_1110) as usize * CHR_BANK_SIZE + addr
+        } else if addr < CHR_BANK_SIZE {
+            self.chr_bank0 as usize * CHR_BANK_SIZE + addr
+        } else {
+            self.chr_bank1 as usize * CHR_BANK_SIZE + (addr - CHR_BANK_SIZE)
+        }
+    }
+}
+
+impl Snapshot for Mmc1 {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.chr.save_state(w);
+        w.write_u8(self.shift_register);
+        w.write_u8(self.control);
+        w.write_u8(self.chr_bank0);
+        w.write_u8(self.chr_bank1);
+        w.write_u8(self.prg_bank);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        self.chr.load_state(r)?;
+        self.shift_register = r.read_u8()?;
+        self.control = r.read_u8()?;
+        self.chr_bank0 = r.read_u8()?;
+        self.chr_bank1 = r.read_u8()?;
+        self.prg_bank = r.read_u8()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn serial_write(mmc1: &mut Mmc1, addr: u16, value: u8) {
+        for i in 0..5 {
+            mmc1.write_prg(addr, (value >> i) & 1);
+        }
+    }
+
+    fn test_mmc1() -> Mmc1 {
+        let mut prg_rom = vec![0; 8 * PRG_BANK_SIZE];
+        for bank in 0..8 {
+            prg_rom[bank * PRG_BANK_SIZE] = bank as u8;
+        }
+        Mmc1::new(prg_rom, ChrMemory::new(vec![]))
+    }
+
+    #[test]
+    fn test_power_on_fixes_last_bank() {
+        let mmc1 = test_mmc1();
+
+        assert_eq!(mmc1.read_prg(0x8000), 0);
+        assert_eq!(mmc1.read_prg(0xC000), 7);
+    }
+
+    #[test]
+    fn test_serial_prg_bank_switch() {
+        let mut mmc1 = test_mmc1();
+        serial_write(&mut mmc1, 0xE000, 5);
+
+        assert_eq!(mmc1.read_prg(0x8000), 5);
+        assert_eq!(mmc1.read_prg(0xC000), 7);
+    }
+
+    #[test]
+    fn test_reset_bit_clears_shift_register() {
+        let mut mmc1 = test_mmc1();
+        mmc1.write_prg(0xE000, 1);
+        mmc1.write_prg(0xE000, 1);
+        mmc1.write_prg(0xE000, 0x80);
+        serial_write(&mut mmc1, 0xE000, 2);
+
+        assert_eq!(mmc1.read_prg(0x8000), 2);
+    }
+
+    #[test]
+    fn test_mirroring_control() {
+        let mut mmc1 = test_mmc1();
+        serial_write(&mut mmc1, 0x8000, 0b0_1110);
+        assert_eq!(mmc1.mirroring(), Mirroring::VERTICAL);
+
+        serial_write(&mut mmc1, 0x8000, 0b0_1101);
+        assert_eq!(mmc1.mirroring(), Mirroring::SINGLE_SCREEN_UPPER);
+    }
+}
@@ -0,0 +1,288 @@
+use super::mapper::{ChrMemory, Mapper};
+use crate::{
+    rom::Mirroring,
+    savestate::{Snapshot, StateReader, StateWriter},
+};
+
+const PRG_BANK_SIZE: usize = 0x2000;
+const CHR_BANK_SIZE: usize = 0x0400;
+
+// Bank select (0x8000 - 0x9FFE, even)
+// 7  bit  0
+// ---- ----
+// CPMx xRRR
+// |||   |||
+// |||   +++- Specify which bank register to update on next write to Bank Data register
+// |||        (0-1: 2 KB CHR banks at PPU $0000/$0800 or $1000/$1800;
+// |||         2-5: 1 KB CHR banks at PPU $1000-$1C00 or $0000-$0C00;
+// |||         6-7: 8 KB PRG ROM banks)
+// ||+------- Nothing on the MMC3
+// |+-------- PRG ROM bank mode (0: $8000-$9FFF swappable, $C000-$DFFF fixed to second-last bank;
+// |                             1: $C000-$DFFF swappable, $8000-$9FFF fixed to second-last bank)
+// +--------- CHR A12 inversion (0: two 2 KB banks at $0000-$0FFF, four 1 KB banks at $1000-$1FFF;
+//                               1: two 2 KB banks at $1000-$1FFF, four 1 KB banks at $0000-$0FFF)
+pub struct Mmc3 {
+    prg_rom: Vec<u8>,
+    chr: ChrMemory,
+    four_screen: bool,
+
+    bank_select: u8,
+    registers: [u8; 8],
+    mirroring: Mirroring,
+
+    // Scanline counter, reloaded from the latch when it reaches 0 and raising an IRQ there if enabled
+    irq_latch: u8,
+    irq_counter: u8,
+    irq_reload: bool,
+    irq_enabled: bool,
+    irq_pending: bool,
+}
+
+impl Mmc3 {
+    pub fn new(prg_rom: Vec<u8>, chr: ChrMemory, mirroring: Mirroring) -> Self {
+        Mmc3 {
+            prg_rom,
+            chr,
+            four_screen: mirroring == Mirroring::FOUR_SCREEN,
+            bank_select: 0,
+            registers: [0, 2, 4, 5, 6, 7, 0, 1],
+            mirroring,
+            irq_latch: 0,
+            irq_counter: 0,
+            irq_reload: false,
+            irq_enabled: false,
+            irq_pending: false,
+        }
+    }
+
+    fn prg_banks(&self) -> usize {
+        self.prg_rom.len() / PRG_BANK_SIZE
+    }
+}
+
+impl Mapper for Mmc3 {
+    fn read_prg(&self, addr: u16) -> u8 {
+        self.prg_rom[self.prg_addr(addr)]
+    }
+
+    fn write_prg(&mut self, addr: u16, data: u8) {
+        let even = addr & 1 == 0;
+
+        match (addr, even) {
+            (0x8000..=0x9FFF, true) => self.bank_select = data,
+            (0x8000..=0x9FFF, false) => self.registers[(self.bank_select & 0b111) as usize] = data,
+            (0xA000..=0xBFFF, true) => {
+                if !self.four_screen {
+                    self.mirroring = if data & 1 == 0 { Mirroring::VERTICAL } else { Mirroring::HORIZONTAL };
+                }
+            }
+            (0xA000..=0xBFFF, false) => { /* PRG RAM protect */ }
+            (0xC000..=0xDFFF, true) => self.irq_latch = data,
+            (0xC000..=0xDFFF, false) => {
+                self.irq_counter = 0;
+                self.irq_reload = true;
+            }
+            // disabling also acknowledges a pending IRQ
+            (_, true) => {
+                self.irq_enabled = false;
+                self.irq_pending = false;
+            }
+            (_, false) => self.irq_enabled = true,
+        }
+    }
+
+    fn read_chr(&self, addr: u16) -> u8 {
+        self.chr.read(self.chr_addr(addr))
+    }
+
+    fn write_chr(&mut self, addr: u16, data: u8) {
+        self.chr.write(self.chr_addr(addr), data);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn prg_addr(&self, addr: u16) -> usize {
+        let second_last = self.prg_banks() - 2;
+        let prg_mode = self.bank_select & 0b0100_0000 != 0;
+
+        let bank = match (prg_mode, addr) {
+            (false, 0x8000..=0x9FFF) => self.registers[6] as usize,
+            (true, 0x8000..=0x9FFF) => second_last,
+            (_, 0xA000..=0xBFFF) => self.registers[7] as usize,
+            (false, 0xC000..=0xDFFF) => second_last,
+            (true, 0xC000..=0xDFFF) => self.registers[6] as usize,
+            (_, _) => self.prg_banks() - 1,
+        };
+
+        (bank % self.prg_banks()) * PRG_BANK_SIZE + (addr as usize & 0x1FFF)
+    }
+
+    fn chr_addr(&self, addr: u16) -> usize {
+        // with the A12 inversion set the two halves of the pattern table are swapped
+        let addr = if self.bank_select & 0b1000_0000 != 0 { addr ^ 0x1000 } else { addr } as usize;
+
+        let bank = match addr {
+            0x0000..=0x07FF => (self.registers[0] & !1) as usize + addr / CHR_BANK_SIZE,
+            0x0800..=0x0FFF => (self.registers[1] & !1) as usize + (addr - 0x0800) / CHR_BANK_SIZE,
+            0x1000..=0x13FF => self.registers[2] as usize,
+            0x1400..=0x17FF => self.registers[3] as usize,
+            0x1800..=0x1BFF => self.registers[4] as usize,
+            _ => self.registers[5] as usize,
+        };
+
+        bank * CHR_BANK_SIZE + addr % CHR_BANK_SIZE
+    }
+
+    fn scanline(&mut self) {
+        if self.irq_counter == 0 || self.irq_reload {
+            self.irq_counter = self.irq_latch;
+            self.irq_reload = false;
+        } else {
+            self.irq_counter -= 1;
+        }
+
+        if self.irq_counter == 0 && self.irq_enabled {
+            self.irq_pending = true;
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.irq_pending
+    }
+}
+
+impl Snapshot for Mmc3 {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.chr.save_state(w);
+        w.write_u8(self.bank_select);
+        w.write_bytes(&self.registers);
+        w.write_u8(match self.mirroring {
+            Mirroring::VERTICAL => 0,
+            Mirroring::HORIZONTAL => 1,
+            Mirroring::FOUR_SCREEN => 2,
+            Mirroring::SINGLE_SCREEN_LOWER => 3,
+            Mirroring::SINGLE_SCREEN_UPPER => 4,
+        });
+        w.write_u8(self.irq_latch);
+        w.write_u8(self.irq_counter);
+        w.write_bool(self.irq_reload);
+        w.write_bool(self.irq_enabled);
+        w.write_bool(self.irq_pending);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        self.chr.load_state(r)?;
+        self.bank_select = r.read_u8()?;
+        r.read_into(&mut self.registers)?;
+        self.mirroring = match r.read_u8()? {
+            0 => Mirroring::VERTICAL,
+            1 => Mirroring::HORIZONTAL,
+            2 => Mirroring::FOUR_SCREEN,
+            3 => Mirroring::SINGLE_SCREEN_LOWER,
+            4 => Mirroring::SINGLE_SCREEN_UPPER,
+            mirroring => return Err(format!("Unknown MMC3 mirroring {mirroring}")),
+        };
+        self.irq_latch = r.read_u8()?;
+        self.irq_counter = r.read_u8()?;
+        self.irq_reload = r.read_bool()?;
+        self.irq_enabled = r.read_bool()?;
+        self.irq_pending = r.read_bool()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn test_mmc3() -> Mmc3 {
+        let mut prg_rom = vec![0; 8 * PRG_BANK_SIZE];
+        for bank in 0..8 {
+            prg_rom[bank * PRG_BANK_SIZE] = bank as u8;
+        }
+        let mut chr_rom = vec![0; 16 * CHR_BANK_SIZE];
+        for bank in 0..16 {
+            chr_rom[bank * CHR_BANK_SIZE] = bank as u8;
+        }
+        Mmc3::new(prg_rom, ChrMemory::new(chr_rom), Mirroring::VERTICAL)
+    }
+
+    #[test]
+    fn test_prg_bank_modes() {
+        let mut mmc3 = test_mmc3();
+        mmc3.write_prg(0x8000, 6);
+        mmc3.write_prg(0x8001, 3);
+
+        assert_eq!(mmc3.read_prg(0x8000), 3);
+        assert_eq!(mmc3.read_prg(0xC000), 6);
+        assert_eq!(mmc3.read_prg(0xE000), 7);
+
+        mmc3.write_prg(0x8000, 0b0100_0110);
+        assert_eq!(mmc3.read_prg(0x8000), 6);
+        assert_eq!(mmc3.read_prg(0xC000), 3);
+    }
+
+    #[test]
+    fn test_chr_a12_inversion() {
+        let mut mmc3 = test_mmc3();
+        mmc3.write_prg(0x8000, 2);
+        mmc3.write_prg(0x8001, 9);
+
+        assert_eq!(mmc3.read_chr(0x1000), 9);
+
+        mmc3.write_prg(0x8000, 0b1000_0010);
+        assert_eq!(mmc3.read_chr(0x0000), 9);
+    }
+
+    #[test]
+    fn test_mirroring_register() {
+        let mut mmc3 = test_mmc3();
+        mmc3.write_prg(0xA000, 1);
+
+        assert_eq!(mmc3.mirroring(), Mirroring::HORIZONTAL);
+    }
+
+    #[test]
+    fn test_scanline_irq() {
+        let mut mmc3 = test_mmc3();
+        mmc3.write_prg(0xC000, 2);
+        mmc3.write_prg(0xC001, 0);
+        mmc3.write_prg(0xE001, 0);
+
+        mmc3.scanline(); // reload
+        mmc3.scanline();
+        assert!(!mmc3.irq());
+        mmc3.scanline();
+        assert!(mmc3.irq());
+
+        mmc3.write_prg(0xE000, 0);
+        assert!(!mmc3.irq());
+    }
+
+    #[test]
+    fn test_save_state_mirroring() {
+        let mut mmc3 = test_mmc3();
+        mmc3.write_prg(0xA000, 1);
+        let mut w = StateWriter::new();
+        mmc3.save_state(&mut w);
+        let mut state = w.into_bytes();
+
+        let mut loaded = test_mmc3();
+        loaded.load_state(&mut StateReader::new(&state)).unwrap();
+        assert_eq!(loaded.mirroring(), Mirroring::HORIZONTAL);
+
+        // the fields saved before the mirroring
+        let mut w = StateWriter::new();
+        mmc3.chr.save_state(&mut w);
+        w.write_u8(mmc3.bank_select);
+        w.write_bytes(&mmc3.registers);
+        let mirroring = w.into_bytes().len();
+        state[mirroring] = 9;
+        assert_eq!(
+            loaded.load_state(&mut StateReader::new(&state)),
+            Err("Unknown MMC3 mirroring 9".to_string())
+        );
+    }
+}
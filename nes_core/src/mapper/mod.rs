@@ -0,0 +1,8 @@
+mod mapper;
+mod nrom;
+mod mmc1;
+mod uxrom;
+mod cnrom;
+mod mmc3;
+
+pub use mapper::*;
@@ -0,0 +1,92 @@
+use super::mapper::{ChrMemory, Mapper};
+use crate::{
+    rom::Mirroring,
+    savestate::{Snapshot, StateReader, StateWriter},
+};
+
+// Mapper 0: no bank switching, 16KB of PRG ROM are mirrored to fill 0x8000 - 0xFFFF
+pub struct Nrom {
+    prg_rom: Vec<u8>,
+    chr: ChrMemory,
+    mirroring: Mirroring,
+}
+
+impl Nrom {
+    pub fn new(prg_rom: Vec<u8>, chr: ChrMemory, mirroring: Mirroring) -> Self {
+        Nrom {
+            prg_rom,
+            chr,
+            mirroring,
+        }
+    }
+}
+
+impl Mapper for Nrom {
+    fn read_prg(&self, addr: u16) -> u8 {
+        self.prg_rom[self.prg_addr(addr)]
+    }
+
+    fn write_prg(&mut self, _addr: u16, _data: u8) {}
+
+    fn read_chr(&self, addr: u16) -> u8 {
+        self.chr.read(addr as usize)
+    }
+
+    fn write_chr(&mut self, addr: u16, data: u8) {
+        self.chr.write(addr as usize, data);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn has_prg_registers(&self) -> bool {
+        false
+    }
+
+    fn prg_addr(&self, addr: u16) -> usize {
+        let mut addr = addr - 0x8000;
+        if self.prg_rom.len() == 0x4000 && addr >= 0x4000 {
+            //mirror if needed
+            addr %= 0x4000;
+        }
+        addr as usize
+    }
+
+    fn chr_addr(&self, addr: u16) -> usize {
+        addr as usize
+    }
+}
+
+impl Snapshot for Nrom {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.chr.save_state(w);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        self.chr.load_state(r)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_16kb_prg_rom_is_mirrored() {
+        let mut prg_rom = vec![0; 0x4000];
+        prg_rom[0x0010] = 0x55;
+        let nrom = Nrom::new(prg_rom, ChrMemory::new(vec![0; 0x2000]), Mirroring::VERTICAL);
+
+        assert_eq!(nrom.read_prg(0x8010), 0x55);
+        assert_eq!(nrom.read_prg(0xC010), 0x55);
+    }
+
+    #[test]
+    fn test_chr_ram_is_writable() {
+        let mut nrom = Nrom::new(vec![0; 0x4000], ChrMemory::new(vec![]), Mirroring::VERTICAL);
+        nrom.write_chr(0x1234, 0x66);
+
+        assert_eq!(nrom.read_chr(0x1234), 0x66);
+    }
+}
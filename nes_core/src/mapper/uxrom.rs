@@ -0,0 +1,98 @@
+use super::mapper::{ChrMemory, Mapper};
+use crate::{
+    rom::Mirroring,
+    savestate::{Snapshot, StateReader, StateWriter},
+};
+
+const PRG_BANK_SIZE: usize = 0x4000;
+
+// Mapper 2: 0x8000 - 0xBFFF is a switchable 16KB bank, 0xC000 - 0xFFFF is fixed to the last bank
+pub struct Uxrom {
+    prg_rom: Vec<u8>,
+    chr: ChrMemory,
+    mirroring: Mirroring,
+    prg_bank: usize,
+}
+
+impl Uxrom {
+    pub fn new(prg_rom: Vec<u8>, chr: ChrMemory, mirroring: Mirroring) -> Self {
+        Uxrom {
+            prg_rom,
+            chr,
+            mirroring,
+            prg_bank: 0,
+        }
+    }
+
+    fn prg_banks(&self) -> usize {
+        self.prg_rom.len() / PRG_BANK_SIZE
+    }
+}
+
+impl Mapper for Uxrom {
+    fn read_prg(&self, addr: u16) -> u8 {
+        self.prg_rom[self.prg_addr(addr)]
+    }
+
+    fn write_prg(&mut self, _addr: u16, data: u8) {
+        self.prg_bank = data as usize % self.prg_banks();
+    }
+
+    fn read_chr(&self, addr: u16) -> u8 {
+        self.chr.read(addr as usize)
+    }
+
+    fn write_chr(&mut self, addr: u16, data: u8) {
+        self.chr.write(addr as usize, data);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn prg_addr(&self, addr: u16) -> usize {
+        let bank = match addr {
+            0x8000..=0xBFFF => self.prg_bank,
+            _ => self.prg_banks() - 1,
+        };
+        bank * PRG_BANK_SIZE + (addr as usize & 0x3FFF)
+    }
+
+    fn chr_addr(&self, addr: u16) -> usize {
+        addr as usize
+    }
+}
+
+impl Snapshot for Uxrom {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.chr.save_state(w);
+        w.write_u64(self.prg_bank as u64);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        self.chr.load_state(r)?;
+        self.prg_bank = r.read_u64()? as usize % self.prg_banks();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bank_switching() {
+        let mut prg_rom = vec![0; 4 * PRG_BANK_SIZE];
+        for bank in 0..4 {
+            prg_rom[bank * PRG_BANK_SIZE] = bank as u8;
+        }
+        let mut uxrom = Uxrom::new(prg_rom, ChrMemory::new(vec![]), Mirroring::VERTICAL);
+
+        assert_eq!(uxrom.read_prg(0x8000), 0);
+        assert_eq!(uxrom.read_prg(0xC000), 3);
+
+        uxrom.write_prg(0x8000, 2);
+        assert_eq!(uxrom.read_prg(0x8000), 2);
+        assert_eq!(uxrom.read_prg(0xC000), 3);
+    }
+}
@@ -0,0 +1,5 @@
+mod nes;
+mod options;
+
+pub use nes::Nes;
+pub use options::Options;
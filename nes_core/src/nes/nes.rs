@@ -0,0 +1,343 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+use super::options::Options;
+use crate::{
+    bus::Bus,
+    controller::Joypad,
+    cpu::{self, FaultPolicy, History, NesCPU, CPU},
+    debugger::{CodeDataLogger, Profiler},
+    ppu::NesPPU,
+    render::Frame,
+    rom::Rom,
+    savestate,
+};
+
+// instructions kept for the crash reports
+const HISTORY_SIZE: usize = 4096;
+
+// The whole console behind a small API for frontends: load a ROM, feed the controller, step the
+// emulation and read back the picture and the sound
+pub struct Nes {
+    cpu: NesCPU<'static>,
+    trace: Option<Box<dyn Write>>,
+    cdl: Option<CodeDataLogger>,
+    profiler: Option<Profiler>,
+    // PRG and CHR ROM sizes, for the code/data log
+    rom_sizes: (usize, usize),
+    options: Options,
+}
+
+impl Nes {
+    pub fn new(rom_bytes: &[u8]) -> Result<Self, String> {
+        Nes::from_rom(Rom::new(rom_bytes)?)
+    }
+
+    pub fn from_rom(rom: Rom) -> Result<Self, String> {
+        let rom_sizes = (rom.prg_rom.len(), rom.chr_rom.len());
+        let bus = Bus::new(rom, |_ppu: &NesPPU, _joypad: &mut Joypad| {})?;
+        let mut cpu = CPU::new(bus);
+        cpu.history = History::new(HISTORY_SIZE);
+        cpu.reset();
+
+        Ok(Nes {
+            cpu,
+            trace: None,
+            cdl: None,
+            profiler: None,
+            rom_sizes,
+            options: Options::new(),
+        })
+    }
+
+    // Turns on the tools picked in `options`, `finish` writes what they logged
+    pub fn configure(&mut self, options: &Options) -> Result<(), String> {
+        let cdl = match &options.cdl {
+            Some(path) => Some(CodeDataLogger::open(self.rom_sizes.0, self.rom_sizes.1, path)?),
+            None => None,
+        };
+        let profiler = match &options.profile {
+            Some(_) => {
+                let mut profiler = Profiler::new();
+                if let Some(path) = &options.labels {
+                    profiler.load_labels(path)?;
+                }
+                Some(profiler)
+            }
+            None => None,
+        };
+        let trace: Option<Box<dyn Write>> = match &options.trace {
+            Some(path) => {
+                let file = File::create(path).map_err(|err| format!("Could not create {}: {err}", path.display()))?;
+                Some(Box::new(BufWriter::new(file)))
+            }
+            None => None,
+        };
+
+        self.set_fault_policy(options.faults);
+        self.set_sprite_limit(options.sprite_limit);
+        self.set_code_data_logger(cdl);
+        self.set_profiler(profiler);
+        self.set_trace(trace);
+        self.options = options.clone();
+        Ok(())
+    }
+
+    // Flushes the trace and writes the code/data log and the profile to the files given to `configure`
+    pub fn finish(&mut self) -> Result<(), String> {
+        if let (Some(path), Some(out)) = (&self.options.trace, &mut self.trace) {
+            out.flush()
+                .map_err(|err| format!("Could not write {}: {err}", path.display()))?;
+        }
+        if let (Some(path), Some(cdl)) = (&self.options.cdl, &self.cdl) {
+            cdl.write(path)?;
+        }
+        if let (Some(path), Some(profiler)) = (&self.options.profile, &self.profiler) {
+            profiler.write(path, self.options.flamegraph.as_deref())?;
+        }
+        Ok(())
+    }
+
+    pub fn reset(&mut self) {
+        self.cpu.reset();
+    }
+
+    // Executes one instruction, returns false when the CPU stops
+    pub fn step(&mut self) -> bool {
+        let frames = self.cpu.bus.frames;
+        let running = if self.trace.is_none() && self.cdl.is_none() && self.profiler.is_none() {
+            self.cpu.step()
+        } else {
+            let (trace, cdl, profiler) = (&mut self.trace, &mut self.cdl, &mut self.profiler);
+            let mut result = Ok(());
+            let running = self.cpu.step_with_callback(&mut |cpu: &mut NesCPU| {
+                if let Some(cdl) = cdl {
+                    cdl.log_instruction(cpu);
+                }
+                if let Some(profiler) = profiler {
+                    profiler.log_instruction(cpu);
+                }
+                if let Some(out) = trace {
+                    result = writeln!(out, "{}", cpu::trace(cpu));
+                }
+            });
+            if let Err(err) = result {
+                eprintln!("Could not write the trace, stopping it: {err}");
+                self.trace = None;
+            }
+            running
+        };
+
+        if let (true, Some(cdl)) = (self.cpu.bus.frames != frames, &mut self.cdl) {
+            for offset in self.cpu.bus.ppu_mut().take_pattern_fetches() {
+                cdl.log_pattern_fetch(offset);
+            }
+        }
+        running
+    }
+
+    // Runs until the PPU starts the next scanline
+    pub fn step_scanline(&mut self) -> bool {
+        let scanline = self.cpu.bus.ppu().scanline();
+        while self.cpu.bus.ppu().scanline() == scanline {
+            if !self.step() {
+                return false;
+            }
+        }
+        true
+    }
+
+    // Runs until the PPU finishes the current frame, which is then available in `frame`
+    pub fn step_frame(&mut self) -> bool {
+        let frames = self.cpu.bus.frames;
+        while self.cpu.bus.frames == frames {
+            if !self.step() {
+                return false;
+            }
+        }
+        true
+    }
+
+    // Writes a line in the nestest.log format for every instruction executed from now on
+    pub fn set_trace(&mut self, out: Option<Box<dyn Write>>) {
+        self.trace = out;
+    }
+
+    // Logs how the ROM is used from now on, see `CodeDataLogger`
+    pub fn set_code_data_logger(&mut self, cdl: Option<CodeDataLogger>) {
+        self.cpu.bus.ppu_mut().pattern_fetches = cdl.as_ref().map(|_| Vec::new());
+        self.cdl = cdl;
+    }
+
+    pub fn code_data_logger(&self) -> Option<&CodeDataLogger> {
+        self.cdl.as_ref()
+    }
+
+    // Counts the cycles spent per address and per routine from now on, see `Profiler`
+    pub fn set_profiler(&mut self, profiler: Option<Profiler>) {
+        self.profiler = profiler;
+    }
+
+    pub fn profiler(&self) -> Option<&Profiler> {
+        self.profiler.as_ref()
+    }
+
+    // What happens on a jam, a write to ROM and the other faults. When one halts the CPU, `step` returns
+    // false and `cpu().halted` has the fault
+    pub fn set_fault_policy(&mut self, policy: FaultPolicy) {
+        self.cpu.fault_policy = policy;
+    }
+
+    // Without the limit of 8 sprites per line the games that flicker their sprites show all of them
+    pub fn set_sprite_limit(&mut self, limit: bool) {
+        self.cpu.bus.ppu_mut().sprite_limit = limit;
+    }
+
+    // Buttons held on the first controller, a combination of the `Joypad` bits
+    pub fn set_buttons(&mut self, buttons: u8) {
+        let joypad = self.cpu.bus.joypad1();
+        joypad.set_button_pressed_status(0xFF, false);
+        joypad.set_button_pressed_status(buttons, true);
+    }
+
+    // Last complete frame as 256x240 RGB pixels
+    pub fn frame(&self) -> &Frame {
+        &self.cpu.bus.ppu().frame
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.cpu.bus.frames
+    }
+
+    pub fn take_audio_samples(&mut self) -> Vec<f32> {
+        self.cpu.bus.take_audio_samples()
+    }
+
+    pub fn battery_ram(&self) -> &[u8] {
+        self.cpu.bus.prg_ram()
+    }
+
+    pub fn load_battery_ram(&mut self, data: &[u8]) {
+        self.cpu.bus.load_prg_ram(data);
+    }
+
+    pub fn save_state(&self) -> Vec<u8> {
+        savestate::save_state(&self.cpu)
+    }
+
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), String> {
+        savestate::load_state(&mut self.cpu, data)
+    }
+
+    pub fn program_counter(&self) -> u16 {
+        self.cpu.program_counter
+    }
+
+    pub(crate) fn cpu(&self) -> &NesCPU<'static> {
+        &self.cpu
+    }
+
+    pub(crate) fn cpu_mut(&mut self) -> &mut NesCPU<'static> {
+        &mut self.cpu
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::{cpu::Mem, rom::Mirroring};
+
+    // LDA $4016 / STA $00 / JMP $8000, with the controller strobe left on
+    fn test_nes() -> Nes {
+        let mut prg_rom = vec![0; 0x4000];
+        prg_rom[..8].copy_from_slice(&[0xAD, 0x16, 0x40, 0x85, 0x00, 0x4C, 0x00, 0x80]);
+        prg_rom[0x3FFC..0x3FFE].copy_from_slice(&[0x00, 0x80]);
+
+        Nes::from_rom(Rom {
+            prg_rom,
+            chr_rom: vec![0; 0x2000],
+            mapper: 0,
+            screen_mirroring: Mirroring::HORIZONTAL,
+            battery: false,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_unsupported_mapper() {
+        let rom = Rom {
+            prg_rom: vec![0; 0x4000],
+            chr_rom: vec![0; 0x2000],
+            mapper: 9,
+            screen_mirroring: Mirroring::HORIZONTAL,
+            battery: false,
+        };
+        assert_eq!(Nes::from_rom(rom).err(), Some("Mapper 9 is not supported".to_string()));
+    }
+
+    #[test]
+    fn test_step_frame_and_scanline() {
+        let mut nes = test_nes();
+        assert!(nes.step_frame());
+        assert!(nes.step_frame());
+        assert_eq!(nes.frame_count(), 2);
+
+        let scanline = nes.cpu().bus.ppu().scanline();
+        assert!(nes.step_scanline());
+        assert_eq!(nes.cpu().bus.ppu().scanline(), scanline + 1);
+    }
+
+    #[test]
+    fn test_set_buttons() {
+        let mut nes = test_nes();
+        nes.cpu_mut().bus.joypad1().write(1);
+        nes.set_buttons(Joypad::A | Joypad::START);
+        nes.step_scanline();
+        assert_eq!(nes.cpu_mut().bus.mem_read(0x00), 1);
+
+        nes.set_buttons(Joypad::START);
+        nes.step_scanline();
+        assert_eq!(nes.cpu_mut().bus.mem_read(0x00), 0);
+    }
+
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_trace() {
+        let mut nes = test_nes();
+        let buffer = Rc::new(RefCell::new(Vec::new()));
+        nes.set_trace(Some(Box::new(SharedBuffer(buffer.clone()))));
+        nes.step();
+        nes.step();
+
+        let trace = String::from_utf8(buffer.borrow().clone()).unwrap();
+        assert_eq!(
+            trace,
+            "8000  AD 16 40  LDA $4016 = 00                  A:00 X:00 Y:00 P:24 SP:FD PPU:  0, 21 CYC:7\n\
+             8003  85 00     STA $00 = 00                    A:00 X:00 Y:00 P:26 SP:FD PPU:  0, 33 CYC:11\n"
+        );
+    }
+
+    #[test]
+    fn test_collects_audio_samples() {
+        let mut nes = test_nes();
+        nes.step_frame();
+
+        let samples = nes.take_audio_samples().len();
+        assert!((700..=750).contains(&samples));
+    }
+}
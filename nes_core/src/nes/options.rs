@@ -0,0 +1,38 @@
+use std::path::PathBuf;
+
+use crate::cpu::FaultPolicy;
+
+// The tools and settings a frontend picks from its command line, turned on with `Nes::configure`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    // nestest.log style trace of every instruction
+    pub trace: Option<PathBuf>,
+    // .cdl file the code/data log is merged into
+    pub cdl: Option<PathBuf>,
+    // report of the cycles per routine, the collapsed stacks for flamegraphs and the labels naming the routines
+    pub profile: Option<PathBuf>,
+    pub flamegraph: Option<PathBuf>,
+    pub labels: Option<PathBuf>,
+    pub faults: FaultPolicy,
+    pub sprite_limit: bool,
+}
+
+impl Options {
+    pub fn new() -> Self {
+        Options {
+            trace: None,
+            cdl: None,
+            profile: None,
+            flamegraph: None,
+            labels: None,
+            faults: FaultPolicy::new(),
+            sprite_limit: true,
+        }
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,108 @@
+use crate::savestate::{Snapshot, StateReader, StateWriter};
+
+// The background half of the pixel pipeline. The fetches fill the latches with the tile 2 ahead of the one
+// being drawn, every 8 dots they are loaded into the low byte of the shift registers while the pixels come
+// out of the high byte
+pub struct Background {
+    // latches
+    pub tile: u8,
+    pub palette: u8,
+    pub pattern_lo: u8,
+    pub pattern_hi: u8,
+
+    shift_lo: u16,
+    shift_hi: u16,
+    // the 2 bits of the palette, repeated for each pixel of the tile
+    attribute_lo: u16,
+    attribute_hi: u16,
+}
+
+impl Background {
+    pub fn new() -> Self {
+        Background {
+            tile: 0,
+            palette: 0,
+            pattern_lo: 0,
+            pattern_hi: 0,
+            shift_lo: 0,
+            shift_hi: 0,
+            attribute_lo: 0,
+            attribute_hi: 0,
+        }
+    }
+
+    pub fn load(&mut self) {
+        self.shift_lo = (self.shift_lo & 0xFF00) | self.pattern_lo as u16;
+        self.shift_hi = (self.shift_hi & 0xFF00) | self.pattern_hi as u16;
+        self.attribute_lo = (self.attribute_lo & 0xFF00) | if self.palette & 0b01 != 0 { 0xFF } else { 0 };
+        self.attribute_hi = (self.attribute_hi & 0xFF00) | if self.palette & 0b10 != 0 { 0xFF } else { 0 };
+    }
+
+    pub fn shift(&mut self) {
+        self.shift_lo <<= 1;
+        self.shift_hi <<= 1;
+        self.attribute_lo <<= 1;
+        self.attribute_hi <<= 1;
+    }
+
+    // Pattern value and palette of the pixel, the fine X scroll picks it from the 8 of the tile
+    pub fn pixel(&self, fine_x: u8) -> (u8, u8) {
+        let bit = 15 - fine_x;
+        let value = ((self.shift_hi >> bit) & 1) << 1 | ((self.shift_lo >> bit) & 1);
+        let palette = ((self.attribute_hi >> bit) & 1) << 1 | ((self.attribute_lo >> bit) & 1);
+        (value as u8, palette as u8)
+    }
+}
+
+impl Snapshot for Background {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.tile);
+        w.write_u8(self.palette);
+        w.write_u8(self.pattern_lo);
+        w.write_u8(self.pattern_hi);
+        w.write_u16(self.shift_lo);
+        w.write_u16(self.shift_hi);
+        w.write_u16(self.attribute_lo);
+        w.write_u16(self.attribute_hi);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        self.tile = r.read_u8()?;
+        self.palette = r.read_u8()?;
+        self.pattern_lo = r.read_u8()?;
+        self.pattern_hi = r.read_u8()?;
+        self.shift_lo = r.read_u16()?;
+        self.shift_hi = r.read_u16()?;
+        self.attribute_lo = r.read_u16()?;
+        self.attribute_hi = r.read_u16()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pixels_come_out_after_8_shifts() {
+        let mut background = Background::new();
+        background.pattern_lo = 0b1000_0001;
+        background.pattern_hi = 0b0000_0001;
+        background.palette = 0b10;
+        background.load();
+
+        for _ in 0..8 {
+            background.shift();
+        }
+        background.pattern_lo = 0xFF;
+        background.palette = 0b01;
+        background.load();
+
+        assert_eq!(background.pixel(0), (1, 2));
+        assert_eq!(background.pixel(1), (0, 2));
+        assert_eq!(background.pixel(7), (3, 2));
+        // the fine X scroll reaches into the next tile
+        background.shift();
+        assert_eq!(background.pixel(7), (1, 1));
+    }
+}
@@ -1,3 +1,5 @@
+use crate::savestate::{Snapshot, StateReader, StateWriter};
+
 // 7  bit  0
 // ---- ----
 // VPHB SINN
@@ -20,7 +22,7 @@ const NAMETABLE2: u8             = 0b00000010;
 const VRAM_ADD_INCREMENT: u8     = 0b00000100;
 const SPRITE_PATTERN_ADDR: u8    = 0b00001000;
 const BACKROUND_PATTERN_ADDR: u8 = 0b00010000;
-// const SPRITE_SIZE: u8            = 0b00100000;
+const SPRITE_SIZE: u8            = 0b00100000;
 // const MASTER_SLAVE_SELECT: u8    = 0b01000000;
 const GENERATE_NMI: u8           = 0b10000000;
 
@@ -75,13 +77,13 @@ impl ControlRegister {
         }
     }
 
-    // pub fn sprite_size(&self) -> u16 {
-    //     if self.is_set(SPRITE_SIZE) {
-    //         16
-    //     } else {
-    //         8
-    //     }
-    // }
+    pub fn sprite_size(&self) -> u16 {
+        if self.is_set(SPRITE_SIZE) {
+            16
+        } else {
+            8
+        }
+    }
 
     // pub fn master_slave_select(&self) -> u8 {
     //     self.is_set(MASTER_SLAVE_SELECT) as u8
@@ -91,3 +93,14 @@ impl ControlRegister {
         self.is_set(GENERATE_NMI)
     }
 }
+
+impl Snapshot for ControlRegister {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.bits);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        self.bits = r.read_u8()?;
+        Ok(())
+    }
+}
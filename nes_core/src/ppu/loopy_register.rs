@@ -0,0 +1,178 @@
+// The internal VRAM address of the PPU, `v` is the current one and `t` the top left of the screen. 0x2000,
+// 0x2005 and 0x2006 write to `t`, rendering moves `v` along the name tables
+//
+// yyy NN YYYYY XXXXX
+// ||| || ||||| +++++-- coarse X scroll
+// ||| || +++++-------- coarse Y scroll
+// ||| ++-------------- nametable select
+// +++----------------- fine Y scroll
+
+const COARSE_X: u16 = 0x001F;
+const COARSE_Y: u16 = 0x03E0;
+const NAMETABLE_X: u16 = 0x0400;
+const NAMETABLE_Y: u16 = 0x0800;
+const FINE_Y: u16 = 0x7000;
+
+const HORIZONTAL: u16 = COARSE_X | NAMETABLE_X;
+const VERTICAL: u16 = COARSE_Y | NAMETABLE_Y | FINE_Y;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct LoopyRegister {
+    bits: u16,
+}
+
+impl LoopyRegister {
+    pub fn new() -> Self {
+        LoopyRegister { bits: 0 }
+    }
+
+    pub fn get(&self) -> u16 {
+        self.bits
+    }
+
+    pub fn set(&mut self, bits: u16) {
+        self.bits = bits & 0x7FFF;
+    }
+
+    pub fn coarse_x(&self) -> u16 {
+        self.bits & COARSE_X
+    }
+
+    pub fn coarse_y(&self) -> u16 {
+        (self.bits & COARSE_Y) >> 5
+    }
+
+    pub fn fine_y(&self) -> u16 {
+        self.bits >> 12
+    }
+
+    pub fn set_nametable(&mut self, nametable: u8) {
+        self.bits = (self.bits & !(NAMETABLE_X | NAMETABLE_Y)) | ((nametable as u16 & 0b11) << 10);
+    }
+
+    pub fn set_coarse_x(&mut self, coarse_x: u8) {
+        self.bits = (self.bits & !COARSE_X) | (coarse_x as u16 & 0x1F);
+    }
+
+    pub fn set_coarse_y(&mut self, coarse_y: u8) {
+        self.bits = (self.bits & !COARSE_Y) | ((coarse_y as u16 & 0x1F) << 5);
+    }
+
+    pub fn set_fine_y(&mut self, fine_y: u8) {
+        self.bits = (self.bits & !FINE_Y) | ((fine_y as u16 & 0b111) << 12);
+    }
+
+    // 0x2006 writes the high byte first, its top bit is cleared
+    pub fn set_high_byte(&mut self, data: u8) {
+        self.bits = (self.bits & 0x00FF) | ((data as u16 & 0x3F) << 8);
+    }
+
+    pub fn set_low_byte(&mut self, data: u8) {
+        self.bits = (self.bits & 0xFF00) | data as u16;
+    }
+
+    // 0x2007 accesses outside of rendering
+    pub fn increment(&mut self, inc: u8) {
+        self.set(self.bits + inc as u16);
+    }
+
+    // Next tile, into the next name table at the end of a row
+    pub fn increment_x(&mut self) {
+        if self.coarse_x() == 31 {
+            self.bits &= !COARSE_X;
+            self.bits ^= NAMETABLE_X;
+        } else {
+            self.bits += 1;
+        }
+    }
+
+    // Next row of pixels. The name tables are 30 tiles high, rows 30 and 31 hold the attributes and wrap
+    // back to row 0 of the same name table when the scroll points there
+    pub fn increment_y(&mut self) {
+        if self.fine_y() < 7 {
+            self.bits += 1 << 12;
+            return;
+        }
+
+        self.bits &= !FINE_Y;
+        match self.coarse_y() {
+            29 => {
+                self.set_coarse_y(0);
+                self.bits ^= NAMETABLE_Y;
+            }
+            31 => self.set_coarse_y(0),
+            y => self.set_coarse_y(y as u8 + 1),
+        }
+    }
+
+    pub fn copy_horizontal(&mut self, t: LoopyRegister) {
+        self.bits = (self.bits & !HORIZONTAL) | (t.bits & HORIZONTAL);
+    }
+
+    pub fn copy_vertical(&mut self, t: LoopyRegister) {
+        self.bits = (self.bits & !VERTICAL) | (t.bits & VERTICAL);
+    }
+
+    // Address of the name table entry of the tile
+    pub fn tile_addr(&self) -> u16 {
+        0x2000 | (self.bits & 0x0FFF)
+    }
+
+    // Address of the attribute byte covering the tile
+    pub fn attribute_addr(&self) -> u16 {
+        0x23C0 | (self.bits & 0x0C00) | ((self.bits >> 4) & 0x38) | ((self.bits >> 2) & 0x07)
+    }
+}
+
+impl Default for LoopyRegister {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_increment_x() {
+        let mut v = LoopyRegister::new();
+        v.set_coarse_x(31);
+        v.increment_x();
+        assert_eq!(v.coarse_x(), 0);
+        assert_eq!(v.tile_addr(), 0x2400);
+
+        v.set_coarse_x(31);
+        v.increment_x();
+        assert_eq!(v.tile_addr(), 0x2000);
+    }
+
+    #[test]
+    fn test_increment_y() {
+        let mut v = LoopyRegister::new();
+        v.set_fine_y(7);
+        v.set_coarse_y(29);
+        v.increment_y();
+        assert_eq!((v.fine_y(), v.coarse_y()), (0, 0));
+        assert_eq!(v.tile_addr(), 0x2800);
+
+        // the attribute rows don't switch name tables
+        v.set_fine_y(7);
+        v.set_coarse_y(31);
+        v.increment_y();
+        assert_eq!(v.tile_addr(), 0x2800);
+
+        v.increment_y();
+        assert_eq!((v.fine_y(), v.coarse_y()), (1, 0));
+    }
+
+    #[test]
+    fn test_attribute_addr() {
+        let mut v = LoopyRegister::new();
+        v.set_nametable(3);
+        v.set_coarse_x(31);
+        v.set_coarse_y(29);
+        assert_eq!(v.tile_addr(), 0x2FBF);
+        assert_eq!(v.attribute_addr(), 0x2FFF);
+    }
+}
@@ -1,3 +1,5 @@
+use crate::savestate::{Snapshot, StateReader, StateWriter};
+
 // 7  bit  0
 // ---- ----
 // BGRs bMmG
@@ -12,9 +14,9 @@
 // +--------- Emphasize blue
 
 // const GRAYSCALE: u8 = 0b0000_0001;
-// const LEFTMOST_BACKGROUND: u8 = 0b0000_0010;
-// const LEFTMOST_SPRITES: u8 = 0b0000_0100;
-// const SHOW_BACKGROUND: u8 = 0b0000_1000;
+const LEFTMOST_BACKGROUND: u8 = 0b0000_0010;
+const LEFTMOST_SPRITES: u8 = 0b0000_0100;
+const SHOW_BACKGROUND: u8 = 0b0000_1000;
 const SHOW_SPRITES: u8 = 0b0001_0000;
 // const EMPHASISE_RED: u8 = 0b0010_0000;
 // const EMPHASISE_GREEN: u8 = 0b0100_0000;
@@ -41,7 +43,34 @@ impl MaskRegister {
     //     };
     // }
 
+    pub fn show_background(&self) -> bool {
+        self.bits & SHOW_BACKGROUND != 0
+    }
+
     pub fn show_sprites(&self) -> bool {
         self.bits & SHOW_SPRITES != 0
     }
+
+    pub fn show_leftmost_background(&self) -> bool {
+        self.bits & LEFTMOST_BACKGROUND != 0
+    }
+
+    pub fn show_leftmost_sprites(&self) -> bool {
+        self.bits & LEFTMOST_SPRITES != 0
+    }
+
+    pub fn is_rendering_enabled(&self) -> bool {
+        self.bits & (SHOW_BACKGROUND | SHOW_SPRITES) != 0
+    }
+}
+
+impl Snapshot for MaskRegister {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.bits);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        self.bits = r.read_u8()?;
+        Ok(())
+    }
 }
@@ -1,9 +1,10 @@
 mod ppu;
+mod background;
 mod control_register;
-mod addr_register;
 mod status_register;
 mod mask_register;
-mod scroll_register;
+mod loopy_register;
+mod sprite;
 
 pub use ppu::NesPPU;
 pub use ppu::PPU;
\ No newline at end of file
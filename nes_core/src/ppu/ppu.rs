@@ -0,0 +1,1047 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    mapper::Mapper,
+    render::{Frame, SYSTEM_PALLETE},
+    rom::Mirroring,
+    savestate::{Snapshot, StateReader, StateWriter},
+};
+
+use super::{
+    background::Background, control_register::ControlRegister, loopy_register::LoopyRegister,
+    mask_register::MaskRegister, sprite::Sprite, status_register::StatusRegister,
+};
+
+// sprites the PPU can fetch for a line
+const SPRITES_PER_LINE: usize = 8;
+
+pub struct NesPPU {
+    // PPU Registers
+    pub ctrl: ControlRegister,  // 0x2000
+    pub mask: MaskRegister,     // 0x2001
+    pub status: StatusRegister, // 0x2002
+    pub oam_addr: u8,           // 0x2003
+    pub oam_data: [u8; 256],    // 0x2004
+
+    // Internal registers shared by 0x2000, 0x2005 and 0x2006
+    pub v: LoopyRegister, // current VRAM address
+    pub t: LoopyRegister, // VRAM address of the top left of the screen
+    pub fine_x: u8,
+    pub w: bool, // write toggle, second write when set
+
+    // PPU Memory Map
+    pub palette_table: [u8; 32],         // 0x3F00 - 0x3FFF
+    pub vram: [u8; 4096],                // 0x2000 - 0x3EFF, the upper half on four-screen cartridges only
+    pub mapper: Rc<RefCell<dyn Mapper>>, // 0x0000 - 0x1FFF
+
+    internal_data_buf: u8,
+    scanline: u16,
+    cycles: usize,
+
+    background: Background,
+    // the ones on the line being drawn, in OAM order
+    pub sprites: Vec<Sprite>,
+    // false draws every sprite of the line instead of the first 8, which removes the flicker of the games
+    // that cycle their sprites through OAM. The overflow flag is set the same either way
+    pub sprite_limit: bool,
+    // the last complete picture and the one being drawn
+    pub frame: Frame,
+    back: Frame,
+    // CHR offsets of the pattern bytes fetched for the screen since the last take, when it's Some
+    pub pattern_fetches: Option<Vec<usize>>,
+
+    pub nmi_interrupt: Option<u8>,
+}
+
+// named like `CPU` and `NesPPU`
+#[allow(clippy::upper_case_acronyms)]
+pub trait PPU {
+    fn write_to_ctrl(&mut self, value: u8);
+    fn write_to_mask(&mut self, value: u8);
+    fn read_status(&mut self) -> u8;
+    fn write_to_oam_addr(&mut self, value: u8);
+    fn write_to_oam_data(&mut self, value: u8);
+    fn read_oam_data(&self) -> u8;
+    fn write_to_scroll(&mut self, value: u8);
+    fn write_to_addr(&mut self, value: u8);
+    fn write_to_data(&mut self, value: u8);
+    fn read_data(&mut self) -> u8;
+    fn write_oam_dma(&mut self, value: &[u8; 256]);
+}
+
+impl NesPPU {
+    pub fn new(mapper: Rc<RefCell<dyn Mapper>>) -> Self {
+        NesPPU {
+            ctrl: ControlRegister::new(),
+            mapper,
+            vram: [0; 4096],
+            oam_addr: 0,
+            oam_data: [0; 64 * 4],
+            palette_table: [0; 32],
+            status: StatusRegister::new(),
+            mask: MaskRegister::new(),
+            v: LoopyRegister::new(),
+            t: LoopyRegister::new(),
+            fine_x: 0,
+            w: false,
+            internal_data_buf: 0,
+            scanline: 0,
+            cycles: 0,
+            background: Background::new(),
+            sprites: Vec::with_capacity(SPRITES_PER_LINE),
+            sprite_limit: true,
+            frame: Frame::new(),
+            back: Frame::new(),
+            pattern_fetches: None,
+            nmi_interrupt: None,
+        }
+    }
+
+    pub fn tick(&mut self, cycles: u8) -> bool {
+        let mut frame_done = false;
+        for _ in 0..cycles {
+            frame_done |= self.tick_dot();
+        }
+        frame_done
+    }
+
+    #[inline]
+    fn tick_dot(&mut self) -> bool {
+        self.cycles += 1;
+        if self.cycles < 341 {
+            // the lines of vblank only wait
+            if (240..261).contains(&self.scanline) {
+                return false;
+            }
+            if self.is_rendering() {
+                self.update_vram_addr();
+                self.fetch_background();
+                if self.cycles == 257 {
+                    self.fetch_sprites();
+                }
+            }
+            if self.scanline < 240 && self.cycles <= 256 {
+                self.draw_pixel();
+            }
+            if self.scanline == 261 && self.cycles == 1 {
+                self.status.set_vblank_status(false);
+                self.status.set_sprite_zero_hit(false);
+                self.status.set_sprite_overflow(false);
+            }
+        } else {
+            // the visible lines and the pre-render line fetch sprites, which is what the MMC3 counts
+            if self.is_rendering() {
+                self.mapper.borrow_mut().scanline();
+            }
+
+            self.cycles -= 341;
+            self.scanline += 1;
+
+            if self.scanline == 240 {
+                std::mem::swap(&mut self.frame, &mut self.back);
+            }
+
+            if self.scanline == 241 {
+                self.status.set_vblank_status(true);
+                if self.ctrl.generate_vblank_nmi() {
+                    self.nmi_interrupt = Some(1);
+                }
+            }
+
+            if self.scanline >= 262 {
+                self.scanline = 0;
+                self.nmi_interrupt = None;
+                return true;
+            }
+        }
+        false
+    }
+
+    // The visible lines and the pre-render line with the background or the sprites on
+    fn is_rendering(&self) -> bool {
+        (self.scanline < 240 || self.scanline == 261) && self.mask.is_rendering_enabled()
+    }
+
+    // Rendering moves `v` to the next tile every 8 dots and to the next line at dot 256, then copies the
+    // horizontal scroll back from `t` at dot 257 and, on the pre-render line, the vertical one at dots 280-304
+    fn update_vram_addr(&mut self) {
+        let dot = self.cycles;
+        if (dot <= 256 || dot >= 328) && dot.is_multiple_of(8) {
+            self.v.increment_x();
+        }
+        match dot {
+            256 => self.v.increment_y(),
+            257 => self.v.copy_horizontal(self.t),
+            280..=304 if self.scanline == 261 => self.v.copy_vertical(self.t),
+            _ => {}
+        }
+    }
+
+    // Every 8 dots: the name table byte, the attribute byte and the two bytes of the pattern, then the
+    // shift registers take the tile. Dots 321-336 fetch the first two tiles of the next line
+    fn fetch_background(&mut self) {
+        let dot = self.cycles;
+        if !((2..=257).contains(&dot) || (321..=337).contains(&dot)) {
+            return;
+        }
+
+        self.background.shift();
+        match (dot - 1) % 8 {
+            0 => {
+                self.background.load();
+                self.background.tile = self.read_vram(self.v.tile_addr());
+            }
+            2 => {
+                // each attribute byte covers 4x4 tiles, 2 bits per 2x2 quadrant
+                let shift = ((self.v.coarse_y() & 0b10) << 1) | (self.v.coarse_x() & 0b10);
+                self.background.palette = (self.read_vram(self.v.attribute_addr()) >> shift) & 0b11;
+            }
+            4 => self.background.pattern_lo = self.fetch_pattern(self.background_pattern_addr()),
+            6 => self.background.pattern_hi = self.fetch_pattern(self.background_pattern_addr() + 8),
+            _ => {}
+        }
+    }
+
+    fn background_pattern_addr(&self) -> u16 {
+        self.ctrl.background_pattern_addr() + self.background.tile as u16 * 16 + self.v.fine_y()
+    }
+
+    // The sprites of the next line, the first 8 in OAM order. The hardware looks for them during dots 65-256
+    // and fetches their patterns during dots 257-320, the pre-render line finds none since sprites can't
+    // start on line 0
+    fn fetch_sprites(&mut self) {
+        self.sprites.clear();
+        if self.scanline == 261 {
+            return;
+        }
+
+        let mut index = 0;
+        while index < 64 && self.sprites.len() < SPRITES_PER_LINE {
+            if let Some(row) = self.sprite_row(self.oam_data[index * 4]) {
+                self.push_sprite(index, row);
+            }
+            index += 1;
+        }
+
+        if self.sprite_overflows(index) {
+            self.status.set_sprite_overflow(true);
+        }
+        if !self.sprite_limit {
+            for index in index..64 {
+                if let Some(row) = self.sprite_row(self.oam_data[index * 4]) {
+                    self.push_sprite(index, row);
+                }
+            }
+        }
+    }
+
+    // Row of the sprite at `y` drawn on the next line, if it's on it. The Y position is one less than the
+    // first line of the sprite
+    fn sprite_row(&self, y: u8) -> Option<u16> {
+        let row = self.scanline.wrapping_sub(y as u16);
+        (row < self.ctrl.sprite_size()).then_some(row)
+    }
+
+    // The search for a 9th sprite after the first 8 is buggy: after an entry out of range it moves to the
+    // next byte of the next entry too, so it takes tiles, attributes and X positions as Y positions. That
+    // gives false positives and misses sprites that are on the line
+    fn sprite_overflows(&self, mut index: usize) -> bool {
+        let mut byte = 0;
+        while index < 64 {
+            if self.sprite_row(self.oam_data[index * 4 + byte]).is_some() {
+                return true;
+            }
+            index += 1;
+            byte = (byte + 1) % 4;
+        }
+        false
+    }
+
+    fn push_sprite(&mut self, index: usize, row: u16) {
+        let entry = index * 4;
+        let mut sprite = Sprite {
+            index: index as u8,
+            x: self.oam_data[entry + 3],
+            attributes: self.oam_data[entry + 2],
+            pattern_lo: 0,
+            pattern_hi: 0,
+        };
+        let height = self.ctrl.sprite_size();
+        let row = if sprite.flip_vertical() { height - 1 - row } else { row };
+        let addr = self.sprite_pattern_addr(self.oam_data[entry + 1], row);
+        sprite.pattern_lo = self.fetch_pattern(addr);
+        sprite.pattern_hi = self.fetch_pattern(addr + 8);
+        self.sprites.push(sprite);
+    }
+
+    // 8x16 sprites take the pattern table from bit 0 of the tile index, the top half is the even tile and
+    // the bottom half the odd one after it
+    fn sprite_pattern_addr(&self, tile: u8, row: u16) -> u16 {
+        if self.ctrl.sprite_size() == 8 {
+            return self.ctrl.sprite_pattern_addr() + tile as u16 * 16 + row;
+        }
+
+        let bank = (tile as u16 & 1) * 0x1000;
+        let tile = (tile & 0xFE) as u16 + row / 8;
+        bank + tile * 16 + row % 8
+    }
+
+    fn fetch_pattern(&mut self, addr: u16) -> u8 {
+        if let Some(fetches) = &mut self.pattern_fetches {
+            fetches.push(self.mapper.borrow().chr_addr(addr));
+        }
+        self.read_chr(addr)
+    }
+
+    // Mixes the background and the first opaque sprite at this dot into a pixel of the back frame
+    fn draw_pixel(&mut self) {
+        let x = self.cycles - 1;
+        let mut color = 0;
+
+        if self.mask.is_rendering_enabled() {
+            let (value, palette) = if self.mask.show_background() && (x >= 8 || self.mask.show_leftmost_background()) {
+                self.background.pixel(self.fine_x)
+            } else {
+                (0, 0)
+            };
+            let sprite = if self.mask.show_sprites() && (x >= 8 || self.mask.show_leftmost_sprites()) {
+                self.sprites.iter().find_map(|sprite| match sprite.pixel(x) {
+                    0 => None,
+                    value => Some((sprite, value)),
+                })
+            } else {
+                None
+            };
+
+            // pattern value 0 is transparent and shows the backdrop color. The first opaque sprite picks the
+            // priority even when it's behind the background, hiding the sprites after it in OAM that are in front
+            color = match sprite {
+                Some((sprite, sprite_value)) => {
+                    if sprite.index == 0 && value != 0 && x != 255 {
+                        self.status.set_sprite_zero_hit(true);
+                    }
+                    if sprite.behind_background() && value != 0 {
+                        palette as usize * 4 + value as usize
+                    } else {
+                        0x10 + sprite.palette() as usize * 4 + sprite_value as usize
+                    }
+                }
+                None if value != 0 => palette as usize * 4 + value as usize,
+                None => 0,
+            };
+        }
+
+        let rgb = SYSTEM_PALLETE[self.palette_table[color] as usize & 0x3F];
+        self.back.set_pixel(x, self.scanline as usize, rgb);
+    }
+
+    // The pattern bytes fetched since the last call, empty unless `pattern_fetches` is on
+    pub fn take_pattern_fetches(&mut self) -> Vec<usize> {
+        match &mut self.pattern_fetches {
+            Some(fetches) => std::mem::take(fetches),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn scanline(&self) -> u16 {
+        self.scanline
+    }
+
+    pub fn dot(&self) -> usize {
+        self.cycles
+    }
+
+    pub fn mirroring(&self) -> Mirroring {
+        self.mapper.borrow().mirroring()
+    }
+
+    pub fn read_chr(&self, addr: u16) -> u8 {
+        self.mapper.borrow().read_chr(addr)
+    }
+
+    // The address 0x2007 accesses
+    pub fn vram_addr(&self) -> u16 {
+        self.v.get() & 0x3FFF
+    }
+
+    // A name table byte, `addr` is in 0x2000 - 0x3EFF
+    pub fn read_vram(&self, addr: u16) -> u8 {
+        self.vram[self.mirror_vram_addr(addr) as usize]
+    }
+
+    // During rendering 0x2007 accesses bump both the coarse X and the Y scroll instead
+    fn increment_vram_addr(&mut self) {
+        if self.is_rendering() {
+            self.v.increment_x();
+            self.v.increment_y();
+        } else {
+            self.v.increment(self.ctrl.vram_addr_increment());
+        }
+    }
+
+    // Horizontal:
+    //   [ A ] [ a ]
+    //   [ B ] [ b ]
+
+    // Vertical:
+    //   [ A ] [ B ]
+    //   [ a ] [ b ]
+    pub fn mirror_vram_addr(&self, addr: u16) -> u16 {
+        let mirrored_vram = addr & 0b10111111111111; // mirror down 0x3000-0x3eff to 0x2000 - 0x2eff
+        let vram_index = mirrored_vram - 0x2000; // to vram vector
+        let name_table = vram_index / 0x400; // to the name table index
+        match (self.mirroring(), name_table) {
+            (Mirroring::VERTICAL, 2) | (Mirroring::VERTICAL, 3) => vram_index - 0x800,
+            (Mirroring::HORIZONTAL, 2) => vram_index - 0x400,
+            (Mirroring::HORIZONTAL, 1) => vram_index - 0x400,
+            (Mirroring::HORIZONTAL, 3) => vram_index - 0x800,
+            (Mirroring::SINGLE_SCREEN_LOWER, _) => vram_index % 0x400,
+            (Mirroring::SINGLE_SCREEN_UPPER, _) => vram_index % 0x400 + 0x400,
+            // the cartridge brings the RAM of the other 2 name tables
+            (Mirroring::FOUR_SCREEN, _) => vram_index,
+            _ => vram_index,
+        }
+    }
+}
+
+// the mapper is shared with the bus, which is responsible for saving it
+impl Snapshot for NesPPU {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.ctrl.save_state(w);
+        self.mask.save_state(w);
+        self.status.save_state(w);
+        w.write_u8(self.oam_addr);
+        w.write_bytes(&self.oam_data);
+        w.write_u16(self.v.get());
+        w.write_u16(self.t.get());
+        w.write_u8(self.fine_x);
+        w.write_bool(self.w);
+        self.background.save_state(w);
+        w.write_u8(self.sprites.len() as u8);
+        for sprite in &self.sprites {
+            w.write_u8(sprite.index);
+            w.write_u8(sprite.x);
+            w.write_u8(sprite.attributes);
+            w.write_u8(sprite.pattern_lo);
+            w.write_u8(sprite.pattern_hi);
+        }
+        w.write_bytes(&self.palette_table);
+        w.write_bytes(&self.vram);
+        w.write_u8(self.internal_data_buf);
+        w.write_u16(self.scanline);
+        w.write_u64(self.cycles as u64);
+        w.write_bool(self.nmi_interrupt.is_some());
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), String> {
+        self.ctrl.load_state(r)?;
+        self.mask.load_state(r)?;
+        self.status.load_state(r)?;
+        self.oam_addr = r.read_u8()?;
+        r.read_into(&mut self.oam_data)?;
+        self.v.set(r.read_u16()?);
+        self.t.set(r.read_u16()?);
+        self.fine_x = r.read_u8()? & 0b111;
+        self.w = r.read_bool()?;
+        self.background.load_state(r)?;
+        self.sprites.clear();
+        for _ in 0..r.read_u8()? {
+            self.sprites.push(Sprite {
+                index: r.read_u8()?,
+                x: r.read_u8()?,
+                attributes: r.read_u8()?,
+                pattern_lo: r.read_u8()?,
+                pattern_hi: r.read_u8()?,
+            });
+        }
+        r.read_into(&mut self.palette_table)?;
+        r.read_into(&mut self.vram)?;
+        self.internal_data_buf = r.read_u8()?;
+        self.scanline = r.read_u16()?;
+        self.cycles = r.read_u64()? as usize;
+        self.nmi_interrupt = r.read_bool()?.then_some(1);
+        Ok(())
+    }
+}
+
+impl PPU for NesPPU {
+    fn write_to_ctrl(&mut self, value: u8) {
+        let before_nmi_status = self.ctrl.generate_vblank_nmi();
+        self.ctrl.update(value);
+        self.t.set_nametable(value);
+        if !before_nmi_status && self.ctrl.generate_vblank_nmi() && self.status.is_in_vblank() {
+            self.nmi_interrupt = Some(1);
+        }
+    }
+
+    fn write_to_mask(&mut self, value: u8) {
+        self.mask.update(value);
+    }
+
+    fn read_status(&mut self) -> u8 {
+        let bits = self.status.get_bits();
+        self.status.set_vblank_status(false);
+        self.w = false;
+        bits
+    }
+
+    fn write_to_oam_addr(&mut self, value: u8) {
+        self.oam_addr = value;
+    }
+
+    fn write_to_oam_data(&mut self, value: u8) {
+        self.oam_data[self.oam_addr as usize] = value;
+        self.oam_addr = self.oam_addr.wrapping_add(1);
+    }
+
+    fn read_oam_data(&self) -> u8 {
+        self.oam_data[self.oam_addr as usize]
+    }
+
+    fn write_to_scroll(&mut self, value: u8) {
+        if self.w {
+            self.t.set_coarse_y(value >> 3);
+            self.t.set_fine_y(value & 0b111);
+        } else {
+            self.t.set_coarse_x(value >> 3);
+            self.fine_x = value & 0b111;
+        }
+        self.w = !self.w;
+    }
+
+    // the second write also moves `v`, which is how games change the scroll mid-frame
+    fn write_to_addr(&mut self, value: u8) {
+        if self.w {
+            self.t.set_low_byte(value);
+            self.v = self.t;
+        } else {
+            self.t.set_high_byte(value);
+        }
+        self.w = !self.w;
+    }
+
+    fn write_to_data(&mut self, data: u8) {
+        let addr = self.vram_addr();
+
+        match addr {
+            0..=0x1fff => self.mapper.borrow_mut().write_chr(addr, data),
+            0x2000..=0x3eff => self.vram[self.mirror_vram_addr(addr) as usize] = data,
+            // 0x3f00..=0x3fff, `vram_addr` is 14 bits
+            _ => self.palette_table[palette_index(addr)] = data,
+        };
+        self.increment_vram_addr();
+    }
+
+    fn read_data(&mut self) -> u8 {
+        let addr = self.vram_addr();
+        self.increment_vram_addr();
+
+        match addr {
+            0..=0x1fff => {
+                let result = self.internal_data_buf;
+                self.internal_data_buf = self.read_chr(addr);
+                result
+            }
+            0x2000..=0x3eff => {
+                let result = self.internal_data_buf;
+                self.internal_data_buf = self.vram[self.mirror_vram_addr(addr) as usize];
+                result
+            }
+            _ => self.palette_table[palette_index(addr)],
+        }
+    }
+
+    fn write_oam_dma(&mut self, value: &[u8; 256]) {
+        for i in value {
+            self.write_to_oam_data(*i);
+        }
+    }
+}
+
+// The 32 palette bytes repeat up to 0x3FFF. Addresses $3F10/$3F14/$3F18/$3F1C are mirrors of
+// $3F00/$3F04/$3F08/$3F0C, the backdrop color and the unused color 0 of the background palettes
+fn palette_index(addr: u16) -> usize {
+    let index = (addr & 0x1F) as usize;
+    if index & 0x13 == 0x10 {
+        index - 0x10
+    } else {
+        index
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::mapper::new_mapper;
+    use crate::rom::Rom;
+
+    fn new_ppu(mirroring: Mirroring) -> NesPPU {
+        let rom = Rom {
+            prg_rom: vec![0; 0x4000],
+            chr_rom: vec![0; 2048],
+            mapper: 0,
+            screen_mirroring: mirroring,
+            battery: false,
+        };
+        NesPPU::new(new_mapper(rom).unwrap())
+    }
+
+    fn new_empty_rom() -> NesPPU {
+        new_ppu(Mirroring::HORIZONTAL)
+    }
+
+    #[test]
+    fn test_ppu_vram_writes() {
+        let mut ppu = new_empty_rom();
+        ppu.write_to_addr(0x23);
+        ppu.write_to_addr(0x05);
+        ppu.write_to_data(0x66);
+
+        assert_eq!(ppu.vram[0x0305], 0x66);
+    }
+
+    #[test]
+    fn test_ppu_vram_reads() {
+        let mut ppu = new_empty_rom();
+        ppu.write_to_ctrl(0);
+        ppu.vram[0x0305] = 0x66;
+
+        ppu.write_to_addr(0x23);
+        ppu.write_to_addr(0x05);
+
+        ppu.read_data(); //load_into_buffer
+        assert_eq!(ppu.vram_addr(), 0x2306);
+        assert_eq!(ppu.read_data(), 0x66);
+    }
+
+    #[test]
+    fn test_ppu_vram_mirrors_0x3000() {
+        let mut ppu = new_empty_rom();
+        ppu.write_to_addr(0x33);
+        ppu.write_to_addr(0x05);
+        ppu.write_to_data(0x66);
+        assert_eq!(ppu.vram[0x0305], 0x66);
+
+        // during rendering the fine Y scroll is in the top bits of the address
+        ppu.write_to_mask(0b1000);
+        ppu.v.set(0x7305);
+        ppu.read_data();
+        ppu.v.set(0x7305);
+        assert_eq!(ppu.read_data(), 0x66);
+    }
+
+    #[test]
+    fn test_ppu_vram_reads_cross_page() {
+        let mut ppu = new_empty_rom();
+        ppu.write_to_ctrl(0);
+        ppu.vram[0x01ff] = 0x66;
+        ppu.vram[0x0200] = 0x77;
+
+        ppu.write_to_addr(0x21);
+        ppu.write_to_addr(0xff);
+
+        ppu.read_data(); //load_into_buffer
+        assert_eq!(ppu.read_data(), 0x66);
+        assert_eq!(ppu.read_data(), 0x77);
+    }
+
+    #[test]
+    fn test_ppu_vram_reads_step_32() {
+        let mut ppu = new_empty_rom();
+        ppu.write_to_ctrl(0b100);
+        ppu.vram[0x01ff] = 0x66;
+        ppu.vram[0x01ff + 32] = 0x77;
+        ppu.vram[0x01ff + 64] = 0x88;
+
+        ppu.write_to_addr(0x21);
+        ppu.write_to_addr(0xff);
+
+        ppu.read_data(); //load_into_buffer
+        assert_eq!(ppu.read_data(), 0x66);
+        assert_eq!(ppu.read_data(), 0x77);
+        assert_eq!(ppu.read_data(), 0x88);
+    }
+
+    // Horizontal: https://wiki.nesdev.com/w/index.php/Mirroring
+    //   [0x2000 A ] [0x2400 a ]
+    //   [0x2800 B ] [0x2C00 b ]
+    #[test]
+    fn test_vram_horizontal_mirror() {
+        let mut ppu = new_empty_rom();
+        ppu.write_to_addr(0x24);
+        ppu.write_to_addr(0x05);
+
+        ppu.write_to_data(0x66); //write to a
+
+        ppu.write_to_addr(0x28);
+        ppu.write_to_addr(0x05);
+
+        ppu.write_to_data(0x77); //write to B
+
+        ppu.write_to_addr(0x20);
+        ppu.write_to_addr(0x05);
+
+        ppu.read_data(); //load into buffer
+        assert_eq!(ppu.read_data(), 0x66); //read from A
+
+        ppu.write_to_addr(0x2C);
+        ppu.write_to_addr(0x05);
+
+        ppu.read_data(); //load into buffer
+        assert_eq!(ppu.read_data(), 0x77); //read from b
+    }
+
+    // Vertical: https://wiki.nesdev.com/w/index.php/Mirroring
+    //   [0x2000 A ] [0x2400 B ]
+    //   [0x2800 a ] [0x2C00 b ]
+    #[test]
+    fn test_vram_four_screen() {
+        let mut ppu = new_ppu(Mirroring::FOUR_SCREEN);
+        for (i, addr) in [0x20, 0x24, 0x28, 0x2C].into_iter().enumerate() {
+            ppu.write_to_addr(addr);
+            ppu.write_to_addr(0x05);
+            ppu.write_to_data(i as u8 + 1);
+        }
+
+        assert_eq!(ppu.read_vram(0x2C05), 4);
+        assert_eq!(ppu.vram[0x0C05], 4);
+        assert_eq!(ppu.read_vram(0x2005), 1);
+    }
+
+    #[test]
+    fn test_vram_vertical_mirror() {
+        let mut ppu = new_ppu(Mirroring::VERTICAL);
+
+        ppu.write_to_addr(0x20);
+        ppu.write_to_addr(0x05);
+
+        ppu.write_to_data(0x66); //write to A
+
+        ppu.write_to_addr(0x2C);
+        ppu.write_to_addr(0x05);
+
+        ppu.write_to_data(0x77); //write to b
+
+        ppu.write_to_addr(0x28);
+        ppu.write_to_addr(0x05);
+
+        ppu.read_data(); //load into buffer
+        assert_eq!(ppu.read_data(), 0x66); //read from a
+
+        ppu.write_to_addr(0x24);
+        ppu.write_to_addr(0x05);
+
+        ppu.read_data(); //load into buffer
+        assert_eq!(ppu.read_data(), 0x77); //read from B
+    }
+
+    #[test]
+    fn test_read_status_resets_latch() {
+        let mut ppu = new_empty_rom();
+        ppu.vram[0x0305] = 0x66;
+
+        ppu.write_to_addr(0x21);
+        ppu.write_to_addr(0x23);
+        ppu.write_to_addr(0x05);
+
+        ppu.read_data(); //load_into_buffer
+        assert_ne!(ppu.read_data(), 0x66);
+
+        ppu.read_status();
+
+        ppu.write_to_addr(0x23);
+        ppu.write_to_addr(0x05);
+
+        ppu.read_data(); //load_into_buffer
+        assert_eq!(ppu.read_data(), 0x66);
+    }
+
+    #[test]
+    fn test_scroll_writes() {
+        let mut ppu = new_empty_rom();
+        ppu.write_to_ctrl(0b10);
+        ppu.write_to_scroll(0x7D);
+        ppu.write_to_scroll(0x5E);
+
+        assert_eq!(ppu.t.get(), 0x696F);
+        assert_eq!(ppu.fine_x, 5);
+        assert!(!ppu.w);
+    }
+
+    // the mid-frame scroll change of the games: 0x2006, 0x2005, 0x2005, 0x2006
+    #[test]
+    fn test_scroll_and_addr_share_the_latch() {
+        let mut ppu = new_empty_rom();
+        ppu.write_to_scroll(0x10);
+        ppu.read_status();
+
+        ppu.write_to_addr(0x04);
+        ppu.write_to_scroll(0x3E);
+        ppu.write_to_scroll(0x7D);
+        ppu.write_to_addr(0xEF);
+
+        assert_eq!(ppu.v.get(), 0x64EF);
+        assert_eq!(ppu.fine_x, 5);
+    }
+
+    #[test]
+    fn test_rendering_moves_v() {
+        let mut ppu = new_empty_rom();
+        ppu.write_to_mask(0b1000);
+        ppu.write_to_scroll(0x0D);
+        ppu.write_to_scroll(0x00);
+        ppu.v = ppu.t;
+
+        // 32 tiles later it's back at coarse X 1 of the next name table, one line down
+        ppu.tick(128);
+        ppu.tick(128);
+        assert_eq!(ppu.v.tile_addr(), 0x2401);
+        assert_eq!(ppu.v.fine_y(), 1);
+
+        ppu.tick(1);
+        assert_eq!(ppu.v.tile_addr(), 0x2001);
+
+        // the first two tiles of the next line are fetched at dots 321-336
+        ppu.tick(83);
+        assert_eq!(ppu.v.tile_addr(), 0x2003);
+
+        // the pre-render line starts the next frame from `t`
+        ppu.write_to_scroll(0x00);
+        ppu.write_to_scroll(0x10);
+        while ppu.scanline() != 261 || ppu.dot() != 320 {
+            ppu.tick(1);
+        }
+        assert_eq!(ppu.v.tile_addr(), 0x2040);
+    }
+
+    fn pixel(frame: &Frame, x: usize, y: usize) -> (u8, u8, u8) {
+        let base = (y * Frame::WIDTH + x) * 3;
+        (frame.data[base], frame.data[base + 1], frame.data[base + 2])
+    }
+
+    // Solid tiles: color 1 for the `lo` ones and color 2 for the `hi` ones
+    fn new_ppu_with_tiles(lo: &[usize], hi: &[usize]) -> NesPPU {
+        let mut chr_rom = vec![0; 0x2000];
+        for tile in lo {
+            chr_rom[tile * 16..tile * 16 + 8].copy_from_slice(&[0xFF; 8]);
+        }
+        for tile in hi {
+            chr_rom[tile * 16 + 8..tile * 16 + 16].copy_from_slice(&[0xFF; 8]);
+        }
+        let rom = Rom {
+            prg_rom: vec![0; 0x4000],
+            chr_rom,
+            mapper: 0,
+            screen_mirroring: Mirroring::HORIZONTAL,
+            battery: false,
+        };
+        NesPPU::new(new_mapper(rom).unwrap())
+    }
+
+    // The first frame starts without the fetches of the pre-render line, so this draws the second one
+    fn draw_frame(ppu: &mut NesPPU) {
+        while !ppu.tick(1) {}
+        while ppu.scanline() != 240 {
+            ppu.tick(1);
+        }
+    }
+
+    #[test]
+    fn test_draws_the_frame_dot_by_dot() {
+        let mut ppu = new_ppu_with_tiles(&[1], &[]);
+        ppu.vram[2] = 1;
+        ppu.palette_table[0] = 0x0F;
+        ppu.palette_table[1] = 0x30;
+        ppu.palette_table[0x11] = 0x16;
+        // sprite 0 on lines 1-8 at x 10-17, over the tile at x 13-20
+        ppu.oam_data[..4].copy_from_slice(&[0, 1, 0, 10]);
+
+        ppu.write_to_mask(0b1_1110);
+        ppu.write_to_scroll(0x03);
+        ppu.write_to_scroll(0x00);
+        draw_frame(&mut ppu);
+
+        let (black, white, red) = (SYSTEM_PALLETE[0x0F], SYSTEM_PALLETE[0x30], SYSTEM_PALLETE[0x16]);
+        assert_eq!(pixel(&ppu.frame, 12, 0), black);
+        assert_eq!(pixel(&ppu.frame, 13, 0), white);
+        assert_eq!(pixel(&ppu.frame, 20, 0), white);
+        assert_eq!(pixel(&ppu.frame, 21, 0), black);
+        assert_eq!(pixel(&ppu.frame, 10, 1), red);
+        assert_eq!(pixel(&ppu.frame, 17, 8), red);
+        assert_eq!(pixel(&ppu.frame, 18, 7), white);
+        assert_eq!(pixel(&ppu.frame, 18, 8), black);
+        assert_eq!(pixel(&ppu.frame, 10, 9), black);
+        assert_eq!(ppu.status.get_bits() & 0x40, 0x40);
+    }
+
+    #[test]
+    fn test_8x16_sprites() {
+        // tiles 2 and 3 of the second pattern table for the sprite, tile 1 of the first one for the background
+        let mut ppu = new_ppu_with_tiles(&[1, 0x102], &[0x103]);
+        ppu.palette_table[0x11] = 0x16;
+        ppu.palette_table[0x12] = 0x2A;
+        // the background only touches the sprite on its last line
+        ppu.vram[64 + 1] = 1;
+        // flipped vertically, on lines 1-16
+        ppu.oam_data[..4].copy_from_slice(&[0, 0x03, 0x80, 8]);
+
+        ppu.write_to_ctrl(0b10_0000);
+        ppu.write_to_mask(0b1_1110);
+        draw_frame(&mut ppu);
+
+        assert_eq!(pixel(&ppu.frame, 8, 1), SYSTEM_PALLETE[0x2A]);
+        assert_eq!(pixel(&ppu.frame, 8, 8), SYSTEM_PALLETE[0x2A]);
+        assert_eq!(pixel(&ppu.frame, 8, 9), SYSTEM_PALLETE[0x16]);
+        assert_eq!(pixel(&ppu.frame, 8, 16), SYSTEM_PALLETE[0x16]);
+        assert_eq!(ppu.status.get_bits() & 0x40, 0x40);
+
+        // as 8x8 it ends before the background
+        ppu.write_to_ctrl(0);
+        ppu.read_status();
+        draw_frame(&mut ppu);
+        assert_eq!(ppu.status.get_bits() & 0x40, 0);
+    }
+
+    #[test]
+    fn test_sprite_priority() {
+        let mut ppu = new_ppu_with_tiles(&[1], &[]);
+        ppu.palette_table[0] = 0x0F;
+        ppu.palette_table[1] = 0x30;
+        ppu.palette_table[0x11] = 0x16;
+        ppu.palette_table[0x15] = 0x2A;
+        // background on x 8-15, y 8-15
+        ppu.vram[32 + 1] = 1;
+        // sprite 0 behind the background on x 12-19, sprite 1 in front of it on x 8-15
+        ppu.oam_data[..8].copy_from_slice(&[7, 1, 0b10_0000, 12, 7, 1, 0b01, 8]);
+
+        ppu.write_to_mask(0b1_1000);
+        draw_frame(&mut ppu);
+
+        assert_eq!(pixel(&ppu.frame, 11, 8), SYSTEM_PALLETE[0x2A]);
+        // sprite 0 hides sprite 1 behind the background
+        assert_eq!(pixel(&ppu.frame, 12, 8), SYSTEM_PALLETE[0x30]);
+        assert_eq!(pixel(&ppu.frame, 16, 8), SYSTEM_PALLETE[0x16]);
+        assert_eq!(pixel(&ppu.frame, 20, 8), SYSTEM_PALLETE[0x0F]);
+    }
+
+    #[test]
+    fn test_palette_mirrors() {
+        let mut ppu = new_empty_rom();
+        ppu.write_to_addr(0x3F);
+        ppu.write_to_addr(0x10);
+        ppu.write_to_data(0x21);
+        ppu.write_to_addr(0x3F);
+        ppu.write_to_addr(0x25);
+        ppu.write_to_data(0x22);
+
+        assert_eq!(ppu.palette_table[0], 0x21);
+        assert_eq!(ppu.palette_table[5], 0x22);
+        ppu.write_to_addr(0x3F);
+        ppu.write_to_addr(0xF0);
+        assert_eq!(ppu.read_data(), 0x21);
+    }
+
+    #[test]
+    fn test_sprite_evaluation() {
+        let mut ppu = new_empty_rom();
+        ppu.scanline = 20;
+        // 9 sprites on line 21, the rest below the screen
+        ppu.oam_data = [0xF0; 256];
+        for entry in ppu.oam_data.chunks_mut(4).take(9) {
+            entry[0] = 16;
+        }
+
+        ppu.fetch_sprites();
+        assert_eq!(ppu.sprites.len(), 8);
+        assert_eq!(ppu.status.get_bits() & 0x20, 0x20);
+
+        ppu.sprite_limit = false;
+        ppu.fetch_sprites();
+        assert_eq!(ppu.sprites.len(), 9);
+        assert_eq!(ppu.sprites[8].index, 8);
+    }
+
+    #[test]
+    fn test_sprite_overflow_diagonal_scan() {
+        let mut ppu = new_empty_rom();
+        ppu.scanline = 20;
+        ppu.oam_data = [0xF0; 256];
+        for entry in ppu.oam_data.chunks_mut(4).take(8) {
+            entry[0] = 16;
+        }
+        // after the miss on sprite 8 the 10th one is on the line but its tile is taken as its Y
+        ppu.oam_data[9 * 4] = 16;
+        ppu.fetch_sprites();
+        assert_eq!(ppu.status.get_bits() & 0x20, 0);
+
+        // and the attributes of the 11th as its Y
+        ppu.oam_data[10 * 4 + 2] = 18;
+        ppu.fetch_sprites();
+        assert_eq!(ppu.status.get_bits() & 0x20, 0x20);
+
+        // until the pre-render line
+        ppu.scanline = 261;
+        ppu.cycles = 0;
+        ppu.tick(1);
+        assert_eq!(ppu.status.get_bits() & 0x20, 0);
+    }
+
+    #[test]
+    fn test_ppu_vram_mirroring() {
+        let mut ppu = new_empty_rom();
+        ppu.write_to_ctrl(0);
+        ppu.vram[0x0305] = 0x66;
+
+        ppu.write_to_addr(0x63); //0x6305 -> 0x2305
+        ppu.write_to_addr(0x05);
+
+        ppu.read_data(); //load into_buffer
+        assert_eq!(ppu.read_data(), 0x66);
+        // assert_eq!(ppu.addr.read(), 0x0306)
+    }
+
+    #[test]
+    fn test_read_status_resets_vblank() {
+        let mut ppu = new_empty_rom();
+        ppu.status.set_vblank_status(true);
+
+        let status = ppu.read_status();
+
+        assert_eq!(status >> 7, 1);
+        assert_eq!(ppu.status.get_bits() >> 7, 0);
+    }
+
+    #[test]
+    fn test_oam_read_write() {
+        let mut ppu = new_empty_rom();
+        ppu.write_to_oam_addr(0x10);
+        ppu.write_to_oam_data(0x66);
+        ppu.write_to_oam_data(0x77);
+
+        ppu.write_to_oam_addr(0x10);
+        assert_eq!(ppu.read_oam_data(), 0x66);
+
+        ppu.write_to_oam_addr(0x11);
+        assert_eq!(ppu.read_oam_data(), 0x77);
+    }
+
+    #[test]
+    fn test_oam_dma() {
+        let mut ppu = new_empty_rom();
+
+        let mut data = [0x66; 256];
+        data[0] = 0x77;
+        data[255] = 0x88;
+
+        ppu.write_to_oam_addr(0x10);
+        ppu.write_oam_dma(&data);
+
+        ppu.write_to_oam_addr(0xF); //wrap around
+        assert_eq!(ppu.read_oam_data(), 0x88);
+
+        ppu.write_to_oam_addr(0x10);
+        ppu.write_to_oam_addr(0x77);
+        ppu.write_to_oam_addr(0x11);
+        ppu.write_to_oam_addr(0x66);
+    }
+}
@@ -0,0 +1,79 @@
+// Attributes, the third byte of an OAM entry
+//
+// 76543210
+// ||||||||
+// ||||||++- Palette (4 to 7) of sprite
+// |||+++--- Unimplemented (read 0)
+// ||+------ Priority (0: in front of background; 1: behind background)
+// |+------- Flip sprite horizontally
+// +-------- Flip sprite vertically
+
+const PALETTE: u8 = 0b0000_0011;
+const BEHIND_BACKGROUND: u8 = 0b0010_0000;
+const FLIP_HORIZONTAL: u8 = 0b0100_0000;
+const FLIP_VERTICAL: u8 = 0b1000_0000;
+
+// One of the sprites of the line being drawn, fetched during the line before
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Sprite {
+    // in OAM, sprite 0 is the one that sets the hit flag
+    pub index: u8,
+    pub x: u8,
+    pub attributes: u8,
+    pub pattern_lo: u8,
+    pub pattern_hi: u8,
+}
+
+impl Sprite {
+    pub fn palette(&self) -> u8 {
+        self.attributes & PALETTE
+    }
+
+    pub fn behind_background(&self) -> bool {
+        self.attributes & BEHIND_BACKGROUND != 0
+    }
+
+    pub fn flip_vertical(&self) -> bool {
+        self.attributes & FLIP_VERTICAL != 0
+    }
+
+    // Pattern value of the pixel at `x` on the screen, 0 outside of the sprite
+    pub fn pixel(&self, x: usize) -> u8 {
+        let column = x.wrapping_sub(self.x as usize);
+        if column >= 8 {
+            return 0;
+        }
+
+        let bit = if self.attributes & FLIP_HORIZONTAL != 0 {
+            column
+        } else {
+            7 - column
+        };
+        ((self.pattern_hi >> bit) & 1) << 1 | ((self.pattern_lo >> bit) & 1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pixel() {
+        let mut sprite = Sprite {
+            index: 0,
+            x: 250,
+            attributes: 0,
+            pattern_lo: 0b1000_0000,
+            pattern_hi: 0b1000_0001,
+        };
+        assert_eq!(sprite.pixel(249), 0);
+        assert_eq!(sprite.pixel(250), 3);
+        assert_eq!(sprite.pixel(255), 0);
+        assert_eq!(sprite.pixel(257), 2);
+        assert_eq!(sprite.pixel(258), 0);
+
+        sprite.attributes = FLIP_HORIZONTAL;
+        assert_eq!(sprite.pixel(250), 2);
+        assert_eq!(sprite.pixel(257), 3);
+    }
+}
@@ -3,8 +3,8 @@ pub struct Frame {
 }
 
 impl Frame {
-    const WIDTH: usize = 256;
-    const HIGHT: usize = 240;
+    pub const WIDTH: usize = 256;
+    pub const HIGHT: usize = 240;
 
     pub fn new() -> Self {
         Frame {
@@ -21,3 +21,9 @@ impl Frame {
         }
     }
 }
+
+impl Default for Frame {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,146 @@
+use std::str::FromStr;
+
+use super::frame::Frame;
+use crate::rom::crc32;
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+// deflate stored blocks hold at most 65535 bytes
+const STORED_BLOCK_SIZE: usize = 0xFFFF;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ImageFormat {
+    Png,
+    Ppm,
+}
+
+impl ImageFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Ppm => "ppm",
+        }
+    }
+
+    pub fn encode(&self, frame: &Frame) -> Vec<u8> {
+        match self {
+            ImageFormat::Png => encode_png(frame),
+            ImageFormat::Ppm => encode_ppm(frame),
+        }
+    }
+}
+
+impl FromStr for ImageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "png" => Ok(ImageFormat::Png),
+            "ppm" => Ok(ImageFormat::Ppm),
+            _ => Err(format!("Unknown image format {s}, expected png or ppm")),
+        }
+    }
+}
+
+// Binary PPM: a text header followed by the raw RGB pixels
+pub fn encode_ppm(frame: &Frame) -> Vec<u8> {
+    let mut out = format!("P6\n{} {}\n255\n", Frame::WIDTH, Frame::HIGHT).into_bytes();
+    out.extend_from_slice(&frame.data);
+    out
+}
+
+// 8 bit RGB PNG. The pixels are not compressed, the zlib stream is made of stored deflate blocks
+// which keeps the encoder small and the output readable by any decoder
+pub fn encode_png(frame: &Frame) -> Vec<u8> {
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&(Frame::WIDTH as u32).to_be_bytes());
+    ihdr.extend_from_slice(&(Frame::HIGHT as u32).to_be_bytes());
+    ihdr.extend_from_slice(&[8, 2, 0, 0, 0]); // bit depth, truecolor, deflate, no filter, no interlace
+
+    // every scanline starts with its filter type, 0 is none
+    let mut scanlines = Vec::with_capacity(Frame::HIGHT * (Frame::WIDTH * 3 + 1));
+    for line in frame.data.chunks(Frame::WIDTH * 3) {
+        scanlines.push(0);
+        scanlines.extend_from_slice(line);
+    }
+
+    let mut out = PNG_SIGNATURE.to_vec();
+    write_chunk(&mut out, b"IHDR", &ihdr);
+    write_chunk(&mut out, b"IDAT", &zlib_stored(&scanlines));
+    write_chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let blocks = data.len().div_ceil(STORED_BLOCK_SIZE);
+
+    for (i, block) in data.chunks(STORED_BLOCK_SIZE).enumerate() {
+        out.push((i + 1 == blocks) as u8); // BFINAL, BTYPE 00
+        out.extend_from_slice(&(block.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(!(block.len() as u16)).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MOD_ADLER: u32 = 65521;
+
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % MOD_ADLER;
+        b = (b + a) % MOD_ADLER;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_adler32() {
+        assert_eq!(adler32(b"Wikipedia"), 0x11E60398);
+        assert_eq!(adler32(&[]), 1);
+    }
+
+    #[test]
+    fn test_ppm_header_and_size() {
+        let ppm = encode_ppm(&Frame::new());
+        let header = b"P6\n256 240\n255\n";
+
+        assert_eq!(&ppm[..header.len()], header);
+        assert_eq!(ppm.len(), header.len() + 256 * 240 * 3);
+    }
+
+    #[test]
+    fn test_png_chunks() {
+        let mut frame = Frame::new();
+        frame.set_pixel(0, 0, (1, 2, 3));
+        let png = encode_png(&frame);
+
+        assert_eq!(&png[..8], &PNG_SIGNATURE);
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[16..24], &[0, 0, 1, 0, 0, 0, 0, 240]);
+        assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+
+        // IDAT: zlib header, first stored block header, filter byte and the first pixel
+        let idat = 8 + 25;
+        assert_eq!(&png[idat + 4..idat + 8], b"IDAT");
+        let zlib = &png[idat + 8..];
+        assert_eq!(&zlib[..2], &[0x78, 0x01]);
+        assert_eq!(&zlib[2..7], &[0, 0xFF, 0xFF, 0, 0]);
+        assert_eq!(&zlib[7..11], &[0, 1, 2, 3]);
+    }
+}
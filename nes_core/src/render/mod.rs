@@ -0,0 +1,7 @@
+mod palette;
+mod frame;
+mod image;
+
+pub use frame::Frame;
+pub use image::ImageFormat;
+pub use palette::SYSTEM_PALLETE;
\ No newline at end of file
@@ -0,0 +1,5 @@
+mod rom;
+mod crc32;
+
+pub use rom::*;
+pub use crc32::crc32;
@@ -1,13 +1,18 @@
+use super::crc32;
+use crate::mapper::SUPPORTED_MAPPERS;
+
 const NES_TAG: [u8; 4] = [0x4E, 0x45, 0x53, 0x1A];
 const PRG_ROM_PAGE_SIZE: usize = 16384;
 const CHR_ROM_PAGE_SIZE: usize = 8192;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 #[allow(non_camel_case_types)]
 pub enum Mirroring {
     VERTICAL,
     HORIZONTAL,
     FOUR_SCREEN,
+    SINGLE_SCREEN_LOWER,
+    SINGLE_SCREEN_UPPER,
 }
 
 pub struct Rom {
@@ -15,10 +20,11 @@ pub struct Rom {
     pub chr_rom: Vec<u8>,
     pub mapper: u8,
     pub screen_mirroring: Mirroring,
+    pub battery: bool,
 }
 
 impl Rom {
-    pub fn new(raw: &Vec<u8>) -> Result<Rom, String> {
+    pub fn new(raw: &[u8]) -> Result<Rom, String> {
         if &raw[0..4] != NES_TAG {
             return Err("File is not in iNES file format".to_string());
         }
@@ -30,6 +36,10 @@ impl Rom {
             return Err("NES2.0 format is not supported".to_string());
         }
 
+        if !SUPPORTED_MAPPERS.contains(&mapper) {
+            return Err(format!("Mapper {mapper} is not supported"));
+        }
+
         let four_screen = raw[6] & 0b1000 != 0;
         let vertical_mirroring = raw[6] & 0b1 != 0;
         let screen_mirroring = match (four_screen, vertical_mirroring) {
@@ -38,6 +48,8 @@ impl Rom {
             (false, false) => Mirroring::HORIZONTAL,
         };
 
+        let battery = raw[6] & 0b10 != 0;
+
         let prg_rom_size = raw[4] as usize * PRG_ROM_PAGE_SIZE;
         let chr_rom_size = raw[5] as usize * CHR_ROM_PAGE_SIZE;
 
@@ -51,8 +63,14 @@ impl Rom {
             chr_rom: raw[chr_rom_start..(chr_rom_start + chr_rom_size)].to_vec(),
             mapper,
             screen_mirroring,
+            battery,
         })
     }
+
+    // identifies the game regardless of the header and trainer
+    pub fn hash(&self) -> u32 {
+        crc32(&[self.prg_rom.as_slice(), self.chr_rom.as_slice()].concat())
+    }
 }
 
 #[cfg(test)]
@@ -167,4 +185,38 @@ pub mod test {
             Result::Err(str) => assert_eq!(str, "NES2.0 format is not supported"),
         }
     }
+
+    #[test]
+    fn test_unknown_mapper_is_not_supported() {
+        let test_rom = create_rom(TestRom {
+            header: vec![
+                0x4E, 0x45, 0x53, 0x1A, 0x01, 0x01, 0x51, 0x10, 00, 00, 00, 00, 00, 00, 00, 00,
+            ],
+            trainer: None,
+            pgp_rom: vec![1; 1 * PRG_ROM_PAGE_SIZE],
+            chr_rom: vec![2; 1 * CHR_ROM_PAGE_SIZE],
+        });
+        let rom = Rom::new(&test_rom);
+        match rom {
+            Result::Ok(_) => assert!(false, "should not load rom"),
+            Result::Err(str) => assert_eq!(str, "Mapper 21 is not supported"),
+        }
+    }
+
+    #[test]
+    fn test_battery_backed_ram() {
+        let raw = create_rom(TestRom {
+            header: vec![
+                0x4E, 0x45, 0x53, 0x1A, 0x02, 0x01, 0x33, 00, 00, 00, 00, 00, 00, 00, 00, 00,
+            ],
+            trainer: None,
+            pgp_rom: vec![1; 2 * PRG_ROM_PAGE_SIZE],
+            chr_rom: vec![2; CHR_ROM_PAGE_SIZE],
+        });
+
+        let rom: Rom = Rom::new(&raw).unwrap();
+
+        assert!(rom.battery);
+        assert!(!test_rom().battery);
+    }
 }
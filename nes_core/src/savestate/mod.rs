@@ -0,0 +1,3 @@
+mod savestate;
+
+pub use savestate::*;
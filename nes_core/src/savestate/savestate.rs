@@ -0,0 +1,240 @@
+use crate::cpu::NesCPU;
+
+const STATE_MAGIC: [u8; 4] = *b"NESS";
+const STATE_VERSION: u16 = 5;
+
+// Implemented by every piece of the console that holds state. Fields are written in declaration
+// order with fixed sizes, so `load_state` must read them back in the same order `save_state` wrote them
+pub trait Snapshot {
+    fn save_state(&self, w: &mut StateWriter);
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), String>;
+}
+
+pub struct StateWriter {
+    data: Vec<u8>,
+}
+
+impl StateWriter {
+    pub fn new() -> Self {
+        StateWriter { data: Vec::new() }
+    }
+
+    pub fn write_u8(&mut self, value: u8) {
+        self.data.push(value);
+    }
+
+    pub fn write_bool(&mut self, value: bool) {
+        self.data.push(value as u8);
+    }
+
+    pub fn write_u16(&mut self, value: u16) {
+        self.data.extend(value.to_le_bytes());
+    }
+
+    pub fn write_u32(&mut self, value: u32) {
+        self.data.extend(value.to_le_bytes());
+    }
+
+    pub fn write_u64(&mut self, value: u64) {
+        self.data.extend(value.to_le_bytes());
+    }
+
+    pub fn write_f32(&mut self, value: f32) {
+        self.data.extend(value.to_le_bytes());
+    }
+
+    pub fn write_f64(&mut self, value: f64) {
+        self.data.extend(value.to_le_bytes());
+    }
+
+    pub fn write_bytes(&mut self, value: &[u8]) {
+        self.write_u32(value.len() as u32);
+        self.data.extend(value);
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+impl Default for StateWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct StateReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> StateReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        StateReader { data, pos: 0 }
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + N)
+            .ok_or_else(|| "Save state is truncated".to_string())?;
+        self.pos += N;
+        Ok(bytes.try_into().unwrap())
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, String> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool, String> {
+        Ok(self.read_u8()? != 0)
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(self.take()?))
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take()?))
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.take()?))
+    }
+
+    pub fn read_f32(&mut self) -> Result<f32, String> {
+        Ok(f32::from_le_bytes(self.take()?))
+    }
+
+    pub fn read_f64(&mut self) -> Result<f64, String> {
+        Ok(f64::from_le_bytes(self.take()?))
+    }
+
+    pub fn read_bytes(&mut self) -> Result<&'a [u8], String> {
+        let len = self.read_u32()? as usize;
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or_else(|| "Save state is truncated".to_string())?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    // reads a block of memory that must have exactly the size of `dest`
+    pub fn read_into(&mut self, dest: &mut [u8]) -> Result<(), String> {
+        let bytes = self.read_bytes()?;
+        if bytes.len() != dest.len() {
+            return Err(format!("Expected {} bytes of memory, found {}", dest.len(), bytes.len()));
+        }
+        dest.copy_from_slice(bytes);
+        Ok(())
+    }
+}
+
+// Header
+// ------
+// "NESS"       magic
+// u16          format version
+// u32          CRC32 of the ROM the state was taken from
+pub fn save_state(cpu: &NesCPU) -> Vec<u8> {
+    let mut w = StateWriter::new();
+    STATE_MAGIC.iter().for_each(|b| w.write_u8(*b));
+    w.write_u16(STATE_VERSION);
+    w.write_u32(cpu.bus.rom_hash());
+
+    cpu.save_state(&mut w);
+    w.into_bytes()
+}
+
+pub fn load_state(cpu: &mut NesCPU, data: &[u8]) -> Result<(), String> {
+    let mut r = StateReader::new(data);
+
+    let magic = r.take::<4>()?;
+    if magic != STATE_MAGIC {
+        return Err("File is not a save state".to_string());
+    }
+
+    let version = r.read_u16()?;
+    if version != STATE_VERSION {
+        return Err(format!("Save state version {version} is not supported"));
+    }
+
+    let rom_hash = r.read_u32()?;
+    if rom_hash != cpu.bus.rom_hash() {
+        return Err(format!(
+            "Save state belongs to another ROM (CRC32 {rom_hash:08X}, loaded {:08X})",
+            cpu.bus.rom_hash()
+        ));
+    }
+
+    // a corrupted state must not leave the running game half overwritten
+    let backup = save_state(cpu);
+    if let Err(err) = cpu.load_state(&mut r) {
+        load_state(cpu, &backup).expect("Could not restore the previous state");
+        return Err(err);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        bus::Bus,
+        controller::Joypad,
+        cpu::{Mem, CPU},
+        ppu::NesPPU,
+        rom::test,
+    };
+
+    fn test_cpu<'a>() -> NesCPU<'a> {
+        CPU::new(Bus::new(test::test_rom(), |_ppu: &NesPPU, _joypad: &mut Joypad| {}).unwrap())
+    }
+
+    #[test]
+    fn test_save_and_load_state() {
+        let mut cpu = test_cpu();
+        cpu.register_a = 0x42;
+        cpu.program_counter = 0x8123;
+        cpu.mem_write(0x0010, 0x55);
+        cpu.mem_write(0x6000, 0x66);
+        cpu.mem_write(0x2006, 0x23);
+        cpu.mem_write(0x2006, 0x05);
+        cpu.mem_write(0x2007, 0x77);
+        cpu.bus.frames = 7;
+
+        let state = save_state(&cpu);
+
+        let mut other = test_cpu();
+        load_state(&mut other, &state).unwrap();
+
+        assert_eq!(other.register_a, 0x42);
+        assert_eq!(other.program_counter, 0x8123);
+        assert_eq!(other.mem_read(0x0010), 0x55);
+        assert_eq!(other.mem_read(0x6000), 0x66);
+        assert_eq!(other.bus.frames, 7);
+        assert_eq!(save_state(&other), state);
+    }
+
+    #[test]
+    fn test_load_state_rejects_other_rom() {
+        let mut cpu = test_cpu();
+        let mut state = save_state(&cpu);
+        state[6] ^= 0xFF;
+
+        assert!(load_state(&mut cpu, &state).unwrap_err().starts_with("Save state belongs to another ROM"));
+    }
+
+    #[test]
+    fn test_truncated_state_keeps_running_game() {
+        let mut cpu = test_cpu();
+        let state = save_state(&cpu);
+
+        cpu.register_x = 0x10;
+        cpu.mem_write(0x0200, 0x99);
+
+        assert_eq!(load_state(&mut cpu, &state[..state.len() - 8]).unwrap_err(), "Save state is truncated");
+        assert_eq!(cpu.register_x, 0x10);
+        assert_eq!(cpu.mem_read(0x0200), 0x99);
+    }
+}
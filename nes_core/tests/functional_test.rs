@@ -0,0 +1,32 @@
+use nes_core::cpu::{FlatMemory, CPU};
+
+// Klaus Dormann's 6502 functional test, https://github.com/Klaus2m5/6502_65C02_functional_tests
+// The binary isn't in the repository, run it with
+// FUNCTIONAL_TEST=path/to/6502_functional_test.bin cargo test -p nes_core -- --ignored
+const START: u16 = 0x0400;
+// where the default build of the test traps once everything passed
+const SUCCESS: u16 = 0x3469;
+
+#[test]
+#[ignore]
+fn test_6502_functional_test() {
+    let path = std::env::var("FUNCTIONAL_TEST").expect("FUNCTIONAL_TEST should point to the binary");
+    let image = std::fs::read(path).unwrap();
+
+    let mut memory = FlatMemory::new();
+    memory.load(0x0000, &image);
+    let mut cpu = CPU::new(memory);
+    cpu.decimal_mode = true;
+    cpu.program_counter = START;
+
+    // every failure is a branch or jump to itself
+    loop {
+        let pc = cpu.program_counter;
+        cpu.step();
+        if cpu.program_counter == pc {
+            break;
+        }
+    }
+
+    assert_eq!(cpu.program_counter, SUCCESS, "trapped at ${:04X}", cpu.program_counter);
+}
@@ -0,0 +1,43 @@
+use nes_core::{Frame, Joypad, Nes};
+
+// NROM image with 16KB of PRG ROM running `JMP $8000` and 8KB of CHR ROM
+fn looping_rom() -> Vec<u8> {
+    let mut raw = vec![0x4E, 0x45, 0x53, 0x1A, 0x01, 0x01, 0x00, 0x00, 0, 0, 0, 0, 0, 0, 0, 0];
+    let mut prg_rom = vec![0; 0x4000];
+    prg_rom[..3].copy_from_slice(&[0x4C, 0x00, 0x80]);
+    prg_rom[0x3FFC..0x3FFE].copy_from_slice(&[0x00, 0x80]);
+    raw.extend(prg_rom);
+    raw.extend(vec![0; 0x2000]);
+    raw
+}
+
+#[test]
+fn test_run_frames_from_rom_bytes() {
+    let mut nes = Nes::new(&looping_rom()).unwrap();
+    nes.set_buttons(Joypad::START);
+
+    for _ in 0..3 {
+        assert!(nes.step_frame());
+    }
+
+    assert_eq!(nes.frame_count(), 3);
+    assert_eq!(nes.frame().data.len(), Frame::WIDTH * Frame::HIGHT * 3);
+    assert!(!nes.take_audio_samples().is_empty());
+    assert_eq!(nes.program_counter() & 0xFFF0, 0x8000);
+}
+
+#[test]
+fn test_save_state_round_trip() {
+    let mut nes = Nes::new(&looping_rom()).unwrap();
+    nes.step_frame();
+    let state = nes.save_state();
+
+    nes.step_frame();
+    nes.load_state(&state).unwrap();
+    assert_eq!(nes.save_state(), state);
+}
+
+#[test]
+fn test_invalid_rom() {
+    assert!(Nes::new(&[0; 16]).is_err());
+}
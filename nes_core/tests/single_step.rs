@@ -0,0 +1,161 @@
+use std::{fs, path::PathBuf};
+
+use nes_core::cpu::{CpuBus, FlatMemory, Mem, CPU, OPS_CODES};
+use serde::Deserialize;
+
+// Runs the per-opcode JSON vectors of https://github.com/SingleStepTests/65x02 (the nes6502 set) found in
+// tests/single_step, one `<opcode>.json` file per opcode. `tests/single_step/vendor.py` copies the first
+// cases of each one there, the full suite can be pointed to with SINGLE_STEP_TESTS=path/to/nes6502/v1.
+// Every opcode but the jams needs its file
+
+#[derive(Deserialize)]
+struct Test {
+    name: String,
+    initial: State,
+    #[serde(rename = "final")]
+    expected: State,
+    cycles: Vec<(u16, u8, String)>,
+}
+
+#[derive(Deserialize)]
+struct State {
+    pc: u16,
+    s: u8,
+    a: u8,
+    x: u8,
+    y: u8,
+    p: u8,
+    ram: Vec<(u16, u8)>,
+}
+
+// FlatMemory that keeps every access of the CPU, to compare with the cycles of the vectors
+struct RecordingBus {
+    memory: FlatMemory,
+    accesses: Vec<(u16, u8, String)>,
+}
+
+impl Mem for RecordingBus {
+    fn mem_read(&mut self, addr: u16) -> u8 {
+        let data = self.memory.mem_read(addr);
+        self.accesses.push((addr, data, "read".to_string()));
+        data
+    }
+
+    fn mem_write(&mut self, addr: u16, data: u8) {
+        self.memory.mem_write(addr, data);
+        self.accesses.push((addr, data, "write".to_string()));
+    }
+}
+
+impl CpuBus for RecordingBus {
+    fn tick(&mut self, cycles: u8) {
+        self.memory.tick(cycles);
+    }
+
+    fn poll_nmi_status(&mut self) -> bool {
+        false
+    }
+
+    fn poll_irq_status(&self) -> bool {
+        false
+    }
+
+    fn peek(&self, addr: u16) -> u8 {
+        self.memory.peek(addr)
+    }
+}
+
+fn run(cpu: &mut CPU<RecordingBus>, test: &Test) -> Result<(), String> {
+    let initial = &test.initial;
+    cpu.program_counter = initial.pc;
+    cpu.stack_counter = initial.s;
+    cpu.register_a = initial.a;
+    cpu.register_x = initial.x;
+    cpu.register_y = initial.y;
+    cpu.status = initial.p;
+    for &(addr, data) in &initial.ram {
+        cpu.bus.memory.mem_write(addr, data);
+    }
+    cpu.bus.accesses.clear();
+
+    cpu.step();
+
+    let expected = &test.expected;
+    let registers = |pc, s, a, x, y, p| format!("PC:{pc:04X} S:{s:02X} A:{a:02X} X:{x:02X} Y:{y:02X} P:{p:02X}");
+    let got = registers(
+        cpu.program_counter,
+        cpu.stack_counter,
+        cpu.register_a,
+        cpu.register_x,
+        cpu.register_y,
+        cpu.status,
+    );
+    let want = registers(expected.pc, expected.s, expected.a, expected.x, expected.y, expected.p);
+    if got != want {
+        return Err(format!("registers are {got}, expected {want}"));
+    }
+
+    for &(addr, data) in &expected.ram {
+        let got = cpu.bus.memory.mem_read(addr);
+        if got != data {
+            return Err(format!("${addr:04X} is {got:02X}, expected {data:02X}"));
+        }
+    }
+
+    if cpu.bus.accesses != test.cycles {
+        return Err(format!(
+            "bus accesses were {:?}, expected {:?}",
+            cpu.bus.accesses, test.cycles
+        ));
+    }
+
+    Ok(())
+}
+
+#[test]
+#[ignore = "needs the vectors of tests/single_step/vendor.py or SINGLE_STEP_TESTS"]
+fn test_single_step_vectors() {
+    let dir = std::env::var("SINGLE_STEP_TESTS")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/single_step"));
+
+    let mut cpu = CPU::new(RecordingBus {
+        memory: FlatMemory::new(),
+        accesses: vec![],
+    });
+
+    let mut failures = vec![];
+    for (opcode, op) in OPS_CODES.iter().enumerate() {
+        // a jammed CPU stops fetching, there is nothing left to compare
+        if op.name == "*KIL" {
+            continue;
+        }
+
+        let path = dir.join(format!("{opcode:02x}.json"));
+        let Ok(json) = fs::read_to_string(&path) else {
+            failures.push(format!("${opcode:02X} {}: no vectors in {}", op.name, path.display()));
+            continue;
+        };
+        let tests: Vec<Test> = serde_json::from_str(&json).unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+
+        let mut failed = 0;
+        let mut first = None;
+        for test in &tests {
+            if let Err(e) = run(&mut cpu, test) {
+                failed += 1;
+                first.get_or_insert_with(|| format!("\"{}\": {e}", test.name));
+            }
+        }
+
+        if let Some(first) = first {
+            failures.push(format!(
+                "${opcode:02X} {} {:?}: {failed} of {} failed, first {first}",
+                op.name,
+                op.mode,
+                tests.len()
+            ));
+        }
+    }
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
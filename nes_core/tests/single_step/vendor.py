@@ -0,0 +1,35 @@
+#!/usr/bin/env python3
+# Copies the first cases of every opcode of the nes6502 set of https://github.com/SingleStepTests/65x02 into
+# this directory, the vectors `tests/single_step.rs` runs offline
+#
+#   python3 nes_core/tests/single_step/vendor.py [cases per opcode] [path/to/nes6502/v1]
+#
+# Without a path the files are downloaded from GitHub
+
+import json
+import sys
+import urllib.request
+from pathlib import Path
+
+URL = "https://raw.githubusercontent.com/SingleStepTests/65x02/main/nes6502/v1/{:02x}.json"
+# a jammed CPU stops fetching, the test skips them
+KIL = {0x02, 0x12, 0x22, 0x32, 0x42, 0x52, 0x62, 0x72, 0x92, 0xB2, 0xD2, 0xF2}
+
+cases = int(sys.argv[1]) if len(sys.argv) > 1 else 20
+source = Path(sys.argv[2]) if len(sys.argv) > 2 else None
+out = Path(__file__).parent
+
+for opcode in range(256):
+    if opcode in KIL:
+        continue
+
+    if source:
+        data = (source / f"{opcode:02x}.json").read_text()
+    else:
+        with urllib.request.urlopen(URL.format(opcode)) as response:
+            data = response.read().decode()
+
+    tests = json.loads(data)[:cases]
+    lines = ",\n".join(json.dumps(test, separators=(", ", ": ")) for test in tests)
+    (out / f"{opcode:02x}.json").write_text(f"[\n{lines}\n]\n")
+    print(f"{opcode:02x}.json: {len(tests)} cases")
@@ -1,70 +0,0 @@
-use super::{cpu::CPU, memory::Mem};
-
-#[derive(Debug)]
-#[allow(non_camel_case_types)]
-pub enum AddressingMode {
-    Accumulator,
-    Immediate,
-    ZeroPage,
-    ZeroPage_X,
-    ZeroPage_Y,
-    Absolute,
-    Absolute_X,
-    Absolute_Y,
-    Indirect,
-    Indirect_X,
-    Indirect_Y,
-    NoneAddressing,
-}
-
-fn is_in_another_page(addr1: u16, addr2: u16) -> bool {
-    addr1 & 0xFF00 != addr2 & 0xFF00
-}
-
-impl<'a> CPU<'a> {
-    pub fn get_operand_address(&mut self, mode: &AddressingMode) -> u16 {
-        match mode {
-            AddressingMode::Immediate => self.program_counter,
-            AddressingMode::ZeroPage => self.mem_read(self.program_counter) as u16,
-            AddressingMode::ZeroPage_X => self.mem_read(self.program_counter).wrapping_add(self.register_x) as u16,
-            AddressingMode::ZeroPage_Y => self.mem_read(self.program_counter).wrapping_add(self.register_y) as u16,
-            AddressingMode::Absolute => self.mem_read_u16(self.program_counter),
-            AddressingMode::Absolute_X => {
-                let base = self.mem_read_u16(self.program_counter);
-                let addr = base.wrapping_add(self.register_x as u16);
-
-                is_in_another_page(base, addr).then(|| self.bus.tick(1));
-
-                addr
-            }
-            AddressingMode::Absolute_Y => {
-                let base = self.mem_read_u16(self.program_counter);
-                let addr = base.wrapping_add(self.register_y as u16);
-
-                is_in_another_page(base, addr).then(|| self.bus.tick(1));
-
-                addr
-            }
-            AddressingMode::Indirect_X => {
-                let ptr: u8 = self.mem_read(self.program_counter).wrapping_add(self.register_x);
-                let lo = self.mem_read(ptr as u16);
-                let hi = self.mem_read(ptr.wrapping_add(1) as u16);
-
-                (hi as u16) << 8 | lo as u16
-            }
-            AddressingMode::Indirect_Y => {
-                let base = self.mem_read(self.program_counter);
-
-                let lo = self.mem_read(base as u16);
-                let hi = self.mem_read((base as u8).wrapping_add(1) as u16);
-                let deref_base = (hi as u16) << 8 | (lo as u16);
-                let deref = deref_base.wrapping_add(self.register_y as u16);
-
-                is_in_another_page(deref_base, deref).then(|| self.bus.tick(1));
-
-                deref
-            }
-            _ => panic!("mode {:?} is not supported", mode),
-        }
-    }
-}
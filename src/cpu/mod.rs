@@ -1,9 +0,0 @@
-mod cpu;
-mod addrssing_modes;
-mod operations;
-mod opscodes;
-mod flags;
-mod memory;
-
-pub use cpu::CPU;
-pub use memory::Mem;
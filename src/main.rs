@@ -1,47 +1,243 @@
-mod bus;
-mod controller;
-mod cpu;
-mod ppu;
-mod render;
-mod rom;
-
-use std::collections::HashMap;
-
-use bus::Bus;
-use clap::Parser;
-use cpu::CPU;
-use ppu::NesPPU;
-use render::Frame;
-use rom::Rom;
-use sdl2::{event::Event, keyboard::Keycode, pixels::PixelFormatEnum};
+use std::{
+    collections::HashMap,
+    io::{BufRead, Write},
+    path::Path,
+};
+
+use clap::{Parser, Subcommand};
+use nes_core::{
+    CrashReporter, Debugger, FaultPolicy, HeadlessConfig, ImageFormat, InputScript, Joypad, Nes, Options, Rom,
+};
+use sdl2::{audio::AudioSpecDesired, event::Event, keyboard::Keycode, pixels::PixelFormatEnum};
+
+// bytes of audio the queue can hold before new samples are dropped to keep the latency low
+const MAX_QUEUED_AUDIO: u32 = nes_core::SAMPLE_RATE / 10 * 4;
+// battery backed RAM is written to disk roughly every 30 seconds when it changed
+const AUTOSAVE_FRAMES: usize = 30 * 60;
+#[rustfmt::skip]
+const SLOT_KEYS: [Keycode; 10] = [
+    Keycode::Num0, Keycode::Num1, Keycode::Num2, Keycode::Num3, Keycode::Num4,
+    Keycode::Num5, Keycode::Num6, Keycode::Num7, Keycode::Num8, Keycode::Num9,
+];
 
 /// Nes Emulator in Rust
 #[derive(Parser, Debug)]
 #[command(author = "Loukis-13", version, about, long_about)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Path to .nes file
-    file: std::path::PathBuf,
+    #[arg(required = true)]
+    file: Option<std::path::PathBuf>,
+
+    /// Run without a window or audio, writing the frames picked with --dump to disk
+    #[arg(long)]
+    headless: bool,
+
+    /// Number of frames to run in headless mode
+    #[arg(long, default_value_t = 600, requires = "headless")]
+    frames: usize,
+
+    /// Input script for headless mode, lines of `<frame> <BUTTON>[+<BUTTON>...]`
+    #[arg(long, requires = "headless")]
+    input: Option<std::path::PathBuf>,
+
+    /// Frames to write in headless mode, counted from 0 [default: the last frame]
+    #[arg(long, value_delimiter = ',', requires = "headless")]
+    dump: Vec<usize>,
+
+    /// Directory the headless frames are written to
+    #[arg(long, default_value = ".", requires = "headless")]
+    output: std::path::PathBuf,
+
+    /// Image format of the headless frames, png or ppm
+    #[arg(long, default_value = "png", requires = "headless")]
+    format: ImageFormat,
+
+    /// Write a nestest.log style line for every executed instruction to this file
+    #[arg(long)]
+    trace: Option<std::path::PathBuf>,
+
+    /// Log which bytes of the ROM run as code, are read as data or are drawn to this FCEUX .cdl file,
+    /// merging with it when it exists
+    #[arg(long)]
+    cdl: Option<std::path::PathBuf>,
+
+    /// Count the CPU cycles spent per routine and per address, the tables are written to this file at exit
+    #[arg(long)]
+    profile: Option<std::path::PathBuf>,
+
+    /// Also write the profile as collapsed stacks, the input of flamegraph tools
+    #[arg(long, requires = "profile")]
+    flamegraph: Option<std::path::PathBuf>,
+
+    /// Label file naming the routines in the profile, from `ld65 -Ln` or an FCEUX .nl file
+    #[arg(long, requires = "profile")]
+    labels: Option<std::path::PathBuf>,
+
+    /// What to do on each fault: <FAULT>=<ACTION>[,...] with the faults jam, rom-write, ppu-status-write,
+    /// stack-wrap or all and the actions ignore, log, break (in the debugger) or halt
+    #[arg(long, default_value = "")]
+    faults: FaultPolicy,
+
+    /// Draw every sprite of a line instead of the first 8, removing the flicker some games use to show more
+    #[arg(long)]
+    no_sprite_limit: bool,
+
+    /// Start paused in the debugger monitor, F12 breaks into it while the game runs
+    #[arg(short, long, conflicts_with = "headless")]
+    debug: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Write the PRG ROM of a .nes file as ca65 assembly source
+    Disasm {
+        /// Path to .nes file
+        file: std::path::PathBuf,
+
+        /// Write the source to this file instead of the standard output
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+    },
+}
 
-    // /// Enable debuging
-    // #[arg(short, long)]
-    // debug: bool,
+fn write_battery_ram(path: &Path, ram: &[u8]) {
+    if let Err(err) = std::fs::write(path, ram) {
+        eprintln!("Could not write {}: {err}", path.display());
+    }
+}
+
+// Reads monitor commands until the debugger resumes the game, returns false when the user quits
+fn run_monitor(debugger: &mut Debugger, nes: &mut Nes) -> bool {
+    println!("{}", debugger.location(nes));
+
+    let mut stdin = std::io::stdin().lock();
+    while debugger.is_paused() {
+        print!("(debug) ");
+        std::io::stdout().flush().unwrap();
+
+        let mut line = String::new();
+        if stdin.read_line(&mut line).unwrap_or(0) == 0 {
+            return false;
+        }
+
+        let output = debugger.execute(nes, &line);
+        if !output.is_empty() {
+            println!("{output}");
+        }
+        if debugger.quit_requested() {
+            return false;
+        }
+        if debugger.is_paused() {
+            println!("{}", debugger.location(nes));
+        }
+    }
+    true
+}
+
+fn options(args: &Args) -> Options {
+    Options {
+        trace: args.trace.clone(),
+        cdl: args.cdl.clone(),
+        profile: args.profile.clone(),
+        flamegraph: args.flamegraph.clone(),
+        labels: args.labels.clone(),
+        faults: args.faults,
+        sprite_limit: !args.no_sprite_limit,
+    }
+}
+
+fn run_headless(rom: Rom, args: &Args, game_name: &str) -> ! {
+    let input = match &args.input {
+        Some(path) => std::fs::read_to_string(path)
+            .map_err(|err| format!("Could not read {}: {err}", path.display()))
+            .and_then(|script| InputScript::parse(&script)),
+        None => Ok(InputScript::new()),
+    };
+
+    let result = input.and_then(|input| {
+        let config = HeadlessConfig {
+            frames: args.frames,
+            input,
+            dump_frames: if args.dump.is_empty() {
+                vec![args.frames.saturating_sub(1)]
+            } else {
+                args.dump.clone()
+            },
+            output_dir: args.output.clone(),
+            name: game_name.to_string(),
+            format: args.format,
+            options: options(args),
+        };
+        nes_core::run_headless(rom, &config)
+    });
+
+    match result {
+        Ok(written) => {
+            for path in written {
+                println!("{}", path.display());
+            }
+            std::process::exit(0);
+        }
+        Err(err) => {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn load_rom(path: &Path) -> Rom {
+    let game_code = std::fs::read(path).expect("Expected ines format file");
+    Rom::new(&game_code).unwrap_or_else(|err| {
+        eprintln!("Could not load {}: {err}", path.display());
+        std::process::exit(1);
+    })
+}
+
+fn run_disasm(file: &Path, output: Option<&Path>) -> ! {
+    let source = nes_core::disassemble_prg(&load_rom(file)).unwrap_or_else(|err| {
+        eprintln!("{err}");
+        std::process::exit(1);
+    });
+    match output {
+        Some(path) => {
+            if let Err(err) = std::fs::write(path, source) {
+                eprintln!("Could not write {}: {err}", path.display());
+                std::process::exit(1);
+            }
+        }
+        None => print!("{source}"),
+    }
+    std::process::exit(0);
 }
 
 fn main() {
     let args = Args::parse();
 
+    if let Some(Command::Disasm { file, output }) = &args.command {
+        run_disasm(file, output.as_deref());
+    }
+
     //load the game
-    let game_code = std::fs::read(&args.file).expect("Expected ines format file");
-    let rom = Rom::new(&game_code).unwrap();
+    let file = args.file.as_ref().expect("clap requires the file");
+    let rom = load_rom(file);
 
-    let game_name = args
-        .file
+    let game_name = file
         .file_name()
         .expect("Expected file")
         .to_str()
         .expect("Expected valid file name")
         .trim_end_matches(".nes");
 
+    if args.headless {
+        run_headless(rom, &args, game_name);
+    }
+
+    let battery_path = rom.battery.then(|| file.with_extension("sav"));
+
     // init sdl2
     let sdl_context = sdl2::init().unwrap();
     let video_subsystem = sdl_context.video().unwrap();
@@ -55,58 +251,158 @@ fn main() {
     let mut event_pump = sdl_context.event_pump().unwrap();
     canvas.set_scale(3.0, 3.0).unwrap();
 
+    let audio_subsystem = sdl_context.audio().unwrap();
+    let audio_queue = audio_subsystem
+        .open_queue::<f32, _>(
+            None,
+            &AudioSpecDesired {
+                freq: Some(nes_core::SAMPLE_RATE as i32),
+                channels: Some(1),
+                samples: None,
+            },
+        )
+        .unwrap();
+    audio_queue.resume();
+
     let creator = canvas.texture_creator();
     let mut texture = creator.create_texture_target(PixelFormatEnum::RGB24, 256, 240).unwrap();
 
     // config controller
     let mut key_map = HashMap::new();
-    key_map.insert(Keycode::Down, controller::Joypad::DOWN);
-    key_map.insert(Keycode::Up, controller::Joypad::UP);
-    key_map.insert(Keycode::Right, controller::Joypad::RIGHT);
-    key_map.insert(Keycode::Left, controller::Joypad::LEFT);
-    key_map.insert(Keycode::Space, controller::Joypad::SELECT);
-    key_map.insert(Keycode::Return, controller::Joypad::START);
-    key_map.insert(Keycode::A, controller::Joypad::A);
-    key_map.insert(Keycode::S, controller::Joypad::B);
-
-    
+    key_map.insert(Keycode::Down, Joypad::DOWN);
+    key_map.insert(Keycode::Up, Joypad::UP);
+    key_map.insert(Keycode::Right, Joypad::RIGHT);
+    key_map.insert(Keycode::Left, Joypad::LEFT);
+    key_map.insert(Keycode::Space, Joypad::SELECT);
+    key_map.insert(Keycode::Return, Joypad::START);
+    key_map.insert(Keycode::A, Joypad::A);
+    key_map.insert(Keycode::S, Joypad::B);
 
-    let mut frame = Frame::new();
+    let mut nes = Nes::from_rom(rom)
+        .and_then(|mut nes| nes.configure(&options(&args)).map(|_| nes))
+        .unwrap_or_else(|err| {
+            eprintln!("{err}");
+            std::process::exit(1);
+        });
 
-    // the game cycle
-    let bus = Bus::new(rom, move |ppu: &NesPPU, joypad: &mut controller::Joypad| {
-        render::render(ppu, &mut frame);
-        texture.update(None, &frame.data, 256 * 3).unwrap();
+    let mut saved_ram = Vec::new();
+    if let Some(path) = &battery_path {
+        if let Ok(data) = std::fs::read(path) {
+            nes.load_battery_ram(&data);
+        }
+        saved_ram = nes.battery_ram().to_vec();
+    }
 
-        canvas.copy(&texture, None, None).unwrap();
+    let state_path = |slot: u8| file.with_extension(format!("ss{slot}"));
+    let mut slot = 1;
+    let mut buttons = 0;
+    let mut debugger = args.debug.then(Debugger::new);
+    let mut crashes = CrashReporter::new(file.with_extension("crash.txt"));
 
-        canvas.present();
+    // the game cycle
+    'running: loop {
         for event in event_pump.poll_iter() {
             match event {
                 Event::Quit { .. }
                 | Event::KeyDown {
                     keycode: Some(Keycode::Escape),
                     ..
-                } => std::process::exit(0),
+                } => break 'running,
+
+                // save states, FCEUX style: 0-9 selects the slot, F5 saves and F7 loads
+                Event::KeyDown {
+                    keycode: Some(Keycode::F5),
+                    ..
+                } => match std::fs::write(state_path(slot), nes.save_state()) {
+                    Ok(()) => println!("Saved state to slot {slot}"),
+                    Err(err) => eprintln!("Could not save state to slot {slot}: {err}"),
+                },
+                Event::KeyDown {
+                    keycode: Some(Keycode::F7),
+                    ..
+                } => {
+                    let result = std::fs::read(state_path(slot))
+                        .map_err(|err| err.to_string())
+                        .and_then(|data| nes.load_state(&data));
+                    match result {
+                        Ok(()) => println!("Loaded state from slot {slot}"),
+                        Err(err) => eprintln!("Could not load state from slot {slot}: {err}"),
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::F12),
+                    ..
+                } => {
+                    if let Some(debugger) = &mut debugger {
+                        debugger.pause();
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(keycode), ..
+                } if SLOT_KEYS.contains(&keycode) => {
+                    slot = SLOT_KEYS.iter().position(|key| *key == keycode).unwrap() as u8;
+                    println!("Save state slot {slot}");
+                }
 
                 Event::KeyDown { keycode, .. } => {
                     if let Some(key) = key_map.get(&keycode.unwrap_or(Keycode::Ampersand)) {
-                        joypad.set_button_pressed_status(*key, true);
+                        buttons |= *key;
                     }
                 }
                 Event::KeyUp { keycode, .. } => {
                     if let Some(key) = key_map.get(&keycode.unwrap_or(Keycode::Ampersand)) {
-                        joypad.set_button_pressed_status(*key, false);
+                        buttons &= !*key;
                     }
                 }
 
                 _ => { /* do nothing */ }
             }
         }
-    });
 
-    let mut cpu = CPU::new(bus);
+        nes.set_buttons(buttons);
+        let result = match &mut debugger {
+            Some(debugger) => {
+                if debugger.is_paused() && !run_monitor(debugger, &mut nes) {
+                    break;
+                }
+                // a halt pauses the debugger, the session goes on
+                crashes.run(&mut nes, |nes| {
+                    match debugger.run_frame(nes) {
+                        Some(reason) if !reason.is_empty() => println!("{reason}"),
+                        _ => {}
+                    }
+                    true
+                })
+            }
+            None => crashes.run(&mut nes, |nes| nes.step_frame()),
+        };
+        if let Err(err) = result {
+            eprintln!("{err}");
+            break;
+        }
+
+        texture.update(None, &nes.frame().data, 256 * 3).unwrap();
+        canvas.copy(&texture, None, None).unwrap();
+        canvas.present();
+
+        let samples = nes.take_audio_samples();
+        if audio_queue.size() < MAX_QUEUED_AUDIO {
+            audio_queue.queue_audio(&samples).unwrap();
+        }
+
+        if let Some(path) = &battery_path {
+            if nes.frame_count().is_multiple_of(AUTOSAVE_FRAMES) && nes.battery_ram() != saved_ram.as_slice() {
+                write_battery_ram(path, nes.battery_ram());
+                saved_ram = nes.battery_ram().to_vec();
+            }
+        }
+    }
+
+    if let Some(path) = &battery_path {
+        write_battery_ram(path, nes.battery_ram());
+    }
 
-    cpu.reset();
-    cpu.run();
+    if let Err(err) = nes.finish() {
+        eprintln!("{err}");
+    }
 }
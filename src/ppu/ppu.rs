@@ -1,413 +0,0 @@
-use crate::rom::Mirroring;
-
-use super::{
-    addr_register::AddrRegister, control_register::ControlRegister, mask_register::MaskRegister,
-    scroll_register::ScrollRegister, status_register::StatusRegister,
-};
-
-pub struct NesPPU {
-    // PPU Registers
-    pub ctrl: ControlRegister,  // 0x2000
-    pub mask: MaskRegister,     // 0x2001
-    pub status: StatusRegister, // 0x2002
-    pub oam_addr: u8,           // 0x2003
-    pub oam_data: [u8; 256],    // 0x2004
-    pub scroll: ScrollRegister, // 0x2005
-    pub addr: AddrRegister,     // 0x2006
-
-    // PPU Memory Map
-    pub mirroring: Mirroring,    // 0x4000 - 0xFFFF
-    pub palette_table: [u8; 32], // 0x3F00 - 0x3FFF
-    pub vram: [u8; 2048],        // 0x2000 - 0x3EFF
-    pub chr_rom: Vec<u8>,        // 0x0000 - 0x1FFF
-
-    internal_data_buf: u8,
-    scanline: u16,
-    cycles: usize,
-
-    pub nmi_interrupt: Option<u8>,
-}
-
-pub trait PPU {
-    fn write_to_ctrl(&mut self, value: u8);
-    fn write_to_mask(&mut self, value: u8);
-    fn read_status(&mut self) -> u8;
-    fn write_to_oam_addr(&mut self, value: u8);
-    fn write_to_oam_data(&mut self, value: u8);
-    fn read_oam_data(&self) -> u8;
-    fn write_to_scroll(&mut self, value: u8);
-    fn write_to_addr(&mut self, value: u8);
-    fn write_to_data(&mut self, value: u8);
-    fn read_data(&mut self) -> u8;
-    fn write_oam_dma(&mut self, value: &[u8; 256]);
-}
-
-impl NesPPU {
-    pub fn new(chr_rom: Vec<u8>, mirroring: Mirroring) -> Self {
-        NesPPU {
-            ctrl: ControlRegister::new(),
-            chr_rom,
-            mirroring,
-            vram: [0; 2048],
-            oam_addr: 0,
-            oam_data: [0; 64 * 4],
-            palette_table: [0; 32],
-            addr: AddrRegister::new(),
-            status: StatusRegister::new(),
-            mask: MaskRegister::new(),
-            scroll: ScrollRegister::new(),
-            internal_data_buf: 0,
-            scanline: 0,
-            cycles: 0,
-            nmi_interrupt: None,
-        }
-    }
-
-    pub fn tick(&mut self, cycles: u8) -> bool {
-        self.cycles += cycles as usize;
-        if self.cycles >= 341 {
-            if self.is_sprite_0_hit(self.cycles) {
-                self.status.set_sprite_zero_hit(true);
-            }
-            
-            self.cycles -= 341;
-            self.scanline += 1;
-
-            if self.scanline == 241 {
-                self.status.set_vblank_status(true);
-                self.status.set_sprite_zero_hit(false);
-                if self.ctrl.generate_vblank_nmi() {
-                    self.nmi_interrupt = Some(1);
-                }
-            }
-
-            if self.scanline >= 262 {
-                self.scanline = 0;
-                self.nmi_interrupt = None;
-                self.status.set_sprite_zero_hit(false);
-                self.status.set_vblank_status(false);
-                return true;
-            }
-        }
-        return false;
-    }
-
-    fn is_sprite_0_hit(&self, cycle: usize) -> bool {
-        let y = self.oam_data[0] as usize;
-        let x = self.oam_data[3] as usize;
-        (y == self.scanline as usize) && x <= cycle && self.mask.show_sprites()
-    }
-
-    fn increment_vram_addr(&mut self) {
-        self.addr.increment(self.ctrl.vram_addr_increment());
-    }
-
-    // Horizontal:
-    //   [ A ] [ a ]
-    //   [ B ] [ b ]
-
-    // Vertical:
-    //   [ A ] [ B ]
-    //   [ a ] [ b ]
-    pub fn mirror_vram_addr(&self, addr: u16) -> u16 {
-        let mirrored_vram = addr & 0b10111111111111; // mirror down 0x3000-0x3eff to 0x2000 - 0x2eff
-        let vram_index = mirrored_vram - 0x2000; // to vram vector
-        let name_table = vram_index / 0x400; // to the name table index
-        match (&self.mirroring, name_table) {
-            (Mirroring::VERTICAL, 2) | (Mirroring::VERTICAL, 3) => vram_index - 0x800,
-            (Mirroring::HORIZONTAL, 2) => vram_index - 0x400,
-            (Mirroring::HORIZONTAL, 1) => vram_index - 0x400,
-            (Mirroring::HORIZONTAL, 3) => vram_index - 0x800,
-            _ => vram_index,
-        }
-    }
-}
-
-impl PPU for NesPPU {
-    fn write_to_ctrl(&mut self, value: u8) {
-        let before_nmi_status = self.ctrl.generate_vblank_nmi();
-        self.ctrl.update(value);
-        if !before_nmi_status && self.ctrl.generate_vblank_nmi() && self.status.is_in_vblank() {
-            self.nmi_interrupt = Some(1);
-        }
-    }
-
-    fn write_to_mask(&mut self, value: u8) {
-        self.mask.update(value);
-    }
-
-    fn read_status(&mut self) -> u8 {
-        let bits = self.status.get_bits();
-        self.status.set_vblank_status(false);
-        self.scroll.reset_latch();
-        self.addr.reset_latch();
-        bits
-    }
-
-    fn write_to_oam_addr(&mut self, value: u8) {
-        self.oam_addr = value;
-    }
-
-    fn write_to_oam_data(&mut self, value: u8) {
-        self.oam_data[self.oam_addr as usize] = value;
-        self.oam_addr = self.oam_addr.wrapping_add(1);
-    }
-
-    fn read_oam_data(&self) -> u8 {
-        self.oam_data[self.oam_addr as usize]
-    }
-
-    fn write_to_scroll(&mut self, value: u8) {
-        self.scroll.write(value);
-    }
-
-    fn write_to_addr(&mut self, value: u8) {
-        self.addr.update(value);
-    }
-
-    fn write_to_data(&mut self, data: u8) {
-        let addr = self.addr.get();
-
-        match addr {
-            0..=0x1fff => println!("attempt to write to chr rom space {}", addr),
-            0x2000..=0x2fff => self.vram[self.mirror_vram_addr(addr) as usize] = data,
-            0x3000..=0x3eff => panic!("addr space 0x3000..0x3eff is not expected to be used, requested = {addr:X}"),
-            //Addresses $3F10/$3F14/$3F18/$3F1C are mirrors of $3F00/$3F04/$3F08/$3F0C
-            0x3f10 | 0x3f14 | 0x3f18 | 0x3f1c => self.palette_table[(addr - 0x10 - 0x3f00) as usize] = data,
-            0x3f00..=0x3fff => self.palette_table[(addr - 0x3f00) as usize] = data,
-            _ => panic!("unexpected access to mirrored space {}", addr),
-        };
-        self.increment_vram_addr();
-    }
-
-    fn read_data(&mut self) -> u8 {
-        let addr = self.addr.get();
-        self.increment_vram_addr();
-
-        match addr {
-            0..=0x1fff => {
-                let result = self.internal_data_buf;
-                self.internal_data_buf = self.chr_rom[addr as usize];
-                result
-            }
-            0x2000..=0x2fff => {
-                let result = self.internal_data_buf;
-                self.internal_data_buf = self.vram[self.mirror_vram_addr(addr) as usize];
-                result
-            }
-            0x3000..=0x3eff => panic!("addr space 0x3000..0x3eff is not expected to be used, requested = {addr}"),
-            0x3f00..=0x3fff => self.palette_table[(addr - 0x3f00) as usize],
-            _ => panic!("unexpected access to mirrored space {}", addr),
-        }
-    }
-
-    fn write_oam_dma(&mut self, value: &[u8; 256]) {
-        for i in value {
-            self.write_to_oam_data(*i);
-        }
-    }
-}
-
-#[cfg(test)]
-pub mod test {
-    use super::*;
-
-    fn new_empty_rom() -> NesPPU {
-        NesPPU::new(vec![0; 2048], Mirroring::HORIZONTAL)
-    }
-
-    #[test]
-    fn test_ppu_vram_writes() {
-        let mut ppu = new_empty_rom();
-        ppu.write_to_addr(0x23);
-        ppu.write_to_addr(0x05);
-        ppu.write_to_data(0x66);
-
-        assert_eq!(ppu.vram[0x0305], 0x66);
-    }
-
-    #[test]
-    fn test_ppu_vram_reads() {
-        let mut ppu = new_empty_rom();
-        ppu.write_to_ctrl(0);
-        ppu.vram[0x0305] = 0x66;
-
-        ppu.write_to_addr(0x23);
-        ppu.write_to_addr(0x05);
-
-        ppu.read_data(); //load_into_buffer
-        assert_eq!(ppu.addr.get(), 0x2306);
-        assert_eq!(ppu.read_data(), 0x66);
-    }
-
-    #[test]
-    fn test_ppu_vram_reads_cross_page() {
-        let mut ppu = new_empty_rom();
-        ppu.write_to_ctrl(0);
-        ppu.vram[0x01ff] = 0x66;
-        ppu.vram[0x0200] = 0x77;
-
-        ppu.write_to_addr(0x21);
-        ppu.write_to_addr(0xff);
-
-        ppu.read_data(); //load_into_buffer
-        assert_eq!(ppu.read_data(), 0x66);
-        assert_eq!(ppu.read_data(), 0x77);
-    }
-
-    #[test]
-    fn test_ppu_vram_reads_step_32() {
-        let mut ppu = new_empty_rom();
-        ppu.write_to_ctrl(0b100);
-        ppu.vram[0x01ff] = 0x66;
-        ppu.vram[0x01ff + 32] = 0x77;
-        ppu.vram[0x01ff + 64] = 0x88;
-
-        ppu.write_to_addr(0x21);
-        ppu.write_to_addr(0xff);
-
-        ppu.read_data(); //load_into_buffer
-        assert_eq!(ppu.read_data(), 0x66);
-        assert_eq!(ppu.read_data(), 0x77);
-        assert_eq!(ppu.read_data(), 0x88);
-    }
-
-    // Horizontal: https://wiki.nesdev.com/w/index.php/Mirroring
-    //   [0x2000 A ] [0x2400 a ]
-    //   [0x2800 B ] [0x2C00 b ]
-    #[test]
-    fn test_vram_horizontal_mirror() {
-        let mut ppu = new_empty_rom();
-        ppu.write_to_addr(0x24);
-        ppu.write_to_addr(0x05);
-
-        ppu.write_to_data(0x66); //write to a
-
-        ppu.write_to_addr(0x28);
-        ppu.write_to_addr(0x05);
-
-        ppu.write_to_data(0x77); //write to B
-
-        ppu.write_to_addr(0x20);
-        ppu.write_to_addr(0x05);
-
-        ppu.read_data(); //load into buffer
-        assert_eq!(ppu.read_data(), 0x66); //read from A
-
-        ppu.write_to_addr(0x2C);
-        ppu.write_to_addr(0x05);
-
-        ppu.read_data(); //load into buffer
-        assert_eq!(ppu.read_data(), 0x77); //read from b
-    }
-
-    // Vertical: https://wiki.nesdev.com/w/index.php/Mirroring
-    //   [0x2000 A ] [0x2400 B ]
-    //   [0x2800 a ] [0x2C00 b ]
-    #[test]
-    fn test_vram_vertical_mirror() {
-        let mut ppu = NesPPU::new(vec![0; 2048], Mirroring::VERTICAL);
-
-        ppu.write_to_addr(0x20);
-        ppu.write_to_addr(0x05);
-
-        ppu.write_to_data(0x66); //write to A
-
-        ppu.write_to_addr(0x2C);
-        ppu.write_to_addr(0x05);
-
-        ppu.write_to_data(0x77); //write to b
-
-        ppu.write_to_addr(0x28);
-        ppu.write_to_addr(0x05);
-
-        ppu.read_data(); //load into buffer
-        assert_eq!(ppu.read_data(), 0x66); //read from a
-
-        ppu.write_to_addr(0x24);
-        ppu.write_to_addr(0x05);
-
-        ppu.read_data(); //load into buffer
-        assert_eq!(ppu.read_data(), 0x77); //read from B
-    }
-
-    #[test]
-    fn test_read_status_resets_latch() {
-        let mut ppu = new_empty_rom();
-        ppu.vram[0x0305] = 0x66;
-
-        ppu.write_to_addr(0x21);
-        ppu.write_to_addr(0x23);
-        ppu.write_to_addr(0x05);
-
-        ppu.read_data(); //load_into_buffer
-        assert_ne!(ppu.read_data(), 0x66);
-
-        ppu.read_status();
-
-        ppu.write_to_addr(0x23);
-        ppu.write_to_addr(0x05);
-
-        ppu.read_data(); //load_into_buffer
-        assert_eq!(ppu.read_data(), 0x66);
-    }
-
-    #[test]
-    fn test_ppu_vram_mirroring() {
-        let mut ppu = new_empty_rom();
-        ppu.write_to_ctrl(0);
-        ppu.vram[0x0305] = 0x66;
-
-        ppu.write_to_addr(0x63); //0x6305 -> 0x2305
-        ppu.write_to_addr(0x05);
-
-        ppu.read_data(); //load into_buffer
-        assert_eq!(ppu.read_data(), 0x66);
-        // assert_eq!(ppu.addr.read(), 0x0306)
-    }
-
-    #[test]
-    fn test_read_status_resets_vblank() {
-        let mut ppu = new_empty_rom();
-        ppu.status.set_vblank_status(true);
-
-        let status = ppu.read_status();
-
-        assert_eq!(status >> 7, 1);
-        assert_eq!(ppu.status.get_bits() >> 7, 0);
-    }
-
-    #[test]
-    fn test_oam_read_write() {
-        let mut ppu = new_empty_rom();
-        ppu.write_to_oam_addr(0x10);
-        ppu.write_to_oam_data(0x66);
-        ppu.write_to_oam_data(0x77);
-
-        ppu.write_to_oam_addr(0x10);
-        assert_eq!(ppu.read_oam_data(), 0x66);
-
-        ppu.write_to_oam_addr(0x11);
-        assert_eq!(ppu.read_oam_data(), 0x77);
-    }
-
-    #[test]
-    fn test_oam_dma() {
-        let mut ppu = new_empty_rom();
-
-        let mut data = [0x66; 256];
-        data[0] = 0x77;
-        data[255] = 0x88;
-
-        ppu.write_to_oam_addr(0x10);
-        ppu.write_oam_dma(&data);
-
-        ppu.write_to_oam_addr(0xF); //wrap around
-        assert_eq!(ppu.read_oam_data(), 0x88);
-
-        ppu.write_to_oam_addr(0x10);
-        ppu.write_to_oam_addr(0x77);
-        ppu.write_to_oam_addr(0x11);
-        ppu.write_to_oam_addr(0x66);
-    }
-}
@@ -1,7 +0,0 @@
-mod palette;
-mod frame;
-mod render;
-mod rect;
-
-pub use render::render;
-pub use frame::Frame;
\ No newline at end of file
@@ -1,3 +0,0 @@
-mod rom;
-
-pub use rom::*;
\ No newline at end of file